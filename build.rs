@@ -0,0 +1,26 @@
+use std::process::Command;
+
+/// Embeds the commit Root was built from for `/version`. Builds without a git checkout, like the
+/// Docker image, can pass it in through `ROOT_GIT_COMMIT` instead.
+fn main() {
+    println!("cargo:rerun-if-env-changed=ROOT_GIT_COMMIT");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+
+    let commit = std::env::var("ROOT_GIT_COMMIT")
+        .ok()
+        .filter(|commit| !commit.is_empty())
+        .or_else(|| {
+            let output = Command::new("git")
+                .args(["rev-parse", "--short", "HEAD"])
+                .output()
+                .ok()?;
+            output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+
+    println!("cargo:rustc-env=ROOT_GIT_COMMIT={}", commit);
+}
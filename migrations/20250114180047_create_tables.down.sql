@@ -0,0 +1,6 @@
+DROP TABLE StatusUpdateStreak;
+DROP TABLE AttendanceSummary;
+DROP TABLE Attendance;
+DROP FUNCTION update_timestamp;
+DROP TABLE Member;
+DROP TYPE sex_type;
@@ -0,0 +1,5 @@
+-- Members keep their group_id, it just stops referring to a MemberGroup.
+ALTER TABLE Member DROP CONSTRAINT fkey_group;
+
+DROP TABLE GroupMentor;
+DROP TABLE MemberGroup;
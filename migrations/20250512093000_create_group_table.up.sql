@@ -0,0 +1,27 @@
+-- Groups were a bare integer on Member. `GROUP` is a reserved word, hence `MemberGroup`.
+CREATE TABLE MemberGroup (
+        group_id SERIAL PRIMARY KEY,
+        name VARCHAR(255) NOT NULL UNIQUE,
+        description TEXT,
+        discord_channel_id VARCHAR(255),
+        created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE GroupMentor (
+        group_id INT REFERENCES MemberGroup(group_id) ON DELETE CASCADE,
+        member_id INT REFERENCES Member(member_id) ON DELETE CASCADE,
+        PRIMARY KEY (group_id, member_id)
+);
+
+-- Every group_id already in use gets a placeholder group so the foreign key can be added.
+INSERT INTO MemberGroup (group_id, name)
+SELECT DISTINCT group_id, 'Group ' || group_id FROM Member;
+
+SELECT setval(
+        pg_get_serial_sequence('membergroup', 'group_id'),
+        COALESCE((SELECT MAX(group_id) FROM MemberGroup), 0) + 1,
+        false
+);
+
+ALTER TABLE Member
+        ADD CONSTRAINT fkey_group FOREIGN KEY (group_id) REFERENCES MemberGroup(group_id);
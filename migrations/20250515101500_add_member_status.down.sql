@@ -0,0 +1,10 @@
+DROP TABLE AcademicYearRollover;
+
+-- Fails if any member is past fourth year, which the original constraint doesn't allow.
+ALTER TABLE Member
+        DROP COLUMN graduation_year,
+        DROP COLUMN status,
+        DROP CONSTRAINT member_year_check,
+        ADD CONSTRAINT member_year_check CHECK (year BETWEEN 1 and 4);
+
+DROP TYPE member_status;
@@ -0,0 +1,16 @@
+-- Graduates are kept as alumni instead of being deleted, so `year` is no longer capped here.
+CREATE TYPE member_status AS ENUM ('active', 'alumni');
+
+ALTER TABLE Member
+        DROP CONSTRAINT member_year_check,
+        ADD CONSTRAINT member_year_check CHECK (year >= 1),
+        ADD COLUMN status member_status NOT NULL DEFAULT 'active',
+        ADD COLUMN graduation_year INT;
+
+-- One row per completed rollover so an academic year can't be promoted twice.
+CREATE TABLE AcademicYearRollover (
+        academic_year INT PRIMARY KEY,
+        promoted_count INT NOT NULL,
+        graduated_count INT NOT NULL,
+        created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
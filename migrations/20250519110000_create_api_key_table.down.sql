@@ -0,0 +1,2 @@
+DROP TABLE ApiKey;
+DROP TYPE api_role;
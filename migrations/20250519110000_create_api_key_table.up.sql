@@ -0,0 +1,12 @@
+-- Keys are shown once on creation and only their SHA-256 hash is stored.
+CREATE TYPE api_role AS ENUM ('admin', 'member');
+
+CREATE TABLE ApiKey (
+        api_key_id SERIAL PRIMARY KEY,
+        name VARCHAR(255) NOT NULL,
+        key_hash CHAR(64) NOT NULL UNIQUE,
+        role api_role NOT NULL,
+        member_id INT REFERENCES Member(member_id) ON DELETE CASCADE,
+        created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        revoked_at TIMESTAMP
+);
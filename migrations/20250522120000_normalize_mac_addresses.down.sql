@@ -0,0 +1,2 @@
+-- The original formatting of each address and email isn't kept, so there is nothing to restore.
+SELECT 1;
@@ -0,0 +1,58 @@
+-- Rewrite stored MAC addresses into the canonical `AA:BB:CC:DD:EE:FF` form, and emails into the
+-- trimmed, lowercase form, that inputs are now normalised to. MAC addresses that aren't 12 hex
+-- digits are left for an admin to fix.
+--
+-- Addresses that only differed in formatting would collide on the UNIQUE constraints, so the
+-- migration stops and lists them instead. Change all but one of each and migrate again.
+CREATE FUNCTION pg_temp.normalized_mac(mac TEXT) RETURNS TEXT AS
+$$
+    SELECT CASE WHEN regexp_replace(mac, '[:.-]', '', 'g') ~ '^[0-9A-Fa-f]{12}$' THEN
+        regexp_replace(UPPER(regexp_replace(mac, '[:.-]', '', 'g')), '(..)(?!$)', '\1:', 'g')
+    END
+$$ LANGUAGE sql IMMUTABLE;
+
+DO
+$$
+DECLARE
+    collisions TEXT;
+BEGIN
+    SELECT string_agg(format('%s (members %s)', mac, member_ids), '; ')
+    INTO collisions
+    FROM (
+        SELECT pg_temp.normalized_mac(mac_address) AS mac,
+               string_agg(member_id::TEXT, ', ' ORDER BY member_id) AS member_ids
+        FROM Member
+        WHERE pg_temp.normalized_mac(mac_address) IS NOT NULL
+        GROUP BY 1
+        HAVING COUNT(*) > 1
+    ) duplicates;
+    IF collisions IS NOT NULL THEN
+        RAISE EXCEPTION 'MAC addresses that are the same once normalised: %', collisions
+            USING HINT = 'Change all but one of each, then migrate again.';
+    END IF;
+
+    SELECT string_agg(format('%s (members %s)', email, member_ids), '; ')
+    INTO collisions
+    FROM (
+        SELECT LOWER(TRIM(email)) AS email,
+               string_agg(member_id::TEXT, ', ' ORDER BY member_id) AS member_ids
+        FROM Member
+        GROUP BY 1
+        HAVING COUNT(*) > 1
+    ) duplicates;
+    IF collisions IS NOT NULL THEN
+        RAISE EXCEPTION 'Emails that are the same once normalised: %', collisions
+            USING HINT = 'Change all but one of each, then migrate again.';
+    END IF;
+END
+$$;
+
+UPDATE Member
+SET mac_address = pg_temp.normalized_mac(mac_address)
+WHERE pg_temp.normalized_mac(mac_address) <> mac_address;
+
+UPDATE Member
+SET email = LOWER(TRIM(email))
+WHERE email <> LOWER(TRIM(email));
+
+DROP FUNCTION pg_temp.normalized_mac(TEXT);
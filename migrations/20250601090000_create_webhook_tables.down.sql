@@ -0,0 +1,6 @@
+DROP TABLE WebhookDeliveryAttempt;
+DROP TABLE WebhookDelivery;
+DROP TYPE webhook_delivery_status;
+DROP TABLE WebhookEvent;
+DROP TABLE Webhook;
+DROP TYPE webhook_event;
@@ -0,0 +1,55 @@
+-- Endpoints are signed for with their secret, so unlike API keys it has to be stored as is.
+CREATE TYPE webhook_event AS ENUM (
+        'member.created',
+        'attendance.marked',
+        'streak.reset',
+        'project.updated',
+        'dailytask.completed'
+);
+
+CREATE TABLE Webhook (
+        webhook_id SERIAL PRIMARY KEY,
+        url TEXT NOT NULL,
+        description TEXT,
+        events webhook_event[] NOT NULL,
+        secret TEXT NOT NULL,
+        active BOOLEAN NOT NULL DEFAULT TRUE,
+        created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+-- The outbox: events are written in the same transaction as the change they describe, and only
+-- when some webhook subscribes to them, then delivered in the background.
+CREATE TABLE WebhookEvent (
+        event_id BIGSERIAL PRIMARY KEY,
+        event_type webhook_event NOT NULL,
+        payload JSONB NOT NULL,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TYPE webhook_delivery_status AS ENUM ('pending', 'delivered', 'failed');
+
+CREATE TABLE WebhookDelivery (
+        delivery_id BIGSERIAL PRIMARY KEY,
+        webhook_id INT NOT NULL REFERENCES Webhook(webhook_id) ON DELETE CASCADE,
+        event_id BIGINT NOT NULL REFERENCES WebhookEvent(event_id) ON DELETE CASCADE,
+        status webhook_delivery_status NOT NULL DEFAULT 'pending',
+        attempts INT NOT NULL DEFAULT 0,
+        -- NULL until the first attempt fails, meaning as soon as possible.
+        next_attempt_at TIMESTAMPTZ,
+        last_response_status INT,
+        last_error TEXT,
+        delivered_at TIMESTAMPTZ,
+        UNIQUE (webhook_id, event_id)
+);
+
+CREATE INDEX webhookdelivery_pending_idx ON WebhookDelivery (next_attempt_at)
+        WHERE status = 'pending';
+
+CREATE TABLE WebhookDeliveryAttempt (
+        attempt_id BIGSERIAL PRIMARY KEY,
+        delivery_id BIGINT NOT NULL REFERENCES WebhookDelivery(delivery_id) ON DELETE CASCADE,
+        attempted_at TIMESTAMPTZ NOT NULL,
+        response_status INT,
+        error TEXT,
+        duration_ms INT NOT NULL
+);
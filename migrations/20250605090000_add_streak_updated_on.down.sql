@@ -0,0 +1 @@
+ALTER TABLE StatusUpdateStreak DROP COLUMN updated_on;
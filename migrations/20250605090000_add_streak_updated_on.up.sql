@@ -0,0 +1,3 @@
+-- The club's date of the last increment or reset, so streaks that haven't been extended lately
+-- can be found.
+ALTER TABLE StatusUpdateStreak ADD COLUMN updated_on DATE;
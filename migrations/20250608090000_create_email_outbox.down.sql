@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS EmailOutbox;
+DROP TYPE IF EXISTS email_status;
+DROP TYPE IF EXISTS email_kind;
+ALTER TABLE Member DROP COLUMN email_opt_out;
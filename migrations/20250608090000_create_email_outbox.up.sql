@@ -0,0 +1,25 @@
+ALTER TABLE Member ADD COLUMN email_opt_out BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TYPE email_kind AS ENUM ('welcome', 'weekly_report', 'monthly_summary');
+
+CREATE TYPE email_status AS ENUM ('pending', 'sent', 'failed');
+
+-- Emails to send, queued by what they are about rather than their content, which is rendered
+-- when they are sent.
+CREATE TABLE EmailOutbox (
+        email_id BIGSERIAL PRIMARY KEY,
+        kind email_kind NOT NULL,
+        member_id INT NOT NULL REFERENCES Member(member_id) ON DELETE CASCADE,
+        -- The first day of the week or month a report covers.
+        period_start DATE,
+        status email_status NOT NULL DEFAULT 'pending',
+        attempts INT NOT NULL DEFAULT 0,
+        -- NULL until the first attempt fails, meaning as soon as possible.
+        next_attempt_at TIMESTAMPTZ,
+        last_error TEXT,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+        sent_at TIMESTAMPTZ,
+        UNIQUE (kind, member_id, period_start)
+);
+
+CREATE INDEX email_outbox_pending_idx ON EmailOutbox (next_attempt_at) WHERE status = 'pending';
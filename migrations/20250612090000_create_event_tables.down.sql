@@ -0,0 +1,5 @@
+DROP TABLE IF EXISTS EventAttendance;
+DROP TABLE IF EXISTS EventRsvp;
+DROP TYPE IF EXISTS rsvp_status;
+DROP TABLE IF EXISTS Event;
+DROP TYPE IF EXISTS event_kind;
@@ -0,0 +1,51 @@
+CREATE TYPE event_kind AS ENUM ('talk', 'workshop', 'meeting', 'other');
+
+-- Talks, workshops and meetings. An empty audience list means the event isn't limited by it,
+-- so an event with neither is open to everyone.
+CREATE TABLE Event (
+        event_id SERIAL PRIMARY KEY,
+        title TEXT NOT NULL,
+        description TEXT,
+        kind event_kind NOT NULL DEFAULT 'other',
+        starts_at TIMESTAMPTZ NOT NULL,
+        ends_at TIMESTAMPTZ NOT NULL,
+        venue TEXT,
+        organizer_id INT,
+        audience_years INT[] NOT NULL DEFAULT '{}',
+        audience_group_ids INT[] NOT NULL DEFAULT '{}',
+        created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        CONSTRAINT fkey_event_organizer FOREIGN KEY (organizer_id)
+                REFERENCES Member(member_id) ON DELETE SET NULL,
+        CHECK (ends_at > starts_at)
+);
+
+CREATE INDEX event_starts_at_idx ON Event (starts_at);
+
+CREATE TYPE rsvp_status AS ENUM ('going', 'maybe', 'not_going');
+
+CREATE TABLE EventRsvp (
+        event_id INT NOT NULL,
+        member_id INT NOT NULL,
+        status rsvp_status NOT NULL,
+        responded_at TIMESTAMPTZ NOT NULL,
+        PRIMARY KEY (event_id, member_id),
+        CONSTRAINT fkey_rsvp_event FOREIGN KEY (event_id)
+                REFERENCES Event(event_id) ON DELETE CASCADE,
+        CONSTRAINT fkey_rsvp_member FOREIGN KEY (member_id)
+                REFERENCES Member(member_id) ON DELETE CASCADE
+);
+
+-- Check-ins at events, separate from daily lab attendance.
+CREATE TABLE EventAttendance (
+        event_id INT NOT NULL,
+        member_id INT NOT NULL,
+        checked_in_at TIMESTAMPTZ NOT NULL,
+        PRIMARY KEY (event_id, member_id),
+        CONSTRAINT fkey_event_attendance_event FOREIGN KEY (event_id)
+                REFERENCES Event(event_id) ON DELETE CASCADE,
+        CONSTRAINT fkey_event_attendance_member FOREIGN KEY (member_id)
+                REFERENCES Member(member_id) ON DELETE CASCADE
+);
+
+CREATE INDEX event_rsvp_member_idx ON EventRsvp (member_id);
+CREATE INDEX event_attendance_member_idx ON EventAttendance (member_id);
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS CalendarFeedToken;
+DROP TABLE IF EXISTS CalendarDate;
+DROP TYPE IF EXISTS calendar_date_kind;
@@ -0,0 +1,23 @@
+CREATE TYPE calendar_date_kind AS ENUM ('holiday', 'deadline');
+
+-- All-day entries for the calendar feeds, for the whole club or one group.
+CREATE TABLE CalendarDate (
+        date_id SERIAL PRIMARY KEY,
+        date DATE NOT NULL,
+        title TEXT NOT NULL,
+        description TEXT,
+        kind calendar_date_kind NOT NULL,
+        group_id INT,
+        created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        CONSTRAINT fkey_calendar_date_group FOREIGN KEY (group_id)
+                REFERENCES MemberGroup(group_id) ON DELETE CASCADE
+);
+
+CREATE INDEX calendar_date_date_idx ON CalendarDate (date);
+
+-- The secret in each member's personal feed URL. Like API keys, only its hash is stored.
+CREATE TABLE CalendarFeedToken (
+        member_id INT PRIMARY KEY REFERENCES Member(member_id) ON DELETE CASCADE,
+        token_hash TEXT NOT NULL UNIQUE,
+        created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS ProfileChangeRequest;
+DROP TYPE IF EXISTS profile_change_status;
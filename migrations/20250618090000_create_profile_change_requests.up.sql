@@ -0,0 +1,18 @@
+CREATE TYPE profile_change_status AS ENUM ('pending', 'approved', 'rejected');
+
+-- Profile changes members asked for that only take effect once an admin approves them. So far
+-- that's only the MAC address, which Presense marks attendance by.
+CREATE TABLE ProfileChangeRequest (
+        change_id SERIAL PRIMARY KEY,
+        member_id INT NOT NULL,
+        mac_address VARCHAR(255) NOT NULL,
+        status profile_change_status NOT NULL DEFAULT 'pending',
+        requested_at TIMESTAMPTZ NOT NULL,
+        reviewed_at TIMESTAMPTZ,
+        CONSTRAINT fkey_profile_change_member FOREIGN KEY (member_id)
+                REFERENCES Member(member_id) ON DELETE CASCADE
+);
+
+-- A member has at most one change waiting, which a newer request replaces.
+CREATE UNIQUE INDEX profile_change_request_pending_idx
+        ON ProfileChangeRequest (member_id) WHERE status = 'pending';
@@ -0,0 +1,5 @@
+DROP TRIGGER IF EXISTS update_member_primary_device ON Member;
+DROP TRIGGER IF EXISTS add_member_primary_device ON Member;
+DROP FUNCTION IF EXISTS update_primary_device();
+DROP FUNCTION IF EXISTS add_primary_device();
+DROP TABLE IF EXISTS MemberDevice;
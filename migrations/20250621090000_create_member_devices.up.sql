@@ -0,0 +1,50 @@
+-- The devices Presense can see a member by. `Member.mac_address` stays as the member's primary
+-- device, mirrored here by the triggers below, and members can add more.
+CREATE TABLE MemberDevice (
+        device_id SERIAL PRIMARY KEY,
+        member_id INT NOT NULL,
+        mac_address VARCHAR(255) NOT NULL UNIQUE,
+        label TEXT NOT NULL,
+        is_primary BOOLEAN NOT NULL DEFAULT FALSE,
+        -- Only verified devices are matched to their member.
+        is_verified BOOLEAN NOT NULL DEFAULT FALSE,
+        last_seen_at TIMESTAMPTZ,
+        created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        CONSTRAINT fkey_member_device_member FOREIGN KEY (member_id)
+                REFERENCES Member(member_id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX member_device_primary_idx ON MemberDevice (member_id) WHERE is_primary;
+
+INSERT INTO MemberDevice (member_id, mac_address, label, is_primary, is_verified)
+SELECT member_id, mac_address, 'Primary', TRUE, TRUE FROM Member;
+
+CREATE OR REPLACE FUNCTION add_primary_device()
+RETURNS TRIGGER AS
+$$
+BEGIN
+    INSERT INTO MemberDevice (member_id, mac_address, label, is_primary, is_verified)
+    VALUES (NEW.member_id, NEW.mac_address, 'Primary', TRUE, TRUE);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER add_member_primary_device
+AFTER INSERT ON Member
+FOR EACH ROW
+EXECUTE FUNCTION add_primary_device();
+
+CREATE OR REPLACE FUNCTION update_primary_device()
+RETURNS TRIGGER AS
+$$
+BEGIN
+    UPDATE MemberDevice SET mac_address = NEW.mac_address
+    WHERE member_id = NEW.member_id AND is_primary;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER update_member_primary_device
+AFTER UPDATE OF mac_address ON Member
+FOR EACH ROW
+EXECUTE FUNCTION update_primary_device();
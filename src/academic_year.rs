@@ -0,0 +1,75 @@
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::models::member::{Member, RolloverReport};
+
+/// Promotes every active member by one year and moves those in `final_year` or later to alumni,
+/// keeping their history.
+///
+/// A dry run executes the same statements inside a transaction that is then rolled back, so the
+/// report shows exactly what a real run would change.
+pub async fn rollover(
+    pool: &PgPool,
+    academic_year: i32,
+    final_year: i32,
+    dry_run: bool,
+) -> Result<RolloverReport, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let graduated = sqlx::query_as::<_, Member>(
+        "UPDATE Member SET status = 'alumni', graduation_year = $1
+         WHERE status = 'active' AND year >= $2
+         RETURNING *",
+    )
+    .bind(academic_year)
+    .bind(final_year)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let promoted = sqlx::query_as::<_, Member>(
+        "UPDATE Member SET year = year + 1
+         WHERE status = 'active'
+         RETURNING *",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO AcademicYearRollover (academic_year, promoted_count, graduated_count)
+         VALUES ($1, $2, $3)",
+    )
+    .bind(academic_year)
+    .bind(promoted.len() as i32)
+    .bind(graduated.len() as i32)
+    .execute(&mut *tx)
+    .await?;
+
+    if dry_run {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+        info!(
+            "Rolled over academic year {}: {} promoted, {} graduated.",
+            academic_year,
+            promoted.len(),
+            graduated.len()
+        );
+    }
+
+    Ok(RolloverReport {
+        academic_year,
+        dry_run,
+        promoted,
+        graduated,
+    })
+}
+
+/// Whether the given academic year has already been rolled over.
+pub async fn has_rolled_over(pool: &PgPool, academic_year: i32) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM AcademicYearRollover WHERE academic_year = $1)",
+    )
+    .bind(academic_year)
+    .fetch_one(pool)
+    .await
+}
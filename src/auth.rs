@@ -0,0 +1,210 @@
+use axum::{
+    extract::{FromRef, FromRequestParts, OptionalFromRequestParts, Request, State},
+    http::{header::AUTHORIZATION, request::Parts, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool};
+
+use crate::error::RootError;
+use crate::routes::AppState;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, sqlx::Type, clap::ValueEnum)]
+#[sqlx(type_name = "api_role", rename_all = "lowercase")]
+pub enum Role {
+    Admin,
+    Member,
+}
+
+/// The holder of a valid, unrevoked API key.
+#[derive(Clone, Debug, FromRow)]
+pub struct Caller {
+    pub api_key_id: i32,
+    pub role: Role,
+    pub member_id: Option<i32>,
+}
+
+impl Caller {
+    pub fn is_admin(&self) -> bool {
+        self.role == Role::Admin
+    }
+}
+
+/// Restricts a GraphQL field to callers with an admin API key.
+pub struct AdminGuard;
+
+impl async_graphql::Guard for AdminGuard {
+    async fn check(&self, ctx: &async_graphql::Context<'_>) -> async_graphql::Result<()> {
+        match ctx.data_opt::<Caller>() {
+            Some(caller) if caller.is_admin() => Ok(()),
+            Some(_) => Err(RootError::Forbidden("This needs an admin API key".to_string()).into()),
+            None => {
+                Err(RootError::Unauthenticated("This needs an admin API key".to_string()).into())
+            }
+        }
+    }
+}
+
+/// Restricts a GraphQL field to admins and the member an API key was issued to.
+pub struct MemberGuard {
+    member_id: i32,
+}
+
+impl MemberGuard {
+    pub fn new(member_id: i32) -> Self {
+        Self { member_id }
+    }
+}
+
+impl async_graphql::Guard for MemberGuard {
+    async fn check(&self, ctx: &async_graphql::Context<'_>) -> async_graphql::Result<()> {
+        match ctx.data_opt::<Caller>() {
+            Some(caller) if caller.is_admin() || caller.member_id == Some(self.member_id) => Ok(()),
+            Some(_) => Err(RootError::Forbidden(
+                "This needs the member's own API key or an admin one".to_string(),
+            )
+            .into()),
+            None => Err(RootError::Unauthenticated("This needs an API key".to_string()).into()),
+        }
+    }
+}
+
+/// The member the caller's API key was issued to, for fields that act on the caller themselves.
+pub fn caller_member_id(ctx: &async_graphql::Context<'_>) -> Result<i32, RootError> {
+    match ctx.data_opt::<Caller>() {
+        Some(Caller {
+            member_id: Some(member_id),
+            ..
+        }) => Ok(*member_id),
+        Some(_) => Err(RootError::Forbidden(
+            "This needs an API key issued to a member".to_string(),
+        )),
+        None => Err(RootError::Unauthenticated(
+            "This needs an API key".to_string(),
+        )),
+    }
+}
+
+/// Checks `signature`, the hex HMAC-SHA256 of `message` keyed with the root secret. Presense signs
+/// attendance marks and event check-ins this way.
+pub fn verify_signature(secret: &str, message: &str, signature: &str) -> Result<(), RootError> {
+    let received = hex::decode(signature).map_err(|_| RootError::Validation {
+        field: Some("hmacSignature"),
+        message: "HMAC signature must be hex-encoded".to_string(),
+    })?;
+
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(message.as_bytes());
+    mac.verify_slice(&received)
+        .map_err(|_| RootError::Unauthenticated("HMAC verification failed".to_string()))
+}
+
+pub(crate) fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Creates a new API key and returns the plaintext key. It cannot be recovered later.
+pub async fn create_api_key(
+    pool: &PgPool,
+    name: &str,
+    role: Role,
+    member_id: Option<i32>,
+) -> Result<String, sqlx::Error> {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let key = format!("root_{}", hex::encode(bytes));
+
+    sqlx::query("INSERT INTO ApiKey (name, key_hash, role, member_id) VALUES ($1, $2, $3, $4)")
+        .bind(name)
+        .bind(hash_key(&key))
+        .bind(role)
+        .bind(member_id)
+        .execute(pool)
+        .await?;
+
+    Ok(key)
+}
+
+/// Looks up the caller for a plaintext key, ignoring revoked keys.
+pub async fn authenticate(pool: &PgPool, key: &str) -> Result<Option<Caller>, sqlx::Error> {
+    sqlx::query_as::<_, Caller>(
+        "SELECT api_key_id, role, member_id FROM ApiKey
+         WHERE key_hash = $1 AND revoked_at IS NULL",
+    )
+    .bind(hash_key(key))
+    .fetch_optional(pool)
+    .await
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+}
+
+/// Authenticates the bearer token, if there is one, and stores the `Caller` in the request's
+/// extensions so the rate limiter, handlers and resolvers don't each look it up again. Requests
+/// without a token carry on anonymously, but an invalid key is rejected outright.
+pub async fn identify_caller(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, RootError> {
+    if let Some(key) = bearer_token(request.headers()).map(str::to_string) {
+        let caller = authenticate(&state.pool, &key)
+            .await?
+            .ok_or_else(|| RootError::Unauthenticated("Invalid API key".to_string()))?;
+        request.extensions_mut().insert(caller);
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// Extracts the caller from an `Authorization: Bearer <key>` header, rejecting the request otherwise.
+impl<S> FromRequestParts<S> for Caller
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = RootError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(caller) = parts.extensions.get::<Caller>() {
+            return Ok(caller.clone());
+        }
+
+        let state = AppState::from_ref(state);
+        let key = bearer_token(&parts.headers)
+            .ok_or_else(|| RootError::Unauthenticated("Missing bearer token".to_string()))?;
+
+        authenticate(&state.pool, key)
+            .await?
+            .ok_or_else(|| RootError::Unauthenticated("Invalid API key".to_string()))
+    }
+}
+
+/// Like the `Caller` extractor, but lets requests without a bearer token through as `None`.
+impl<S> OptionalFromRequestParts<S> for Caller
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = RootError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &S,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        if parts.extensions.get::<Caller>().is_none() && bearer_token(&parts.headers).is_none() {
+            return Ok(None);
+        }
+        <Caller as FromRequestParts<S>>::from_request_parts(parts, state)
+            .await
+            .map(Some)
+    }
+}
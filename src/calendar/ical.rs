@@ -0,0 +1,216 @@
+//! Just enough of RFC 5545 to publish events: text escaping, line folding and a `VTIMEZONE` for
+//! the club's zone.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeDelta, TimeZone, Utc};
+use chrono_tz::{OffsetComponents, OffsetName, Tz};
+
+/// Lines longer than this many octets are folded onto continuation lines.
+const MAX_LINE_OCTETS: usize = 75;
+
+/// How far past `stamp` timezone changes are listed. Calendar apps carry the last one forward, and
+/// an entry dated centuries ahead would otherwise have every day until then checked.
+const TIMEZONE_HORIZON: TimeDelta = TimeDelta::days(2 * 365);
+
+const DATE_FORMAT: &str = "%Y%m%d";
+const LOCAL_FORMAT: &str = "%Y%m%dT%H%M%S";
+const UTC_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+pub enum When {
+    /// Shown at these times in the club's timezone.
+    Timed {
+        starts_at: DateTime<Utc>,
+        ends_at: DateTime<Utc>,
+    },
+    AllDay(NaiveDate),
+}
+
+/// One `VEVENT`.
+pub struct Entry {
+    /// Stays the same for as long as the entry exists, so calendar apps update it in place.
+    pub uid: String,
+    pub summary: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub category: &'static str,
+    pub when: When,
+}
+
+impl Entry {
+    fn span(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        match self.when {
+            When::Timed { starts_at, ends_at } => (starts_at, ends_at),
+            When::AllDay(date) => {
+                let midnight = date.and_time(Default::default()).and_utc();
+                (midnight, midnight + TimeDelta::days(1))
+            }
+        }
+    }
+}
+
+/// Writes `entries` as a `VCALENDAR` named `name`, with timed entries in `timezone`.
+pub fn calendar(name: &str, timezone: Tz, stamp: DateTime<Utc>, entries: &[Entry]) -> String {
+    let mut out = Writer::default();
+    out.line("BEGIN:VCALENDAR");
+    out.line("VERSION:2.0");
+    out.line("PRODID:-//amFOSS//Root//EN");
+    out.line("CALSCALE:GREGORIAN");
+    out.line("METHOD:PUBLISH");
+    out.property("X-WR-CALNAME", &escape(name));
+    out.property("X-WR-TIMEZONE", timezone.name());
+
+    let spans = entries.iter().map(Entry::span);
+    let from = spans.clone().map(|(start, _)| start).min().unwrap_or(stamp);
+    let to = spans
+        .map(|(_, end)| end)
+        .max()
+        .unwrap_or(stamp)
+        .min(stamp + TIMEZONE_HORIZON);
+    write_timezone(&mut out, timezone, from, to);
+
+    for entry in entries {
+        out.line("BEGIN:VEVENT");
+        out.property("UID", &escape(&entry.uid));
+        out.property("DTSTAMP", &stamp.format(UTC_FORMAT).to_string());
+        match entry.when {
+            When::Timed { starts_at, ends_at } => {
+                let local = |at: DateTime<Utc>| {
+                    at.with_timezone(&timezone).format(LOCAL_FORMAT).to_string()
+                };
+                out.property(
+                    &format!("DTSTART;TZID={}", timezone.name()),
+                    &local(starts_at),
+                );
+                out.property(&format!("DTEND;TZID={}", timezone.name()), &local(ends_at));
+            }
+            When::AllDay(date) => {
+                out.property("DTSTART;VALUE=DATE", &date.format(DATE_FORMAT).to_string());
+                let next_day = date
+                    .succ_opt()
+                    .expect("Calendar dates must not be the last date");
+                out.property(
+                    "DTEND;VALUE=DATE",
+                    &next_day.format(DATE_FORMAT).to_string(),
+                );
+                out.line("TRANSP:TRANSPARENT");
+            }
+        }
+        out.property("SUMMARY", &escape(&entry.summary));
+        if let Some(description) = &entry.description {
+            out.property("DESCRIPTION", &escape(description));
+        }
+        if let Some(location) = &entry.location {
+            out.property("LOCATION", &escape(location));
+        }
+        out.property("CATEGORIES", entry.category);
+        out.line("END:VEVENT");
+    }
+
+    out.line("END:VCALENDAR");
+    out.0
+}
+
+/// A `VTIMEZONE` with an observance for the offset in effect at `from` and each change in it
+/// until `to`, found by checking the offset daily and narrowing down to the second.
+fn write_timezone(out: &mut Writer, timezone: Tz, from: DateTime<Utc>, to: DateTime<Utc>) {
+    let offset_at = |at: DateTime<Utc>| timezone.offset_from_utc_datetime(&at.naive_utc());
+    let seconds = |offset: TimeDelta| offset.num_seconds() as i32;
+    let utc_offset = |at: DateTime<Utc>| {
+        let offset = offset_at(at);
+        seconds(offset.base_utc_offset() + offset.dst_offset())
+    };
+
+    out.line("BEGIN:VTIMEZONE");
+    out.property("TZID", timezone.name());
+
+    let mut observe = |onset: DateTime<Utc>, offset_from: i32| {
+        let offset = offset_at(onset);
+        let offset_to = utc_offset(onset);
+        let kind = if offset.dst_offset().is_zero() {
+            "STANDARD"
+        } else {
+            "DAYLIGHT"
+        };
+        // The onset is given in the local time that was in effect until then.
+        let local_onset: NaiveDateTime = onset.naive_utc() + TimeDelta::seconds(offset_from.into());
+        out.line(&format!("BEGIN:{}", kind));
+        out.property("DTSTART", &local_onset.format(LOCAL_FORMAT).to_string());
+        out.property("TZOFFSETFROM", &format_offset(offset_from));
+        out.property("TZOFFSETTO", &format_offset(offset_to));
+        if let Some(name) = offset.abbreviation() {
+            out.property("TZNAME", &escape(name));
+        }
+        out.line(&format!("END:{}", kind));
+    };
+
+    let start = from - TimeDelta::days(1);
+    observe(start, utc_offset(start));
+
+    let mut day = start;
+    while day < to {
+        let next_day = day + TimeDelta::days(1);
+        let offset_before = utc_offset(day);
+        if utc_offset(next_day) != offset_before {
+            let (mut before, mut after) = (day, next_day);
+            while after - before > TimeDelta::seconds(1) {
+                let middle = before + (after - before) / 2;
+                if utc_offset(middle) == offset_before {
+                    before = middle;
+                } else {
+                    after = middle;
+                }
+            }
+            observe(after, offset_before);
+        }
+        day = next_day;
+    }
+
+    out.line("END:VTIMEZONE");
+}
+
+/// `+0530`, `-0400` and so on.
+fn format_offset(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let minutes = seconds.abs() / 60;
+    format!("{}{:02}{:02}", sign, minutes / 60, minutes % 60)
+}
+
+/// Escapes a TEXT value.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[derive(Default)]
+struct Writer(String);
+
+impl Writer {
+    fn property(&mut self, name: &str, value: &str) {
+        self.line(&format!("{}:{}", name, value));
+    }
+
+    /// Writes `content` with CRLF endings, folded so no line exceeds the limit, without splitting
+    /// a character.
+    fn line(&mut self, content: &str) {
+        let mut octets = 0;
+        for c in content.chars() {
+            if octets + c.len_utf8() > MAX_LINE_OCTETS {
+                self.0.push_str("\r\n ");
+                // The leading space of a continuation line counts towards its length.
+                octets = 1;
+            }
+            self.0.push(c);
+            octets += c.len_utf8();
+        }
+        self.0.push_str("\r\n");
+    }
+}
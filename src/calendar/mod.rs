@@ -0,0 +1,261 @@
+//! iCalendar feeds of club events, holidays and deadlines, for calendar apps to subscribe to.
+//!
+//! The club and group feeds are public, as the events in them already are. Each member's feed
+//! lives at a secret URL instead, since calendar apps can't send API keys.
+
+mod ical;
+
+use axum::{
+    extract::{Path, State},
+    http::header::{CACHE_CONTROL, CONTENT_TYPE},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use sqlx::{FromRow, PgPool};
+
+use crate::auth::hash_key;
+use crate::clock::ClubClock;
+use crate::error::RootError;
+use crate::models::calendar::CalendarDateKind;
+use crate::models::event::EventKind;
+use crate::routes::AppState;
+
+use ical::{Entry, When};
+
+/// How far back feeds go. Calendar apps keep what they've already seen, so this only limits
+/// what a new subscription starts with.
+const HISTORY: TimeDelta = TimeDelta::days(365);
+
+/// Calendar apps refresh on their own schedule, but this keeps them from doing it too eagerly.
+const MAX_AGE_SECS: u32 = 15 * 60;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FeedScope {
+    /// Every event, and the holidays and deadlines that apply to everyone.
+    Club,
+    /// The events a group is invited to, and the club's and the group's holidays and deadlines.
+    Group(i32),
+    /// The events a member is invited to and hasn't declined, or has checked in at, and the
+    /// holidays and deadlines of the club and the member's group.
+    Member(i32),
+}
+
+#[derive(FromRow)]
+struct FeedEvent {
+    event_id: i32,
+    title: String,
+    description: Option<String>,
+    kind: EventKind,
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+    venue: Option<String>,
+}
+
+#[derive(FromRow)]
+struct FeedDate {
+    date_id: i32,
+    date: NaiveDate,
+    title: String,
+    description: Option<String>,
+    kind: CalendarDateKind,
+}
+
+/// Writes the feed for `scope`, or `None` if its group or member doesn't exist.
+pub async fn feed(
+    pool: &PgPool,
+    clock: &ClubClock,
+    scope: FeedScope,
+) -> Result<Option<String>, sqlx::Error> {
+    let now = clock.now().to_utc();
+    let since = now - HISTORY;
+    let since_date = (clock.now() - HISTORY).date_naive();
+
+    let (name, events, dates) = match scope {
+        FeedScope::Club => {
+            let events = sqlx::query_as::<_, FeedEvent>(
+                "SELECT * FROM Event WHERE ends_at > $1 ORDER BY starts_at, event_id",
+            )
+            .bind(since)
+            .fetch_all(pool)
+            .await?;
+            let dates = sqlx::query_as::<_, FeedDate>(
+                "SELECT * FROM CalendarDate WHERE date >= $1 AND group_id IS NULL
+                 ORDER BY date, date_id",
+            )
+            .bind(since_date)
+            .fetch_all(pool)
+            .await?;
+            ("amFOSS".to_string(), events, dates)
+        }
+        FeedScope::Group(group_id) => {
+            let Some(group_name) =
+                sqlx::query_scalar::<_, String>("SELECT name FROM MemberGroup WHERE group_id = $1")
+                    .bind(group_id)
+                    .fetch_optional(pool)
+                    .await?
+            else {
+                return Ok(None);
+            };
+            let events = sqlx::query_as::<_, FeedEvent>(
+                "SELECT * FROM Event
+                 WHERE ends_at > $1
+                 AND (CARDINALITY(audience_group_ids) = 0 OR $2 = ANY(audience_group_ids))
+                 ORDER BY starts_at, event_id",
+            )
+            .bind(since)
+            .bind(group_id)
+            .fetch_all(pool)
+            .await?;
+            let dates = sqlx::query_as::<_, FeedDate>(
+                "SELECT * FROM CalendarDate
+                 WHERE date >= $1 AND (group_id IS NULL OR group_id = $2)
+                 ORDER BY date, date_id",
+            )
+            .bind(since_date)
+            .bind(group_id)
+            .fetch_all(pool)
+            .await?;
+            (format!("amFOSS {}", group_name), events, dates)
+        }
+        FeedScope::Member(member_id) => {
+            let Some(member_name) =
+                sqlx::query_scalar::<_, String>("SELECT name FROM Member WHERE member_id = $1")
+                    .bind(member_id)
+                    .fetch_optional(pool)
+                    .await?
+            else {
+                return Ok(None);
+            };
+            let events = sqlx::query_as::<_, FeedEvent>(
+                "SELECT ev.* FROM Event ev
+                 JOIN Member mem ON mem.member_id = $2
+                 LEFT JOIN EventRsvp rsvp
+                    ON rsvp.event_id = ev.event_id AND rsvp.member_id = mem.member_id
+                 WHERE ev.ends_at > $1
+                 AND (
+                    EXISTS (
+                        SELECT 1 FROM EventAttendance att
+                        WHERE att.event_id = ev.event_id AND att.member_id = mem.member_id
+                    )
+                    OR (
+                        (CARDINALITY(ev.audience_years) = 0 OR mem.year = ANY(ev.audience_years))
+                        AND (CARDINALITY(ev.audience_group_ids) = 0
+                             OR mem.group_id = ANY(ev.audience_group_ids))
+                        AND rsvp.status IS DISTINCT FROM 'not_going'
+                    )
+                 )
+                 ORDER BY ev.starts_at, ev.event_id",
+            )
+            .bind(since)
+            .bind(member_id)
+            .fetch_all(pool)
+            .await?;
+            let dates = sqlx::query_as::<_, FeedDate>(
+                "SELECT cal.* FROM CalendarDate cal
+                 JOIN Member mem ON mem.member_id = $2
+                 WHERE cal.date >= $1
+                 AND (cal.group_id IS NULL OR cal.group_id = mem.group_id)
+                 ORDER BY cal.date, cal.date_id",
+            )
+            .bind(since_date)
+            .bind(member_id)
+            .fetch_all(pool)
+            .await?;
+            (format!("amFOSS ({})", member_name), events, dates)
+        }
+    };
+
+    let entries: Vec<Entry> = events
+        .into_iter()
+        .map(|event| Entry {
+            uid: format!("event-{}@root.amfoss.in", event.event_id),
+            summary: event.title,
+            description: event.description,
+            location: event.venue,
+            category: match event.kind {
+                EventKind::Talk => "TALK",
+                EventKind::Workshop => "WORKSHOP",
+                EventKind::Meeting => "MEETING",
+                EventKind::Other => "EVENT",
+            },
+            when: When::Timed {
+                starts_at: event.starts_at,
+                ends_at: event.ends_at,
+            },
+        })
+        .chain(dates.into_iter().map(|date| Entry {
+            uid: format!("date-{}@root.amfoss.in", date.date_id),
+            summary: date.title,
+            description: date.description,
+            location: None,
+            category: match date.kind {
+                CalendarDateKind::Holiday => "HOLIDAY",
+                CalendarDateKind::Deadline => "DEADLINE",
+            },
+            when: When::AllDay(date.date),
+        }))
+        .collect();
+
+    Ok(Some(ical::calendar(&name, clock.timezone(), now, &entries)))
+}
+
+pub async fn club_feed(State(state): State<AppState>) -> Result<Response, RootError> {
+    feed_response(&state, FeedScope::Club).await
+}
+
+pub async fn group_feed(
+    State(state): State<AppState>,
+    Path(group_id): Path<i32>,
+) -> Result<Response, RootError> {
+    feed_response(&state, FeedScope::Group(group_id)).await
+}
+
+/// The feed at a member's secret URL, see `createCalendarFeedToken`.
+pub async fn member_feed(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Response, RootError> {
+    let member_id = feed_token_member(&state.pool, &token)
+        .await?
+        .ok_or_else(|| RootError::NotFound("No calendar feed at this URL".to_string()))?;
+
+    feed_response(&state, FeedScope::Member(member_id)).await
+}
+
+/// The member whose feed `token` is for, if any. Calendar apps insist on an `.ics` extension, so
+/// it's allowed but not required.
+pub async fn feed_token_member(pool: &PgPool, token: &str) -> Result<Option<i32>, sqlx::Error> {
+    let token = token.strip_suffix(".ics").unwrap_or(token);
+    sqlx::query_scalar::<_, i32>("SELECT member_id FROM CalendarFeedToken WHERE token_hash = $1")
+        .bind(hash_key(token))
+        .fetch_optional(pool)
+        .await
+}
+
+async fn feed_response(state: &AppState, scope: FeedScope) -> Result<Response, RootError> {
+    let calendar = feed(&state.pool, &state.clock, scope)
+        .await?
+        .ok_or_else(|| match scope {
+            FeedScope::Group(group_id) => {
+                RootError::NotFound(format!("Group {} does not exist", group_id))
+            }
+            _ => RootError::NotFound("No calendar feed at this URL".to_string()),
+        })?;
+
+    // Shared caches mustn't keep a member's feed, which is only as private as its URL.
+    let visibility = match scope {
+        FeedScope::Member(_) => "private",
+        FeedScope::Club | FeedScope::Group(_) => "public",
+    };
+    Ok((
+        [
+            (CONTENT_TYPE, "text/calendar; charset=utf-8".to_string()),
+            (
+                CACHE_CONTROL,
+                format!("{}, max-age={}", visibility, MAX_AGE_SECS),
+            ),
+        ],
+        calendar,
+    )
+        .into_response())
+}
@@ -0,0 +1,96 @@
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand, ValueEnum};
+
+use crate::auth::Role;
+use crate::export::{ExportFilter, ExportFormat, ExportTable};
+
+/// Root runs the GraphQL server when no subcommand is given.
+#[derive(Parser)]
+#[command(
+    version,
+    about = "A GraphQL backend for managing club member information"
+)]
+pub struct Cli {
+    /// TOML configuration file. Defaults to `root.toml` in the working directory, if present.
+    #[arg(long, global = true, env = "ROOT_CONFIG")]
+    pub config: Option<PathBuf>,
+    /// Print the effective configuration, with secrets masked, and exit.
+    #[arg(long, global = true)]
+    pub print_config: bool,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run the GraphQL server, applying pending migrations first. The default without a subcommand.
+    Serve,
+    /// Apply, revert or list database migrations.
+    Migrate {
+        #[command(subcommand)]
+        action: MigrateAction,
+    },
+    /// Run a scheduled job once, now.
+    RunJob {
+        job: Job,
+        /// The day to run it for, in `attendance.timezone`. Defaults to today.
+        #[arg(long)]
+        date: Option<NaiveDate>,
+    },
+    /// Fill a development database with sample groups, members, attendance, streaks and projects.
+    Seed,
+    /// Validate a CSV of members, with the same columns as `createMember`, and insert the valid rows.
+    ImportMembers {
+        /// Path to the CSV file. The first line must be the header.
+        path: PathBuf,
+        /// Validate and report without inserting anything.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Write a table as CSV or JSON, with the same columns and filters as its export route.
+    Export {
+        table: ExportTable,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// Write to this file instead of stdout.
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        filter: ExportFilter,
+    },
+    /// Create an API key and print it. The key is not stored in plaintext.
+    CreateApiKey {
+        /// A label to recognise the key by, e.g. who or what it was issued to.
+        #[arg(long)]
+        name: String,
+        #[arg(long, value_enum, default_value_t = Role::Member)]
+        role: Role,
+        /// Member the key acts on behalf of.
+        #[arg(long)]
+        member_id: Option<i32>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MigrateAction {
+    /// Apply every pending migration.
+    Run,
+    /// Revert the latest migration, or every migration newer than `--target`.
+    Revert {
+        /// Version to revert back to, e.g. 20250519110000. Use 0 to revert everything.
+        #[arg(long)]
+        target: Option<i64>,
+    },
+    /// List every migration and whether it has been applied.
+    Status,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Job {
+    /// Insert the day's attendance records and update monthly attendance summaries.
+    DailyTask,
+    /// Send each group its digest of the day before, whether or not `notifier.enabled` is set.
+    Digests,
+}
@@ -0,0 +1,82 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+
+/// Where the current time comes from.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The real time, as reported by the operating system.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that only moves when told to, for tests.
+pub struct FixedClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl FixedClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: Mutex::new(now),
+        }
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().expect("Fixed clock lock poisoned") = now;
+    }
+
+    pub fn advance(&self, by: TimeDelta) {
+        *self.now.lock().expect("Fixed clock lock poisoned") += by;
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().expect("Fixed clock lock poisoned")
+    }
+}
+
+/// The current time in the club's timezone. Everything that depends on what day or time it is
+/// for the club, such as the daily task's schedule and attendance times, reads it from here.
+#[derive(Clone)]
+pub struct ClubClock {
+    clock: Arc<dyn Clock>,
+    timezone: Tz,
+}
+
+impl ClubClock {
+    pub fn new(clock: Arc<dyn Clock>, timezone: Tz) -> Self {
+        Self { clock, timezone }
+    }
+
+    /// The real time in `timezone`.
+    pub fn system(timezone: Tz) -> Self {
+        Self::new(Arc::new(SystemClock), timezone)
+    }
+
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+    }
+
+    pub fn now(&self) -> DateTime<Tz> {
+        self.clock.now().with_timezone(&self.timezone)
+    }
+
+    /// The club's current date.
+    pub fn today(&self) -> NaiveDate {
+        self.now().date_naive()
+    }
+
+    /// The club's current wall-clock time.
+    pub fn time(&self) -> NaiveTime {
+        self.now().time()
+    }
+}
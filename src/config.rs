@@ -0,0 +1,644 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use axum::http::HeaderValue;
+use chrono::NaiveTime;
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use tracing_subscriber::EnvFilter;
+
+/// Read when `--config` isn't given. Unlike an explicitly passed file, it may be missing.
+const DEFAULT_CONFIG_FILE: &str = "root.toml";
+
+/// Environment variables from before the config file existed, mapped onto their new keys.
+const LEGACY_ENV_VARS: [(&str, &str); 2] = [
+    ("DATABASE_URL", "ROOT_DATABASE__URL"),
+    ("ROOT_PORT", "ROOT_SERVER__PORT"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Environment {
+    #[default]
+    Development,
+    Production,
+}
+
+/// Everything Root can be configured with. Values are layered, each overriding the last:
+/// defaults, then `root.toml` (or the file passed with `--config`), then `ROOT_*` environment
+/// variables with `__` separating sections, e.g. `ROOT_DATABASE__MAX_CONNECTIONS=5`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub env: Environment,
+    /// Used to verify the origin of attendance mutations.
+    pub secret: String,
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub cors: CorsConfig,
+    pub scheduler: SchedulerConfig,
+    pub logging: LoggingConfig,
+    pub telemetry: TelemetryConfig,
+    pub limits: LimitsConfig,
+    pub persisted_queries: PersistedQueriesConfig,
+    pub attendance: AttendancePolicy,
+    pub webhooks: WebhooksConfig,
+    pub notifier: NotifierConfig,
+    pub email: EmailConfig,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    /// How long to wait for in-flight requests and background tasks on shutdown.
+    pub shutdown_timeout_secs: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: "0.0.0.0".to_string(),
+            port: 3000,
+            shutdown_timeout_secs: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub min_connections: u32,
+    pub max_connections: u32,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            min_connections: 2,
+            max_connections: 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CorsConfig {
+    pub allowed_origins: Vec<String>,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec![
+                "http://127.0.0.1:3000".to_string(),
+                "https://home.amfoss.in".to_string(),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    pub enabled: bool,
+    /// Local time, in the club's timezone, at which the daily task runs.
+    pub run_at: NaiveTime,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            run_at: NaiveTime::from_hms_opt(0, 30, 0).expect("Hardcoded time must be valid"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Pretty,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Never,
+    /// At midnight server time.
+    #[default]
+    Daily,
+    /// Once the file reaches `max_file_size_mb`.
+    Size,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// An `EnvFilter` directive. Defaults to `trace` in development and `info` in production.
+    pub level: Option<String>,
+    pub format: LogFormat,
+    pub file: PathBuf,
+    pub rotation: LogRotation,
+    pub max_file_size_mb: u64,
+    /// Rotated files to keep besides the current one, as `root.log.1`, `root.log.2` and so on.
+    pub retained_files: usize,
+    /// Also log to stdout. Defaults to on in development only, since nobody watches it in production.
+    pub stdout: Option<bool>,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: None,
+            format: LogFormat::Pretty,
+            file: PathBuf::from("root.log"),
+            rotation: LogRotation::Daily,
+            max_file_size_mb: 10,
+            retained_files: 7,
+            stdout: None,
+        }
+    }
+}
+
+/// Guards against expensive queries and clients sending too many requests.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LimitsConfig {
+    pub max_depth: usize,
+    /// Roughly the number of fields a query resolves, with nested lists counted several times over.
+    pub max_complexity: usize,
+    pub anonymous_requests_per_minute: u32,
+    pub authenticated_requests_per_minute: u32,
+    /// Identify anonymous clients by `X-Forwarded-For`. Only safe behind a proxy that sets it,
+    /// otherwise clients can pick their own address.
+    pub trust_forwarded_for: bool,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            // Deep enough for GraphiQL's introspection query.
+            max_depth: 15,
+            max_complexity: 500,
+            anonymous_requests_per_minute: 120,
+            authenticated_requests_per_minute: 1200,
+            trust_forwarded_for: false,
+        }
+    }
+}
+
+/// Lets clients send a query's SHA-256 hash instead of its text, and can restrict non-admin
+/// callers to the operations first-party clients are known to send.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PersistedQueriesConfig {
+    /// Remember queries that clients send along with their hash (Apollo's automatic persisted
+    /// queries), so later requests can send just the hash.
+    pub automatic: bool,
+    /// How many automatically persisted queries to remember, dropping the least recently used.
+    pub cache_size: usize,
+    /// An Apollo persisted query manifest of the operations Home, amD and Presense send.
+    pub manifest: Option<PathBuf>,
+    /// Only run operations from `manifest`, unless the caller has an admin API key.
+    pub allowlist_only: bool,
+}
+
+impl Default for PersistedQueriesConfig {
+    fn default() -> Self {
+        Self {
+            automatic: true,
+            cache_size: 1000,
+            manifest: None,
+            allowlist_only: false,
+        }
+    }
+}
+
+/// Delivery of webhook events to the endpoints admins register.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WebhooksConfig {
+    /// Deliver queued events. Events are still queued while this is off.
+    pub enabled: bool,
+    pub poll_interval_secs: u64,
+    /// Deliveries attempted at once.
+    pub batch_size: u32,
+    /// How long to wait for an endpoint to respond.
+    pub timeout_secs: u64,
+    /// Attempts after which a delivery is given up on.
+    pub max_attempts: u32,
+    /// The wait before the first retry, doubling with each one after up to `max_backoff_secs`.
+    pub initial_backoff_secs: u64,
+    pub max_backoff_secs: u64,
+    /// Days to keep events and their delivery logs once there is nothing left to deliver.
+    pub retention_days: u32,
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            poll_interval_secs: 5,
+            batch_size: 20,
+            timeout_secs: 10,
+            max_attempts: 8,
+            initial_backoff_secs: 30,
+            max_backoff_secs: 6 * 60 * 60,
+            retention_days: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifierSinkKind {
+    #[default]
+    Discord,
+    /// Write digests to the log instead of sending them anywhere.
+    Log,
+}
+
+/// Daily digests of absentees, streaks at risk and new members, posted to each group's channel.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NotifierConfig {
+    /// Send digests after each scheduled run of the daily task.
+    pub enabled: bool,
+    pub sink: NotifierSinkKind,
+    /// Consecutive days absent after which a member is listed.
+    pub absent_days: i32,
+    /// The shortest streak worth warning about before it breaks.
+    pub streak_at_risk_min: i32,
+    pub timeout_secs: u64,
+    pub discord: DiscordConfig,
+    pub templates: NotifierTemplates,
+}
+
+impl Default for NotifierConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sink: NotifierSinkKind::default(),
+            absent_days: 3,
+            streak_at_risk_min: 3,
+            timeout_secs: 10,
+            discord: DiscordConfig::default(),
+            templates: NotifierTemplates::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DiscordConfig {
+    /// Where digests go for groups without a webhook of their own. Unset to skip those groups.
+    pub default_webhook_url: Option<String>,
+    /// Discord webhook URLs by group ID, so each group's digest goes to its own channel.
+    pub group_webhook_urls: HashMap<String, String>,
+}
+
+/// The lines of a digest. `{placeholders}` are filled in for each group or member.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NotifierTemplates {
+    /// `{group}` and `{date}`.
+    pub header: String,
+    /// `{name}`, `{mention}` and `{days}`.
+    pub absentee: String,
+    /// `{name}`, `{mention}` and `{streak}`.
+    pub streak_at_risk: String,
+    /// `{name}`, `{mention}` and `{year}`.
+    pub new_member: String,
+}
+
+impl Default for NotifierTemplates {
+    fn default() -> Self {
+        Self {
+            header: "**{group}**: daily digest for {date}".to_string(),
+            absentee: ":warning: {mention} hasn't been to the lab in {days} days".to_string(),
+            streak_at_risk: ":fire: {mention}'s {streak} day status update streak breaks without an update today".to_string(),
+            new_member: ":wave: Welcome {mention} (year {year}) to the group!".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTls {
+    /// Plaintext, for a local stand-in such as MailHog.
+    None,
+    /// Upgrade a plaintext connection, usually on port 587.
+    #[default]
+    Starttls,
+    /// TLS from the start, usually on port 465.
+    Tls,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub tls: SmtpTls,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub timeout_secs: u64,
+}
+
+impl Default for SmtpConfig {
+    fn default() -> Self {
+        Self {
+            host: "localhost".to_string(),
+            port: 587,
+            tls: SmtpTls::default(),
+            username: None,
+            password: None,
+            timeout_secs: 10,
+        }
+    }
+}
+
+/// Welcome emails, weekly attendance reports to members and monthly summaries to mentors.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EmailConfig {
+    /// Send queued emails. Emails are still queued while this is off.
+    pub enabled: bool,
+    /// The sender, e.g. `amFOSS <root@amfoss.in>`.
+    pub from: String,
+    pub smtp: SmtpConfig,
+    /// Overrides for the built-in templates, named like those in `templates/email`.
+    pub templates_dir: Option<PathBuf>,
+    pub poll_interval_secs: u64,
+    /// Emails sent per poll.
+    pub batch_size: u32,
+    /// Attempts after which an email is given up on.
+    pub max_attempts: u32,
+    /// The wait before the first retry, doubling with each one after up to `max_backoff_secs`.
+    pub initial_backoff_secs: u64,
+    pub max_backoff_secs: u64,
+    /// Days to keep emails, sent or not.
+    pub retention_days: u32,
+}
+
+impl Default for EmailConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            from: "amFOSS <root@amfoss.in>".to_string(),
+            smtp: SmtpConfig::default(),
+            templates_dir: None,
+            poll_interval_secs: 10,
+            batch_size: 20,
+            max_attempts: 5,
+            initial_backoff_secs: 60,
+            max_backoff_secs: 6 * 60 * 60,
+            retention_days: 30,
+        }
+    }
+}
+
+/// OpenTelemetry trace export. Off unless an OTLP collector is available.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    pub enabled: bool,
+    /// An OTLP/HTTP traces endpoint.
+    pub endpoint: String,
+    pub service_name: String,
+    /// Fraction of traces to keep, between 0 and 1.
+    pub sample_ratio: f64,
+    /// An `EnvFilter` directive for which spans are exported, independent of `logging.level`.
+    pub level: String,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: "http://localhost:4318/v1/traces".to_string(),
+            service_name: "root".to_string(),
+            sample_ratio: 1.0,
+            level: "info,sqlx::query=debug".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AttendancePolicy {
+    /// The club's timezone. Days, the daily task's schedule and log timestamps follow it.
+    pub timezone: Tz,
+    /// Members in this year graduate into alumni on rollover instead of being promoted.
+    pub final_year: i32,
+    /// Scans older than this are refused, so a signed batch can't rewrite attendance from long ago.
+    pub max_scan_age_hours: u32,
+}
+
+impl Default for AttendancePolicy {
+    fn default() -> Self {
+        Self {
+            timezone: chrono_tz::Asia::Kolkata,
+            final_year: 4,
+            max_scan_age_hours: 6,
+        }
+    }
+}
+
+impl Config {
+    /// Loads and validates the layered configuration. `path` must exist if given.
+    pub fn load(path: Option<&Path>) -> Result<Self, String> {
+        let _ = dotenv::dotenv();
+
+        let file = match path {
+            Some(path) => ::config::File::from(path).required(true),
+            None => ::config::File::with_name(DEFAULT_CONFIG_FILE).required(false),
+        };
+        let legacy_env: HashMap<String, String> = LEGACY_ENV_VARS
+            .iter()
+            .filter_map(|(old, new)| Some((new.to_string(), std::env::var(old).ok()?)))
+            .collect();
+
+        let config: Config = ::config::Config::builder()
+            .add_source(file)
+            .add_source(root_env().source(Some(legacy_env)))
+            .add_source(root_env())
+            .build()
+            .and_then(|c| c.try_deserialize())
+            .map_err(|e| e.to_string())?;
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.secret.is_empty() {
+            return Err("`secret` must be set, e.g. with ROOT_SECRET".to_string());
+        }
+        if self.database.url.is_empty() {
+            return Err("`database.url` must be set, e.g. with DATABASE_URL".to_string());
+        }
+        if self.database.max_connections == 0
+            || self.database.min_connections > self.database.max_connections
+        {
+            return Err(format!(
+                "`database.min_connections` ({}) must not exceed `database.max_connections` ({}), which must be positive",
+                self.database.min_connections, self.database.max_connections
+            ));
+        }
+        for origin in &self.cors.allowed_origins {
+            HeaderValue::from_str(origin).map_err(|_| {
+                format!("`cors.allowed_origins` has an invalid origin '{}'", origin)
+            })?;
+        }
+        EnvFilter::try_new(self.log_level())
+            .map_err(|e| format!("`logging.level` is not a valid filter: {}", e))?;
+        if self.logging.rotation == LogRotation::Size && self.logging.max_file_size_mb == 0 {
+            return Err("`logging.max_file_size_mb` must be positive".to_string());
+        }
+        if self.telemetry.enabled {
+            reqwest::Url::parse(&self.telemetry.endpoint)
+                .map_err(|e| format!("`telemetry.endpoint` is not a valid URL: {}", e))?;
+            EnvFilter::try_new(&self.telemetry.level)
+                .map_err(|e| format!("`telemetry.level` is not a valid filter: {}", e))?;
+        }
+        if !(0.0..=1.0).contains(&self.telemetry.sample_ratio) {
+            return Err("`telemetry.sample_ratio` must be between 0 and 1".to_string());
+        }
+        if self.limits.anonymous_requests_per_minute == 0
+            || self.limits.authenticated_requests_per_minute == 0
+        {
+            return Err("Rate limits in `limits` must be positive".to_string());
+        }
+        if self.persisted_queries.automatic && self.persisted_queries.cache_size == 0 {
+            return Err("`persisted_queries.cache_size` must be positive".to_string());
+        }
+        if self.persisted_queries.allowlist_only && self.persisted_queries.manifest.is_none() {
+            return Err(
+                "`persisted_queries.allowlist_only` needs a `persisted_queries.manifest`"
+                    .to_string(),
+            );
+        }
+        if self.webhooks.poll_interval_secs == 0
+            || self.webhooks.batch_size == 0
+            || self.webhooks.timeout_secs == 0
+            || self.webhooks.max_attempts == 0
+        {
+            return Err(
+                "`webhooks.poll_interval_secs`, `batch_size`, `timeout_secs` and `max_attempts` must be positive"
+                    .to_string(),
+            );
+        }
+        if self.notifier.absent_days < 1 || self.notifier.streak_at_risk_min < 1 {
+            return Err(
+                "`notifier.absent_days` and `notifier.streak_at_risk_min` must be positive"
+                    .to_string(),
+            );
+        }
+        if self.notifier.timeout_secs == 0 {
+            return Err("`notifier.timeout_secs` must be positive".to_string());
+        }
+        let discord = &self.notifier.discord;
+        for (group, url) in discord
+            .default_webhook_url
+            .iter()
+            .map(|url| ("default", url))
+            .chain(
+                discord
+                    .group_webhook_urls
+                    .iter()
+                    .map(|(k, v)| (k.as_str(), v)),
+            )
+        {
+            reqwest::Url::parse(url).map_err(|e| {
+                format!("`notifier.discord` has an invalid URL for {}: {}", group, e)
+            })?;
+        }
+        for group in discord.group_webhook_urls.keys() {
+            group.parse::<i32>().map_err(|_| {
+                format!(
+                    "`notifier.discord.group_webhook_urls` keys must be group IDs, not '{}'",
+                    group
+                )
+            })?;
+        }
+        if self.email.enabled {
+            self.email
+                .from
+                .parse::<lettre::message::Mailbox>()
+                .map_err(|e| format!("`email.from` is not a valid address: {}", e))?;
+        }
+        if self.email.poll_interval_secs == 0
+            || self.email.batch_size == 0
+            || self.email.max_attempts == 0
+            || self.email.smtp.timeout_secs == 0
+        {
+            return Err(
+                "`email.poll_interval_secs`, `batch_size`, `max_attempts` and `smtp.timeout_secs` must be positive"
+                    .to_string(),
+            );
+        }
+        if self.attendance.final_year < 1 {
+            return Err("`attendance.final_year` must be at least 1".to_string());
+        }
+        if self.attendance.max_scan_age_hours == 0 {
+            return Err("`attendance.max_scan_age_hours` must be positive".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn is_dev(&self) -> bool {
+        self.env == Environment::Development
+    }
+
+    pub fn log_level(&self) -> &str {
+        match &self.logging.level {
+            Some(level) => level,
+            None if self.is_dev() => "trace",
+            None => "info",
+        }
+    }
+
+    pub fn log_to_stdout(&self) -> bool {
+        self.logging.stdout.unwrap_or(self.is_dev())
+    }
+
+    /// The effective configuration as TOML, with credentials masked.
+    pub fn to_redacted_toml(&self) -> String {
+        let mut redacted = self.clone();
+        redacted.secret = "<redacted>".to_string();
+        if let Ok(mut url) = reqwest::Url::parse(&redacted.database.url) {
+            if url.password().is_some() {
+                let _ = url.set_password(Some("redacted"));
+                redacted.database.url = url.to_string();
+            }
+        }
+        if redacted.email.smtp.password.is_some() {
+            redacted.email.smtp.password = Some("<redacted>".to_string());
+        }
+        // Anyone with a Discord webhook URL can post to its channel.
+        let discord = &mut redacted.notifier.discord;
+        if let Some(url) = &mut discord.default_webhook_url {
+            *url = "<redacted>".to_string();
+        }
+        for url in discord.group_webhook_urls.values_mut() {
+            *url = "<redacted>".to_string();
+        }
+        toml::to_string_pretty(&redacted).expect("Config must serialize to TOML")
+    }
+}
+
+fn root_env() -> ::config::Environment {
+    ::config::Environment::with_prefix("ROOT")
+        .prefix_separator("_")
+        .separator("__")
+        .try_parsing(true)
+        .list_separator(",")
+        .with_list_parse_key("cors.allowed_origins")
+}
@@ -1,27 +1,56 @@
-use chrono::{Datelike, NaiveDate, NaiveTime};
-use chrono_tz::Asia::Kolkata;
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, TimeDelta, Utc};
+use chrono_tz::Tz;
 use sqlx::PgPool;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tokio::time::sleep_until;
-use tracing::{debug, error, info};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, instrument};
 
+use crate::clock::ClubClock;
+use crate::email;
+use crate::metrics;
 use crate::models::member::Member;
+use crate::notifier::Notifier;
+use crate::webhooks::{self, Event};
 
-pub async fn run_daily_task_at_midnight(pool: Arc<PgPool>) {
+/// When the daily task last ran, shared with the version route.
+#[derive(Debug, Default)]
+pub struct DailyTaskStatus {
+    last_run: Mutex<Option<DateTime<Utc>>>,
+}
+
+impl DailyTaskStatus {
+    /// When the daily task last finished, if it has since Root started.
+    pub fn last_run(&self) -> Option<DateTime<Utc>> {
+        *self
+            .last_run
+            .lock()
+            .expect("Daily task status lock poisoned")
+    }
+
+    fn record_run(&self, at: DateTime<Utc>) {
+        *self
+            .last_run
+            .lock()
+            .expect("Daily task status lock poisoned") = Some(at);
+    }
+}
+
+/// Runs the daily task every day at `run_at` on the club's clock, until `shutdown` is cancelled,
+/// followed by the `notifier`'s digests if there is one. A run that has already started is
+/// allowed to finish.
+pub async fn run_daily_task_at_midnight(
+    pool: Arc<PgPool>,
+    run_at: NaiveTime,
+    clock: ClubClock,
+    notifier: Option<Arc<Notifier>>,
+    status: Arc<DailyTaskStatus>,
+    shutdown: CancellationToken,
+) {
     loop {
-        let now = chrono::Utc::now().with_timezone(&Kolkata);
-        let naive_midnight =
-            NaiveTime::from_hms_opt(00, 30, 00).expect("Hardcoded time must be valid");
-        let today_midnight = now
-            .with_time(naive_midnight)
-            .single()
-            .expect("Hardcoded time must be valid");
-
-        let next_midnight = if now >= today_midnight {
-            today_midnight + chrono::Duration::days(1)
-        } else {
-            today_midnight
-        };
+        let now = clock.now();
+        let next_midnight = next_run(now, run_at);
         debug!("next_midnight: {}", next_midnight);
 
         let duration_until_midnight = next_midnight.signed_duration_since(now);
@@ -29,33 +58,87 @@ pub async fn run_daily_task_at_midnight(pool: Arc<PgPool>) {
         let sleep_duration =
             tokio::time::Duration::from_secs(duration_until_midnight.num_seconds() as u64);
 
-        sleep_until(tokio::time::Instant::now() + sleep_duration).await;
-        execute_daily_task(pool.clone()).await;
+        tokio::select! {
+            _ = sleep_until(tokio::time::Instant::now() + sleep_duration) => {}
+            _ = shutdown.cancelled() => return,
+        }
+        execute_daily_task(pool.clone(), next_midnight.date_naive()).await;
+        if let Some(notifier) = &notifier {
+            notifier.send_digests(next_midnight.date_naive()).await;
+        }
+        status.record_run(clock.now().to_utc());
+    }
+}
+
+/// The first time after `now` that the club's clock reads `run_at`. Where a DST change skips
+/// `run_at`, the run falls an hour later that day.
+pub fn next_run(now: DateTime<Tz>, run_at: NaiveTime) -> DateTime<Tz> {
+    let timezone = now.timezone();
+    let run_on = |date: NaiveDate| {
+        let run_at = date.and_time(run_at);
+        run_at
+            .and_local_timezone(timezone)
+            .earliest()
+            .or_else(|| {
+                (run_at + TimeDelta::hours(1))
+                    .and_local_timezone(timezone)
+                    .earliest()
+            })
+            .expect("Run time must exist in the club's timezone")
+    };
+
+    let today = run_on(now.date_naive());
+    if now < today {
+        today
+    } else {
+        run_on(now.date_naive() + Days::new(1))
     }
 }
 
 /// This function does a number of things, including:
 /// * Insert new attendance records everyday for [`presense`](https://www.github.com/amfoss/presense) to update them later in the day.
 /// * Update the AttendanceSummary table
-async fn execute_daily_task(pool: Arc<PgPool>) {
-    // Members is queried outside of each function to avoid repetition
-    let members = sqlx::query_as::<_, Member>("SELECT * FROM Member")
+/// * Queue the weekly and monthly attendance emails when they're due
+/// * Notify webhooks subscribed to `dailytask.completed`
+///
+/// `today` is the club's local date the task runs for, normally the current one. Returns whether
+/// every attendance record could be inserted.
+#[instrument(name = "daily_task", skip(pool))]
+pub async fn execute_daily_task(pool: Arc<PgPool>, today: NaiveDate) -> bool {
+    let start = Instant::now();
+    // Members is queried outside of each function to avoid repetition.
+    // Alumni no longer attend, so they don't get new records.
+    let members = sqlx::query_as::<_, Member>("SELECT * FROM Member WHERE status = 'active'")
         .fetch_all(&*pool)
         .await;
 
-    match members {
-        Ok(members) => update_attendance(members, &pool).await,
-        // TODO: Handle this
-        Err(e) => error!("Failed to fetch members: {:?}", e),
+    let (member_count, succeeded) = match members {
+        Ok(members) => (
+            members.len(),
+            update_attendance(members, today, &pool).await,
+        ),
+        Err(e) => {
+            error!("Failed to fetch members: {:?}", e);
+            (0, false)
+        }
     };
+    metrics::observe_daily_task(start, succeeded);
+
+    if let Err(e) = email::enqueue_reports(pool.as_ref(), today).await {
+        error!("Failed to queue attendance emails: {:?}", e);
+    }
+
+    let event = Event::daily_task_completed(today, member_count, succeeded);
+    if let Err(e) = webhooks::enqueue(pool.as_ref(), event).await {
+        error!("Failed to queue the dailytask.completed webhook: {:?}", e);
+    }
+    succeeded
 }
 
-async fn update_attendance(members: Vec<Member>, pool: &PgPool) {
-    #[allow(deprecated)]
-    let today = chrono::Utc::now()
-        .with_timezone(&Kolkata)
-        .date()
-        .naive_local();
+/// Returns whether every member's attendance record could be inserted.
+#[instrument(skip_all, fields(members = members.len()))]
+async fn update_attendance(members: Vec<Member>, today: NaiveDate, pool: &PgPool) -> bool {
+    let mut succeeded = true;
     debug!("Updating attendance on {}", today);
 
     for member in members {
@@ -84,21 +167,20 @@ async fn update_attendance(members: Vec<Member>, pool: &PgPool) {
                     "Failed to insert attendance for member ID: {}: {:?}",
                     member.member_id, e
                 );
+                succeeded = false;
             }
         }
         // This could have been called in `execute_daily_task()` but that would require us to loop through members twice.
         // Whether or not inserting attendance failed, Root will attempt to update AttendanceSummary. This can potentially fail too since insertion failed earlier. However, these two do not depend on each other and one of them failing is no reason to avoid trying the other.
-        update_attendance_summary(member.member_id, pool).await;
+        update_attendance_summary(member.member_id, today, pool).await;
     }
+
+    succeeded
 }
 
-async fn update_attendance_summary(member_id: i32, pool: &PgPool) {
+#[instrument(level = "debug", skip(pool))]
+async fn update_attendance_summary(member_id: i32, today: NaiveDate, pool: &PgPool) {
     debug!("Updating summary for member #{}", member_id);
-    #[allow(deprecated)]
-    let today = chrono::Utc::now()
-        .with_timezone(&Kolkata)
-        .date()
-        .naive_local();
     let yesterday = today - chrono::Duration::days(1);
 
     let was_present_yesterday = sqlx::query_scalar::<_, bool>(
@@ -129,6 +211,7 @@ async fn update_attendance_summary(member_id: i32, pool: &PgPool) {
     }
 }
 
+#[instrument(level = "debug", skip(pool))]
 async fn update_days_attended(member_id: i32, today: NaiveDate, pool: &PgPool) {
     // Convert year and month into i32 cause SQLx cannot encode u32 into database types
     let month: i32 = (today.month0() + 1) as i32;
@@ -151,7 +234,7 @@ async fn update_days_attended(member_id: i32, today: NaiveDate, pool: &PgPool) {
 
     match existing_days_attended {
         Ok(Some(days_attended)) => {
-            sqlx::query(
+            let update = sqlx::query(
                 r#"
                     UPDATE AttendanceSummary
                     SET days_attended = days_attended + 1
@@ -164,9 +247,15 @@ async fn update_days_attended(member_id: i32, today: NaiveDate, pool: &PgPool) {
             .bind(year)
             .bind(month)
             .execute(pool)
-            .await
-            .unwrap();
+            .await;
 
+            if let Err(e) = update {
+                error!(
+                    "Failed to update days_attended for member ID {}: {:?}",
+                    member_id, e
+                );
+                return;
+            }
             debug!(
                 "Updated days_attended for member ID: {}. New days_attended: {}",
                 member_id,
@@ -174,7 +263,7 @@ async fn update_days_attended(member_id: i32, today: NaiveDate, pool: &PgPool) {
             );
         }
         Ok(None) => {
-            sqlx::query(
+            let insert = sqlx::query(
                 r#"
                     INSERT INTO AttendanceSummary (member_id, year, month, days_attended)
                     VALUES ($1, $2, $3, 1)
@@ -184,9 +273,15 @@ async fn update_days_attended(member_id: i32, today: NaiveDate, pool: &PgPool) {
             .bind(year)
             .bind(month)
             .execute(pool)
-            .await
-            .unwrap();
+            .await;
 
+            if let Err(e) = insert {
+                error!(
+                    "Failed to create attendance summary for member ID {}: {:?}",
+                    member_id, e
+                );
+                return;
+            }
             debug!(
                 "Created new streak for member ID: {} for the month.",
                 member_id
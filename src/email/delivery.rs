@@ -0,0 +1,287 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{Datelike, Days, NaiveDate, TimeDelta};
+use sqlx::{FromRow, PgPool};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, warn};
+
+use super::{EmailKind, EmailStatus, EmailTemplates, Mailer, OutgoingEmail};
+use crate::clock::ClubClock;
+use crate::config::EmailConfig;
+use crate::metrics::observe_email_send;
+
+/// How long past the time its sends could take a claimed batch stays claimed, to cover rendering
+/// and recording the results.
+const CLAIM_MARGIN: TimeDelta = TimeDelta::seconds(60);
+
+/// A queued email whose next attempt is due, with its recipient.
+#[derive(FromRow)]
+struct DueEmail {
+    email_id: i64,
+    kind: EmailKind,
+    member_id: i32,
+    period_start: Option<NaiveDate>,
+    attempts: i32,
+    name: String,
+    email: String,
+    roll_no: String,
+    group_name: String,
+}
+
+/// Sends queued emails, see [`crate::email`].
+pub struct EmailDispatcher {
+    pool: Arc<PgPool>,
+    mailer: Arc<dyn Mailer>,
+    templates: EmailTemplates,
+    clock: ClubClock,
+    config: EmailConfig,
+}
+
+impl EmailDispatcher {
+    pub fn new(
+        pool: Arc<PgPool>,
+        clock: ClubClock,
+        mailer: Arc<dyn Mailer>,
+        templates: EmailTemplates,
+        config: &EmailConfig,
+    ) -> Self {
+        Self {
+            pool,
+            mailer,
+            templates,
+            clock,
+            config: config.clone(),
+        }
+    }
+
+    /// Checks for due emails every `poll_interval_secs` until `shutdown` is cancelled.
+    pub async fn run(&self, shutdown: CancellationToken) {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.config.poll_interval_secs));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.cancelled() => return,
+            }
+
+            loop {
+                match self.send_due().await {
+                    Ok(attempted) if attempted == self.config.batch_size as usize => continue,
+                    Ok(_) => break,
+                    Err(e) => {
+                        error!("Failed to send emails: {:?}", e);
+                        break;
+                    }
+                }
+            }
+            if let Err(e) = self.prune().await {
+                error!("Failed to prune emails: {:?}", e);
+            }
+        }
+    }
+
+    /// Makes the next attempt at up to `batch_size` due emails and returns how many were
+    /// attempted. Emails being sent by another instance are skipped.
+    pub async fn send_due(&self) -> Result<usize, sqlx::Error> {
+        let now = self.clock.now().to_utc();
+        // Claimed emails aren't due again until the lease runs out, so nothing stays locked while
+        // they are sent. They go out one after another, so it covers every send timing out.
+        let sends = self.config.smtp.timeout_secs * u64::from(self.config.batch_size);
+        let lease_until = now + TimeDelta::seconds(sends as i64) + CLAIM_MARGIN;
+
+        let due = sqlx::query_as::<_, DueEmail>(
+            "WITH claimed AS (
+                SELECT email_id FROM EmailOutbox
+                WHERE status = 'pending'
+                AND (next_attempt_at IS NULL OR next_attempt_at <= $1)
+                ORDER BY email_id
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+             )
+             UPDATE EmailOutbox queued
+             SET next_attempt_at = $3
+             FROM claimed, Member mem, MemberGroup grp
+             WHERE queued.email_id = claimed.email_id
+             AND mem.member_id = queued.member_id
+             AND grp.group_id = mem.group_id
+             RETURNING queued.email_id, queued.kind, queued.member_id, queued.period_start,
+                       queued.attempts, mem.name, mem.email, mem.roll_no,
+                       grp.name AS group_name",
+        )
+        .bind(now)
+        .bind(i64::from(self.config.batch_size))
+        .bind(lease_until)
+        .fetch_all(self.pool.as_ref())
+        .await?;
+
+        for email in &due {
+            let result = match self.compose(email).await {
+                Ok(outgoing) => self.mailer.send(&outgoing).await,
+                Err(e) => Err(format!("Failed to render: {}", e)),
+            };
+
+            let attempted_at = self.clock.now().to_utc();
+            let attempts_made = email.attempts + 1;
+            let (status, next_attempt_at) = if result.is_ok() {
+                (EmailStatus::Sent, None)
+            } else if attempts_made >= self.config.max_attempts as i32 {
+                (EmailStatus::Failed, None)
+            } else {
+                (
+                    EmailStatus::Pending,
+                    Some(attempted_at + self.backoff(attempts_made)),
+                )
+            };
+
+            match status {
+                EmailStatus::Sent => {
+                    debug!("Sent email #{}", email.email_id);
+                    observe_email_send("sent");
+                }
+                EmailStatus::Pending => observe_email_send("retrying"),
+                EmailStatus::Failed => {
+                    warn!(
+                        "Giving up on email #{} to {} after {} attempts",
+                        email.email_id, email.email, attempts_made
+                    );
+                    observe_email_send("failed");
+                }
+            }
+
+            sqlx::query(
+                "UPDATE EmailOutbox
+                 SET attempts = $2, status = $3, next_attempt_at = $4, last_error = $5,
+                     sent_at = CASE WHEN $3 = 'sent' THEN $6 END
+                 WHERE email_id = $1",
+            )
+            .bind(email.email_id)
+            .bind(attempts_made)
+            .bind(status)
+            .bind(next_attempt_at)
+            .bind(result.err())
+            .bind(attempted_at)
+            .execute(self.pool.as_ref())
+            .await?;
+        }
+
+        Ok(due.len())
+    }
+
+    /// Renders `email` from its template with the data it is about.
+    async fn compose(&self, email: &DueEmail) -> Result<OutgoingEmail, sqlx::Error> {
+        let pool = self.pool.as_ref();
+        let period_start = email.period_start.unwrap_or_default();
+
+        let rendered = match email.kind {
+            EmailKind::Welcome => self.templates.welcome.render(
+                &[
+                    ("name", email.name.clone()),
+                    ("group", email.group_name.clone()),
+                    ("roll_no", email.roll_no.clone()),
+                ],
+                &[],
+            ),
+            EmailKind::WeeklyReport => {
+                let week_end = period_start + Days::new(6);
+                let (days_present, days_recorded) = sqlx::query_as::<_, (i32, i32)>(
+                    "SELECT COUNT(*) FILTER (WHERE is_present)::INT, COUNT(*)::INT
+                     FROM Attendance WHERE member_id = $1 AND date BETWEEN $2 AND $3",
+                )
+                .bind(email.member_id)
+                .bind(period_start)
+                .bind(week_end)
+                .fetch_one(pool)
+                .await?;
+                let current_streak = sqlx::query_scalar::<_, i32>(
+                    "SELECT current_streak FROM StatusUpdateStreak WHERE member_id = $1",
+                )
+                .bind(email.member_id)
+                .fetch_optional(pool)
+                .await?
+                .unwrap_or(0);
+
+                self.templates.weekly_report.render(
+                    &[
+                        ("name", email.name.clone()),
+                        ("week_start", period_start.to_string()),
+                        ("week_end", week_end.to_string()),
+                        ("days_present", days_present.to_string()),
+                        ("days_recorded", days_recorded.to_string()),
+                        ("current_streak", current_streak.to_string()),
+                    ],
+                    &[],
+                )
+            }
+            EmailKind::MonthlySummary => {
+                let groups = sqlx::query_scalar::<_, String>(
+                    "SELECT grp.name FROM GroupMentor gm
+                     JOIN MemberGroup grp ON grp.group_id = gm.group_id
+                     WHERE gm.member_id = $1
+                     ORDER BY grp.name",
+                )
+                .bind(email.member_id)
+                .fetch_all(pool)
+                .await?;
+                let rows = sqlx::query_as::<_, (String, String, i32)>(
+                    "SELECT mem.name, grp.name, COALESCE(summary.days_attended, 0) AS days
+                     FROM GroupMentor gm
+                     JOIN MemberGroup grp ON grp.group_id = gm.group_id
+                     JOIN Member mem ON mem.group_id = gm.group_id AND mem.status = 'active'
+                     LEFT JOIN AttendanceSummary summary ON summary.member_id = mem.member_id
+                        AND summary.year = $2 AND summary.month = $3
+                     WHERE gm.member_id = $1
+                     ORDER BY grp.name, days DESC, mem.name",
+                )
+                .bind(email.member_id)
+                .bind(period_start.year())
+                .bind(period_start.month() as i32)
+                .fetch_all(pool)
+                .await?;
+
+                self.templates.monthly_summary.render(
+                    &[
+                        ("name", email.name.clone()),
+                        ("month", period_start.format("%B %Y").to_string()),
+                        ("groups", groups.join(", ")),
+                    ],
+                    &rows
+                        .into_iter()
+                        .map(|(name, group, days)| vec![name, group, days.to_string()])
+                        .collect::<Vec<_>>(),
+                )
+            }
+        };
+
+        Ok(OutgoingEmail {
+            to_name: email.name.clone(),
+            to: email.email.clone(),
+            subject: rendered.subject,
+            text: rendered.text,
+            html: rendered.html,
+        })
+    }
+
+    /// How long to wait before retrying an email that has failed `attempts` times.
+    fn backoff(&self, attempts: i32) -> TimeDelta {
+        let factor = 1u64
+            .checked_shl(attempts.saturating_sub(1) as u32)
+            .unwrap_or(u64::MAX);
+        let secs = self
+            .config
+            .initial_backoff_secs
+            .saturating_mul(factor)
+            .min(self.config.max_backoff_secs);
+        TimeDelta::seconds(secs as i64)
+    }
+
+    /// Deletes emails queued more than `retention_days` ago, whether or not they were sent.
+    pub async fn prune(&self) -> Result<u64, sqlx::Error> {
+        let cutoff = self.clock.now().to_utc() - TimeDelta::days(self.config.retention_days as i64);
+        let result = sqlx::query("DELETE FROM EmailOutbox WHERE created_at < $1")
+            .bind(cutoff)
+            .execute(self.pool.as_ref())
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,86 @@
+//! Email to members: a welcome when they join, a weekly report of their attendance, and a
+//! monthly summary of their groups' attendance for mentors.
+//!
+//! Emails are queued by what they are about, in the same transaction as the change that calls
+//! for them where there is one, and rendered from [`EmailTemplates`] when the
+//! [`EmailDispatcher`] sends them. Members who opt out don't get reports.
+
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use sqlx::{PgExecutor, PgPool};
+
+mod delivery;
+mod mailer;
+mod templates;
+
+pub use delivery::EmailDispatcher;
+pub use mailer::{Mailer, MockMailer, OutgoingEmail, SmtpMailer};
+pub use templates::{EmailTemplates, RenderedEmail, Template};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "email_kind", rename_all = "snake_case")]
+pub enum EmailKind {
+    Welcome,
+    WeeklyReport,
+    MonthlySummary,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "email_status", rename_all = "lowercase")]
+pub enum EmailStatus {
+    /// Not sent yet, but will be tried (again).
+    Pending,
+    Sent,
+    /// Given up on after too many attempts.
+    Failed,
+}
+
+/// Queues a welcome email for a member who has just been created.
+pub async fn enqueue_welcome<'c>(
+    executor: impl PgExecutor<'c>,
+    member_id: i32,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO EmailOutbox (kind, member_id) VALUES ('welcome', $1)")
+        .bind(member_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Queues the reports due on `today`: on Mondays, each active member's report of the week
+/// before, and on the 1st, each mentor's summary of the month before. Reports that are already
+/// queued aren't queued again. Returns how many were queued.
+pub async fn enqueue_reports(pool: &PgPool, today: NaiveDate) -> Result<u64, sqlx::Error> {
+    let mut queued = 0;
+
+    if today.weekday() == Weekday::Mon {
+        let week_start = today - Days::new(7);
+        queued += sqlx::query(
+            "INSERT INTO EmailOutbox (kind, member_id, period_start)
+             SELECT 'weekly_report', member_id, $1 FROM Member
+             WHERE status = 'active' AND NOT email_opt_out
+             ON CONFLICT (kind, member_id, period_start) DO NOTHING",
+        )
+        .bind(week_start)
+        .execute(pool)
+        .await?
+        .rows_affected();
+    }
+
+    if today.day() == 1 {
+        let month_start = today - Months::new(1);
+        queued += sqlx::query(
+            "INSERT INTO EmailOutbox (kind, member_id, period_start)
+             SELECT DISTINCT 'monthly_summary'::email_kind, mem.member_id, $1::DATE
+             FROM GroupMentor gm
+             JOIN Member mem ON mem.member_id = gm.member_id
+             WHERE mem.status = 'active' AND NOT mem.email_opt_out
+             ON CONFLICT (kind, member_id, period_start) DO NOTHING",
+        )
+        .bind(month_start)
+        .execute(pool)
+        .await?
+        .rows_affected();
+    }
+
+    Ok(queued)
+}
@@ -0,0 +1,138 @@
+use std::path::Path;
+
+/// A template's `.txt` file starts with this, followed by the subject.
+const SUBJECT_PREFIX: &str = "Subject:";
+
+/// The subject and bodies of one kind of email, with `{placeholders}` to fill in.
+#[derive(Debug, Clone)]
+pub struct Template {
+    subject: String,
+    text: String,
+    html: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedEmail {
+    pub subject: String,
+    pub text: String,
+    pub html: String,
+}
+
+impl Template {
+    /// `text` must start with a `Subject:` line, which the rest of it follows after a blank line.
+    pub fn parse(text: &str, html: &str) -> Result<Self, String> {
+        let (subject, body) = text.split_once('\n').unwrap_or((text, ""));
+        let subject = subject
+            .strip_prefix(SUBJECT_PREFIX)
+            .ok_or_else(|| format!("must start with a `{}` line", SUBJECT_PREFIX))?;
+        Ok(Self {
+            subject: subject.trim().to_string(),
+            text: body.trim_start_matches(['\r', '\n']).to_string(),
+            html: html.to_string(),
+        })
+    }
+
+    /// Fills in each `{key}` with its value, escaped in the HTML body. `{rows}` becomes `rows` as
+    /// a list in the text body and as table rows in the HTML one.
+    pub fn render(&self, values: &[(&str, String)], rows: &[Vec<String>]) -> RenderedEmail {
+        let text_rows = rows
+            .iter()
+            .map(|row| format!("- {}", row.join(", ")))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let html_rows = rows
+            .iter()
+            .map(|row| {
+                let cells: String = row
+                    .iter()
+                    .map(|cell| format!("<td>{}</td>", escape_html(cell)))
+                    .collect();
+                format!("<tr>{}</tr>", cells)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let fill = |template: &str, escape: bool, rows: &str| {
+            let filled = values
+                .iter()
+                .fold(template.to_string(), |filled, (key, value)| {
+                    let value = if escape {
+                        escape_html(value)
+                    } else {
+                        value.clone()
+                    };
+                    filled.replace(&format!("{{{}}}", key), &value)
+                });
+            filled.replace("{rows}", rows)
+        };
+        RenderedEmail {
+            subject: fill(&self.subject, false, ""),
+            text: fill(&self.text, false, &text_rows),
+            html: fill(&self.html, true, &html_rows),
+        }
+    }
+}
+
+/// A template for each kind of email.
+#[derive(Debug, Clone)]
+pub struct EmailTemplates {
+    pub welcome: Template,
+    pub weekly_report: Template,
+    pub monthly_summary: Template,
+}
+
+impl EmailTemplates {
+    /// The built-in templates, with any of `<name>.txt` and `<name>.html` found in `dir` used
+    /// instead.
+    pub fn load(dir: Option<&Path>) -> Result<Self, String> {
+        let load = |name: &str, text: &str, html: &str| {
+            let read = |file: String, default: &str| match dir.map(|dir| dir.join(&file)) {
+                Some(path) if path.exists() => std::fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e)),
+                _ => Ok(default.to_string()),
+            };
+            let text = read(format!("{}.txt", name), text)?;
+            let html = read(format!("{}.html", name), html)?;
+            Template::parse(&text, &html).map_err(|e| format!("{}.txt {}", name, e))
+        };
+
+        Ok(Self {
+            welcome: load(
+                "welcome",
+                include_str!("../../templates/email/welcome.txt"),
+                include_str!("../../templates/email/welcome.html"),
+            )?,
+            weekly_report: load(
+                "weekly_report",
+                include_str!("../../templates/email/weekly_report.txt"),
+                include_str!("../../templates/email/weekly_report.html"),
+            )?,
+            monthly_summary: load(
+                "monthly_summary",
+                include_str!("../../templates/email/monthly_summary.txt"),
+                include_str!("../../templates/email/monthly_summary.html"),
+            )?,
+        })
+    }
+}
+
+impl Default for EmailTemplates {
+    fn default() -> Self {
+        Self::load(None).expect("Built-in email templates must be valid")
+    }
+}
+
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,201 @@
+use async_graphql::ErrorExtensions;
+use axum::{
+    http::{header::RETRY_AFTER, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use sqlx::error::ErrorKind;
+use tracing::error;
+
+use crate::validation::ValidationError;
+
+pub type Result<T, E = RootError> = std::result::Result<T, E>;
+
+/// Errors returned to clients. Each variant maps to a stable `extensions.code` so Home, amD and
+/// Presense can branch on it instead of parsing messages.
+///
+/// This deliberately doesn't implement `Display`: async-graphql converts every `Display` type
+/// into an error without extensions, which would silently drop the code.
+#[derive(Debug)]
+pub enum RootError {
+    NotFound(String),
+    Conflict {
+        field: Option<&'static str>,
+        message: String,
+    },
+    Unauthenticated(String),
+    Forbidden(String),
+    Validation {
+        field: Option<&'static str>,
+        message: String,
+    },
+    /// The client has used up its request budget and should wait before trying again.
+    RateLimited {
+        retry_after_secs: u64,
+    },
+    /// Details are logged, never sent to the client.
+    Internal,
+}
+
+impl RootError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            RootError::NotFound(_) => "NOT_FOUND",
+            RootError::Conflict { .. } => "CONFLICT",
+            RootError::Unauthenticated(_) => "UNAUTHENTICATED",
+            RootError::Forbidden(_) => "FORBIDDEN",
+            RootError::Validation { .. } => "VALIDATION",
+            RootError::RateLimited { .. } => "RATE_LIMITED",
+            RootError::Internal => "INTERNAL",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            RootError::NotFound(message)
+            | RootError::Unauthenticated(message)
+            | RootError::Forbidden(message)
+            | RootError::Conflict { message, .. }
+            | RootError::Validation { message, .. } => message,
+            RootError::RateLimited { .. } => {
+                "Too many requests, retry after the Retry-After interval"
+            }
+            RootError::Internal => "Internal server error",
+        }
+    }
+
+    fn field(&self) -> Option<&'static str> {
+        match self {
+            RootError::Conflict { field, .. } | RootError::Validation { field, .. } => *field,
+            _ => None,
+        }
+    }
+
+    fn status(&self) -> StatusCode {
+        match self {
+            RootError::NotFound(_) => StatusCode::NOT_FOUND,
+            RootError::Conflict { .. } => StatusCode::CONFLICT,
+            RootError::Unauthenticated(_) => StatusCode::UNAUTHORIZED,
+            RootError::Forbidden(_) => StatusCode::FORBIDDEN,
+            RootError::Validation { .. } => StatusCode::BAD_REQUEST,
+            RootError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            RootError::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// The GraphQL field a unique or foreign key constraint guards, and how to describe a violation.
+fn describe_constraint(constraint: &str) -> Option<(&'static str, &'static str)> {
+    Some(match constraint {
+        "member_roll_no_key" => ("rollNo", "A member with this roll number already exists"),
+        "member_email_key" => ("email", "A member with this email already exists"),
+        "member_mac_address_key" => (
+            "macAddress",
+            "A member with this MAC address already exists",
+        ),
+        "memberdevice_mac_address_key" => (
+            "macAddress",
+            "A device with this MAC address is already registered",
+        ),
+        "fkey_member_device_member" => ("memberId", "Member does not exist"),
+        "member_discord_id_key" => ("discordId", "A member with this Discord ID already exists"),
+        "membergroup_name_key" => ("name", "A group with this name already exists"),
+        "fkey_group" => ("groupId", "Group does not exist"),
+        "fkey_profile_change_member" => ("memberId", "Member does not exist"),
+        "fkey_calendar_date_group" => ("groupId", "Group does not exist"),
+        "fkey_event_organizer" => ("organizerId", "Member does not exist"),
+        "fkey_rsvp_event" | "fkey_event_attendance_event" => ("eventId", "Event does not exist"),
+        "fkey_rsvp_member" | "fkey_event_attendance_member" => {
+            ("memberId", "Member does not exist")
+        }
+        _ => return None,
+    })
+}
+
+impl From<sqlx::Error> for RootError {
+    fn from(e: sqlx::Error) -> Self {
+        if let sqlx::Error::RowNotFound = e {
+            return RootError::NotFound("No matching record was found".to_string());
+        }
+
+        if let Some(db_error) = e.as_database_error() {
+            let described = db_error.constraint().and_then(describe_constraint);
+            let field = described.map(|(field, _)| field);
+            let message = |fallback: &str| {
+                described
+                    .map(|(_, message)| message)
+                    .unwrap_or(fallback)
+                    .to_string()
+            };
+
+            match db_error.kind() {
+                ErrorKind::UniqueViolation => {
+                    return RootError::Conflict {
+                        field,
+                        message: message("A record with these values already exists"),
+                    }
+                }
+                ErrorKind::ForeignKeyViolation => {
+                    return RootError::Validation {
+                        field,
+                        message: message("A referenced record does not exist"),
+                    }
+                }
+                ErrorKind::CheckViolation | ErrorKind::NotNullViolation => {
+                    return RootError::Validation {
+                        field,
+                        message: message("A value is outside the allowed range"),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        error!("Database error: {:?}", e);
+        RootError::Internal
+    }
+}
+
+impl From<std::io::Error> for RootError {
+    fn from(e: std::io::Error) -> Self {
+        error!("I/O error: {:?}", e);
+        RootError::Internal
+    }
+}
+
+impl From<ValidationError> for RootError {
+    fn from(e: ValidationError) -> Self {
+        RootError::Validation {
+            message: format!("Invalid {}: {}", e.field, e.message),
+            field: Some(e.field),
+        }
+    }
+}
+
+impl From<RootError> for async_graphql::Error {
+    fn from(e: RootError) -> Self {
+        async_graphql::Error::new(e.message()).extend_with(|_, extensions| {
+            extensions.set("code", e.code());
+            if let Some(field) = e.field() {
+                extensions.set("field", field);
+            }
+        })
+    }
+}
+
+impl IntoResponse for RootError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({
+            "code": self.code(),
+            "message": self.message(),
+            "field": self.field(),
+        });
+        let mut response = (self.status(), Json(body)).into_response();
+        if let RootError::RateLimited { retry_after_secs } = self {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(retry_after_secs));
+        }
+        response
+    }
+}
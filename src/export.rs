@@ -0,0 +1,434 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{Query, State},
+    http::header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    response::{IntoResponse, Response},
+};
+use chrono::NaiveDate;
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, FromRow, PgPool, Postgres, QueryBuilder};
+use std::io::Write;
+use std::sync::Arc;
+use tracing::error;
+
+use crate::auth::Caller;
+use crate::error::RootError;
+use crate::graphql::queries::member_queries::members_query;
+use crate::models::{
+    attendance::{AttendanceSummary, AttendanceWithMember},
+    member::Member,
+    status_update_streak::StatusUpdateStreak,
+};
+use crate::routes::AppState;
+
+#[derive(Deserialize, Default, Clone, Copy, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+/// The tables `root export` can write, named like their routes.
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum ExportTable {
+    Members,
+    Attendance,
+    AttendanceSummary,
+    Streaks,
+}
+
+/// The filters of every export route. Each table only uses the ones its route takes.
+#[derive(clap::Args)]
+pub struct ExportFilter {
+    #[arg(long)]
+    member_id: Option<i32>,
+    /// Members' year of study, or the calendar year of attendance summaries.
+    #[arg(long)]
+    year: Option<i32>,
+    #[arg(long)]
+    month: Option<i32>,
+    #[arg(long)]
+    group_id: Option<i32>,
+    #[arg(long)]
+    include_alumni: bool,
+    #[arg(long)]
+    start_date: Option<NaiveDate>,
+    #[arg(long)]
+    end_date: Option<NaiveDate>,
+}
+
+/// A table row as exported, with its CSV header.
+pub trait ExportRow: for<'r> FromRow<'r, PgRow> + Serialize + Send + Unpin + 'static {
+    /// The serialized field names, in order, so the header is written even when there are no
+    /// rows.
+    const COLUMNS: &'static [&'static str];
+}
+
+impl ExportRow for Member {
+    const COLUMNS: &'static [&'static str] = &[
+        "member_id",
+        "roll_no",
+        "name",
+        "email",
+        "sex",
+        "year",
+        "hostel",
+        "mac_address",
+        "discord_id",
+        "group_id",
+        "status",
+        "graduation_year",
+        "email_opt_out",
+    ];
+}
+
+impl ExportRow for AttendanceWithMember {
+    const COLUMNS: &'static [&'static str] = &[
+        "attendance_id",
+        "member_id",
+        "date",
+        "is_present",
+        "time_in",
+        "time_out",
+        "name",
+        "year",
+    ];
+}
+
+impl ExportRow for AttendanceSummary {
+    const COLUMNS: &'static [&'static str] = &["member_id", "year", "month", "days_attended"];
+}
+
+impl ExportRow for StatusUpdateStreak {
+    const COLUMNS: &'static [&'static str] = &["member_id", "current_streak", "max_streak"];
+}
+
+#[derive(Deserialize)]
+pub struct FormatParam {
+    #[serde(default)]
+    format: ExportFormat,
+}
+
+/// Same arguments as the `members` query.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MembersFilter {
+    year: Option<i32>,
+    group_id: Option<i32>,
+    #[serde(default)]
+    include_alumni: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttendanceFilter {
+    member_id: Option<i32>,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryFilter {
+    member_id: Option<i32>,
+    year: Option<i32>,
+    month: Option<i32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreakFilter {
+    member_id: Option<i32>,
+}
+
+/// Every member's contact details and MAC address, so only admins may export them.
+pub async fn export_members(
+    caller: Caller,
+    State(state): State<AppState>,
+    Query(FormatParam { format }): Query<FormatParam>,
+    Query(filter): Query<MembersFilter>,
+) -> Result<Response, RootError> {
+    if !caller.is_admin() {
+        return Err(RootError::Forbidden(
+            "Exporting members needs an admin API key".to_string(),
+        ));
+    }
+    let query = members_export_query(filter.year, filter.group_id, filter.include_alumni);
+    Ok(stream_rows::<Member>(state, query, format, "members"))
+}
+
+pub async fn export_attendance(
+    caller: Caller,
+    State(state): State<AppState>,
+    Query(FormatParam { format }): Query<FormatParam>,
+    Query(filter): Query<AttendanceFilter>,
+) -> Result<Response, RootError> {
+    let member_id = own_records(&caller, filter.member_id)?;
+    let query = attendance_query(member_id, filter.start_date, filter.end_date);
+    Ok(stream_rows::<AttendanceWithMember>(
+        state,
+        query,
+        format,
+        "attendance",
+    ))
+}
+
+pub async fn export_attendance_summary(
+    caller: Caller,
+    State(state): State<AppState>,
+    Query(FormatParam { format }): Query<FormatParam>,
+    Query(filter): Query<SummaryFilter>,
+) -> Result<Response, RootError> {
+    let member_id = own_records(&caller, filter.member_id)?;
+    let query = attendance_summary_query(member_id, filter.year, filter.month);
+    Ok(stream_rows::<AttendanceSummary>(
+        state,
+        query,
+        format,
+        "attendance-summary",
+    ))
+}
+
+pub async fn export_streaks(
+    caller: Caller,
+    State(state): State<AppState>,
+    Query(FormatParam { format }): Query<FormatParam>,
+    Query(filter): Query<StreakFilter>,
+) -> Result<Response, RootError> {
+    let member_id = own_records(&caller, filter.member_id)?;
+    let query = streaks_query(member_id);
+    Ok(stream_rows::<StatusUpdateStreak>(
+        state, query, format, "streaks",
+    ))
+}
+
+/// The `memberId` filter an export runs with. Admins can export anyone's records, while member
+/// keys only get the records of the member they were issued to.
+fn own_records(caller: &Caller, member_id: Option<i32>) -> Result<Option<i32>, RootError> {
+    if caller.is_admin() {
+        return Ok(member_id);
+    }
+    match (caller.member_id, member_id) {
+        (Some(own), None) => Ok(Some(own)),
+        (Some(own), Some(requested)) if own == requested => Ok(Some(own)),
+        _ => Err(RootError::Forbidden(
+            "Member API keys can only export their own member's records".to_string(),
+        )),
+    }
+}
+
+/// Writes a table the same way its export route would send it.
+pub async fn write_export(
+    pool: Arc<PgPool>,
+    table: ExportTable,
+    filter: &ExportFilter,
+    format: ExportFormat,
+    writer: &mut impl Write,
+) -> Result<(), axum::BoxError> {
+    let mut rows = match table {
+        ExportTable::Members => encoded_rows::<Member>(
+            pool,
+            members_export_query(filter.year, filter.group_id, filter.include_alumni),
+            format,
+        )
+        .boxed(),
+        ExportTable::Attendance => encoded_rows::<AttendanceWithMember>(
+            pool,
+            attendance_query(filter.member_id, filter.start_date, filter.end_date),
+            format,
+        )
+        .boxed(),
+        ExportTable::AttendanceSummary => encoded_rows::<AttendanceSummary>(
+            pool,
+            attendance_summary_query(filter.member_id, filter.year, filter.month),
+            format,
+        )
+        .boxed(),
+        ExportTable::Streaks => {
+            encoded_rows::<StatusUpdateStreak>(pool, streaks_query(filter.member_id), format)
+                .boxed()
+        }
+    };
+
+    while let Some(bytes) = rows.next().await {
+        writer.write_all(&bytes?)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn members_export_query(
+    year: Option<i32>,
+    group_id: Option<i32>,
+    include_alumni: bool,
+) -> QueryBuilder<'static, Postgres> {
+    let mut query = members_query(year, group_id, include_alumni);
+    query.push(" ORDER BY member_id");
+    query
+}
+
+fn attendance_query(
+    member_id: Option<i32>,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+) -> QueryBuilder<'static, Postgres> {
+    let mut query = QueryBuilder::new(
+        "SELECT att.attendance_id, att.member_id, att.date, att.is_present,
+                att.time_in, att.time_out, mem.name, mem.year
+         FROM Attendance att
+         JOIN Member mem ON att.member_id = mem.member_id
+         WHERE 1=1",
+    );
+    if let Some(member_id) = member_id {
+        query.push(" AND att.member_id = ");
+        query.push_bind(member_id);
+    }
+    if let Some(start_date) = start_date {
+        query.push(" AND att.date >= ");
+        query.push_bind(start_date);
+    }
+    if let Some(end_date) = end_date {
+        query.push(" AND att.date <= ");
+        query.push_bind(end_date);
+    }
+    query.push(" ORDER BY att.date, att.member_id");
+
+    query
+}
+
+fn attendance_summary_query(
+    member_id: Option<i32>,
+    year: Option<i32>,
+    month: Option<i32>,
+) -> QueryBuilder<'static, Postgres> {
+    let mut query = QueryBuilder::new("SELECT * FROM AttendanceSummary WHERE 1=1");
+    if let Some(member_id) = member_id {
+        query.push(" AND member_id = ");
+        query.push_bind(member_id);
+    }
+    if let Some(year) = year {
+        query.push(" AND year = ");
+        query.push_bind(year);
+    }
+    if let Some(month) = month {
+        query.push(" AND month = ");
+        query.push_bind(month);
+    }
+    query.push(" ORDER BY year, month, member_id");
+
+    query
+}
+
+fn streaks_query(member_id: Option<i32>) -> QueryBuilder<'static, Postgres> {
+    let mut query = QueryBuilder::new("SELECT * FROM StatusUpdateStreak WHERE 1=1");
+    if let Some(member_id) = member_id {
+        query.push(" AND member_id = ");
+        query.push_bind(member_id);
+    }
+    query.push(" ORDER BY member_id");
+
+    query
+}
+
+/// Streams the rows of `query` to the client as they are fetched instead of buffering the table.
+///
+/// If the database fails mid-way the body is cut short, which clients see as an aborted download.
+fn stream_rows<T>(
+    state: AppState,
+    query: QueryBuilder<'static, Postgres>,
+    format: ExportFormat,
+    name: &str,
+) -> Response
+where
+    T: ExportRow,
+{
+    let body = encoded_rows::<T>(state.pool, query, format);
+
+    let (content_type, extension) = match format {
+        ExportFormat::Csv => ("text/csv; charset=utf-8", "csv"),
+        ExportFormat::Json => ("application/json", "json"),
+    };
+    (
+        [
+            (CONTENT_TYPE, content_type.to_string()),
+            (
+                CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.{}\"", name, extension),
+            ),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response()
+}
+
+/// Encodes the rows of `query` one at a time as they are fetched. The stream ends after the first
+/// error.
+fn encoded_rows<T>(
+    pool: Arc<PgPool>,
+    mut query: QueryBuilder<'static, Postgres>,
+    format: ExportFormat,
+) -> impl Stream<Item = Result<Bytes, axum::BoxError>> + Send
+where
+    T: ExportRow,
+{
+    async_stream::stream! {
+        let mut rows = query.build_query_as::<T>().fetch(pool.as_ref());
+        let mut first = true;
+
+        match format {
+            ExportFormat::Csv => yield encode_header(T::COLUMNS),
+            ExportFormat::Json => yield Ok(Bytes::from_static(b"[")),
+        }
+        while let Some(row) = rows.next().await {
+            let row = match row {
+                Ok(row) => row,
+                Err(e) => {
+                    error!("Export failed while fetching rows: {:?}", e);
+                    yield Err(e.into());
+                    break;
+                }
+            };
+            match encode_row(&row, format, first) {
+                Ok(bytes) => yield Ok(bytes),
+                Err(e) => {
+                    error!("Export failed while encoding a row: {:?}", e);
+                    yield Err(e);
+                    break;
+                }
+            }
+            first = false;
+        }
+        if let ExportFormat::Json = format {
+            yield Ok(Bytes::from_static(b"]"));
+        }
+    }
+}
+
+fn encode_header(columns: &[&str]) -> Result<Bytes, axum::BoxError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(columns)?;
+    Ok(Bytes::from(writer.into_inner()?))
+}
+
+fn encode_row<T: Serialize>(
+    row: &T,
+    format: ExportFormat,
+    first: bool,
+) -> Result<Bytes, axum::BoxError> {
+    match format {
+        ExportFormat::Csv => {
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(false)
+                .from_writer(Vec::new());
+            writer.serialize(row)?;
+            Ok(Bytes::from(writer.into_inner()?))
+        }
+        ExportFormat::Json => {
+            let mut bytes = if first { Vec::new() } else { vec![b','] };
+            serde_json::to_writer(&mut bytes, row)?;
+            Ok(Bytes::from(bytes))
+        }
+    }
+}
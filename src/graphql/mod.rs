@@ -1,6 +1,12 @@
 use async_graphql::MergedObject;
-use mutations::{AttendanceMutations, MemberMutations, ProjectMutations, StreakMutations};
-use queries::{AttendanceQueries, MemberQueries, ProjectQueries, StreakQueries};
+use mutations::{
+    AttendanceMutations, CalendarMutations, DeviceMutations, EventMutations, GroupMutations,
+    MemberMutations, ProjectMutations, StreakMutations, WebhookMutations,
+};
+use queries::{
+    AttendanceQueries, CalendarQueries, EventQueries, GroupQueries, MemberQueries, ProjectQueries,
+    StreakQueries, WebhookQueries,
+};
 
 pub mod mutations;
 pub mod queries;
@@ -11,6 +17,10 @@ pub struct Query(
     AttendanceQueries,
     StreakQueries,
     ProjectQueries,
+    GroupQueries,
+    WebhookQueries,
+    EventQueries,
+    CalendarQueries,
 );
 
 #[derive(MergedObject, Default)]
@@ -19,4 +29,9 @@ pub struct Mutation(
     AttendanceMutations,
     StreakMutations,
     ProjectMutations,
+    GroupMutations,
+    WebhookMutations,
+    EventMutations,
+    CalendarMutations,
+    DeviceMutations,
 );
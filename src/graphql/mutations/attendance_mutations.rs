@@ -1,15 +1,14 @@
 use std::sync::Arc;
 
-use async_graphql::{Context, Object, Result};
-use chrono::Local;
-use chrono_tz::Asia::Kolkata;
-use hmac::{Hmac, Mac};
-use sha2::Sha256;
+use async_graphql::{Context, Object};
 use sqlx::PgPool;
 
+use crate::auth::verify_signature;
+use crate::clock::ClubClock;
+use crate::error::{Result, RootError};
+use crate::metrics::observe_attendance_mark;
 use crate::models::attendance::{Attendance, MarkAttendanceInput};
-
-type HmacSha256 = Hmac<Sha256>;
+use crate::webhooks::{self, Event};
 
 #[derive(Default)]
 pub struct AttendanceMutations;
@@ -30,34 +29,54 @@ impl AttendanceMutations {
             .data::<String>()
             .expect("ROOT_SECRET must be found in context");
 
-        let mut mac = HmacSha256::new_from_slice(secret_key.as_bytes())
-            .expect("HMAC can take key of any size");
         let message = format!("{}{}", input.member_id, input.date);
-        mac.update(message.as_bytes());
-
-        let expected_signature = mac.finalize().into_bytes();
-        let received_signature = hex::decode(input.hmac_signature)?;
+        verify_signature(secret_key, &message, &input.hmac_signature)
+            .inspect_err(|_| observe_attendance_mark("hmac_failure"))?;
 
-        if expected_signature.as_slice() != received_signature.as_slice() {
-            return Err(async_graphql::Error::new("HMAC verification failed"));
+        let now = ctx
+            .data::<ClubClock>()
+            .expect("Clock must be in context.")
+            .time();
+        let attendance = async {
+            let mut tx = pool.begin().await?;
+            let attendance = sqlx::query_as::<_, Attendance>(
+                "UPDATE Attendance SET time_in = CASE 
+                    WHEN time_in IS NULL THEN $1 
+                    ELSE time_in END,
+                 time_out = $1,
+                 is_present = TRUE
+                 WHERE member_id = $2 AND date = $3 RETURNING *
+                ",
+            )
+            .bind(now)
+            .bind(input.member_id)
+            .bind(input.date)
+            .fetch_optional(&mut *tx)
+            .await?;
+            if let Some(attendance) = &attendance {
+                webhooks::enqueue(&mut *tx, Event::attendance_marked(attendance)).await?;
+            }
+            tx.commit().await?;
+            Ok::<_, sqlx::Error>(attendance)
         }
+        .await;
 
-        let now = Local::now().with_timezone(&Kolkata).time();
-        let attendance = sqlx::query_as::<_, Attendance>(
-            "UPDATE Attendance SET time_in = CASE 
-                WHEN time_in IS NULL THEN $1 
-                ELSE time_in END,
-             time_out = $1,
-             is_present = TRUE
-             WHERE member_id = $2 AND date = $3 RETURNING *
-            ",
-        )
-        .bind(now)
-        .bind(input.member_id)
-        .bind(input.date)
-        .fetch_one(pool.as_ref())
-        .await?;
-
-        Ok(attendance)
+        match attendance {
+            Ok(Some(attendance)) => {
+                observe_attendance_mark("success");
+                Ok(attendance)
+            }
+            Ok(None) => {
+                observe_attendance_mark("not_found");
+                Err(RootError::NotFound(format!(
+                    "No attendance record for member {} on {}",
+                    input.member_id, input.date
+                )))
+            }
+            Err(e) => {
+                observe_attendance_mark("error");
+                Err(e.into())
+            }
+        }
     }
 }
@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Object};
+use rand::RngCore;
+use sqlx::PgPool;
+
+use crate::auth::{hash_key, AdminGuard, MemberGuard};
+use crate::error::{Result, RootError};
+use crate::models::calendar::{AddCalendarDateInput, CalendarDate};
+
+#[derive(Default)]
+pub struct CalendarMutations;
+
+#[Object]
+impl CalendarMutations {
+    /// Adds a holiday or deadline to the calendar feeds.
+    #[graphql(name = "addCalendarDate", guard = "AdminGuard")]
+    async fn add_calendar_date(
+        &self,
+        ctx: &Context<'_>,
+        input: AddCalendarDateInput,
+    ) -> Result<CalendarDate> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let input = input.validate()?;
+
+        Ok(sqlx::query_as::<_, CalendarDate>(
+            "INSERT INTO CalendarDate (date, title, description, kind, group_id)
+             VALUES ($1, $2, $3, $4, $5) RETURNING *",
+        )
+        .bind(input.date)
+        .bind(&input.title)
+        .bind(&input.description)
+        .bind(input.kind)
+        .bind(input.group_id)
+        .fetch_one(pool.as_ref())
+        .await?)
+    }
+
+    #[graphql(name = "deleteCalendarDate", guard = "AdminGuard")]
+    async fn delete_calendar_date(&self, ctx: &Context<'_>, date_id: i32) -> Result<CalendarDate> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        sqlx::query_as::<_, CalendarDate>("DELETE FROM CalendarDate WHERE date_id = $1 RETURNING *")
+            .bind(date_id)
+            .fetch_optional(pool.as_ref())
+            .await?
+            .ok_or_else(|| RootError::NotFound(format!("Calendar date {} does not exist", date_id)))
+    }
+
+    /// Returns the path of a new secret feed URL for the member, e.g.
+    /// `/calendar/members/<token>.ics`. Any earlier URL stops working, and this one cannot be
+    /// shown again.
+    #[graphql(
+        name = "createCalendarFeedToken",
+        guard = "MemberGuard::new(member_id)"
+    )]
+    async fn create_calendar_feed_token(
+        &self,
+        ctx: &Context<'_>,
+        member_id: i32,
+    ) -> Result<String> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let token = hex::encode(bytes);
+
+        sqlx::query(
+            "INSERT INTO CalendarFeedToken (member_id, token_hash) VALUES ($1, $2)
+             ON CONFLICT (member_id) DO UPDATE
+                SET token_hash = EXCLUDED.token_hash, created_at = CURRENT_TIMESTAMP",
+        )
+        .bind(member_id)
+        .bind(hash_key(&token))
+        .execute(pool.as_ref())
+        .await?;
+
+        Ok(format!("/calendar/members/{}.ics", token))
+    }
+}
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Object};
+use sqlx::PgPool;
+
+use crate::auth::{AdminGuard, Caller, MemberGuard};
+use crate::error::{Result, RootError};
+use crate::models::device::{AddDeviceInput, MemberDevice};
+
+#[derive(Default)]
+pub struct DeviceMutations;
+
+#[Object]
+impl DeviceMutations {
+    /// Registers another device for a member. Devices members add themselves aren't matched to
+    /// them until an admin verifies them, while those added by admins are verified already.
+    #[graphql(name = "addDevice", guard = "MemberGuard::new(input.member_id)")]
+    async fn add_device(&self, ctx: &Context<'_>, input: AddDeviceInput) -> Result<MemberDevice> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let input = input.validate()?;
+        let verified = ctx.data_opt::<Caller>().is_some_and(Caller::is_admin);
+
+        Ok(sqlx::query_as::<_, MemberDevice>(
+            "INSERT INTO MemberDevice (member_id, mac_address, label, is_verified)
+             VALUES ($1, $2, $3, $4) RETURNING *",
+        )
+        .bind(input.member_id)
+        .bind(&input.mac_address)
+        .bind(&input.label)
+        .bind(verified)
+        .fetch_one(pool.as_ref())
+        .await?)
+    }
+
+    /// Removes one of a member's devices. The primary device can only be replaced, by changing
+    /// the member's MAC address.
+    #[graphql(name = "removeDevice", guard = "MemberGuard::new(member_id)")]
+    async fn remove_device(
+        &self,
+        ctx: &Context<'_>,
+        member_id: i32,
+        device_id: i32,
+    ) -> Result<MemberDevice> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let mut tx = pool.begin().await?;
+        let device = sqlx::query_as::<_, MemberDevice>(
+            "SELECT * FROM MemberDevice WHERE device_id = $1 AND member_id = $2 FOR UPDATE",
+        )
+        .bind(device_id)
+        .bind(member_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| {
+            RootError::NotFound(format!("Member {} has no device {}", member_id, device_id))
+        })?;
+        if device.is_primary {
+            return Err(RootError::Validation {
+                field: Some("deviceId"),
+                message: "The primary device can't be removed, only given a new MAC address"
+                    .to_string(),
+            });
+        }
+
+        sqlx::query("DELETE FROM MemberDevice WHERE device_id = $1")
+            .bind(device_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(device)
+    }
+
+    #[graphql(name = "verifyDevice", guard = "AdminGuard")]
+    async fn verify_device(&self, ctx: &Context<'_>, device_id: i32) -> Result<MemberDevice> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        sqlx::query_as::<_, MemberDevice>(
+            "UPDATE MemberDevice SET is_verified = TRUE WHERE device_id = $1 RETURNING *",
+        )
+        .bind(device_id)
+        .fetch_optional(pool.as_ref())
+        .await?
+        .ok_or_else(|| RootError::NotFound(format!("Device {} does not exist", device_id)))
+    }
+}
@@ -0,0 +1,229 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Object};
+use chrono::TimeDelta;
+use sqlx::PgPool;
+
+use crate::auth::{verify_signature, AdminGuard, MemberGuard};
+use crate::clock::ClubClock;
+use crate::error::{Result, RootError};
+use crate::models::event::{
+    check_times, CreateEventInput, Event, EventCheckIn, EventCheckInInput, EventRsvp, RsvpInput,
+    UpdateEventInput,
+};
+
+/// How long before an event starts members can check in.
+const CHECK_IN_OPENS_BEFORE: TimeDelta = TimeDelta::hours(1);
+
+/// How far a check-in's `signedAt` may be from the current time, so a captured signature can't be
+/// replayed for the rest of the event.
+const CHECK_IN_SIGNATURE_LIFETIME: TimeDelta = TimeDelta::minutes(5);
+
+#[derive(Default)]
+pub struct EventMutations;
+
+#[Object]
+impl EventMutations {
+    #[graphql(name = "createEvent", guard = "AdminGuard")]
+    async fn create_event(&self, ctx: &Context<'_>, input: CreateEventInput) -> Result<Event> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let input = input.validate()?;
+
+        Ok(sqlx::query_as::<_, Event>(
+            "INSERT INTO Event (title, description, kind, starts_at, ends_at, venue, organizer_id,
+                audience_years, audience_group_ids)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING *",
+        )
+        .bind(&input.title)
+        .bind(&input.description)
+        .bind(input.kind)
+        .bind(input.starts_at)
+        .bind(input.ends_at)
+        .bind(&input.venue)
+        .bind(input.organizer_id)
+        .bind(&input.audience_years)
+        .bind(&input.audience_group_ids)
+        .fetch_one(pool.as_ref())
+        .await?)
+    }
+
+    /// Changes the given fields of an event.
+    #[graphql(name = "updateEvent", guard = "AdminGuard")]
+    async fn update_event(&self, ctx: &Context<'_>, input: UpdateEventInput) -> Result<Event> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let input = input.validate()?;
+
+        let mut tx = pool.begin().await?;
+        let event =
+            sqlx::query_as::<_, Event>("SELECT * FROM Event WHERE event_id = $1 FOR UPDATE")
+                .bind(input.event_id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or_else(|| {
+                    RootError::NotFound(format!("Event {} does not exist", input.event_id))
+                })?;
+        check_times(
+            input.starts_at.unwrap_or(event.starts_at),
+            input.ends_at.unwrap_or(event.ends_at),
+        )?;
+
+        let event = sqlx::query_as::<_, Event>(
+            "UPDATE Event SET
+                title = COALESCE($2, title),
+                description = COALESCE($3, description),
+                kind = COALESCE($4, kind),
+                starts_at = COALESCE($5, starts_at),
+                ends_at = COALESCE($6, ends_at),
+                venue = COALESCE($7, venue),
+                organizer_id = COALESCE($8, organizer_id),
+                audience_years = COALESCE($9, audience_years),
+                audience_group_ids = COALESCE($10, audience_group_ids)
+             WHERE event_id = $1 RETURNING *",
+        )
+        .bind(input.event_id)
+        .bind(&input.title)
+        .bind(&input.description)
+        .bind(input.kind)
+        .bind(input.starts_at)
+        .bind(input.ends_at)
+        .bind(&input.venue)
+        .bind(input.organizer_id)
+        .bind(&input.audience_years)
+        .bind(&input.audience_group_ids)
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(event)
+    }
+
+    /// Removes an event along with its RSVPs and check-ins.
+    #[graphql(name = "deleteEvent", guard = "AdminGuard")]
+    async fn delete_event(&self, ctx: &Context<'_>, event_id: i32) -> Result<Event> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        sqlx::query_as::<_, Event>("DELETE FROM Event WHERE event_id = $1 RETURNING *")
+            .bind(event_id)
+            .fetch_optional(pool.as_ref())
+            .await?
+            .ok_or_else(|| RootError::NotFound(format!("Event {} does not exist", event_id)))
+    }
+
+    /// Records or changes a member's response to an event they're invited to.
+    #[graphql(name = "rsvpEvent", guard = "MemberGuard::new(input.member_id)")]
+    async fn rsvp_event(&self, ctx: &Context<'_>, input: RsvpInput) -> Result<EventRsvp> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let now = ctx
+            .data::<ClubClock>()
+            .expect("Clock must be in context.")
+            .now()
+            .to_utc();
+
+        check_invited(pool.as_ref(), input.event_id, input.member_id).await?;
+
+        Ok(sqlx::query_as::<_, EventRsvp>(
+            "INSERT INTO EventRsvp (event_id, member_id, status, responded_at)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (event_id, member_id) DO UPDATE
+                SET status = EXCLUDED.status, responded_at = EXCLUDED.responded_at
+             RETURNING *",
+        )
+        .bind(input.event_id)
+        .bind(input.member_id)
+        .bind(input.status)
+        .bind(now)
+        .fetch_one(pool.as_ref())
+        .await?)
+    }
+
+    /// Checks an invited member in at an event, signed like `markAttendance` along with the time
+    /// it was signed. Check-in opens an hour before the event and closes when it ends. Checking
+    /// in again keeps the first time.
+    #[graphql(name = "checkInEvent")]
+    async fn check_in_event(
+        &self,
+        ctx: &Context<'_>,
+        input: EventCheckInInput,
+    ) -> Result<EventCheckIn> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let secret_key = ctx
+            .data::<String>()
+            .expect("ROOT_SECRET must be found in context");
+
+        let message = format!(
+            "{}:{}:{}",
+            input.event_id,
+            input.member_id,
+            input.signed_at.timestamp()
+        );
+        verify_signature(secret_key, &message, &input.hmac_signature)?;
+
+        let now = ctx
+            .data::<ClubClock>()
+            .expect("Clock must be in context.")
+            .now()
+            .to_utc();
+        if (now - input.signed_at).abs() > CHECK_IN_SIGNATURE_LIFETIME {
+            return Err(RootError::Validation {
+                field: Some("signedAt"),
+                message: format!(
+                    "Check-ins must be signed within {} minutes of being sent",
+                    CHECK_IN_SIGNATURE_LIFETIME.num_minutes()
+                ),
+            });
+        }
+        let event = sqlx::query_as::<_, Event>("SELECT * FROM Event WHERE event_id = $1")
+            .bind(input.event_id)
+            .fetch_optional(pool.as_ref())
+            .await?
+            .ok_or_else(|| {
+                RootError::NotFound(format!("Event {} does not exist", input.event_id))
+            })?;
+        if now < event.starts_at - CHECK_IN_OPENS_BEFORE || now > event.ends_at {
+            return Err(RootError::Validation {
+                field: Some("eventId"),
+                message: format!("Check-in for event {} isn't open", input.event_id),
+            });
+        }
+        check_invited(pool.as_ref(), input.event_id, input.member_id).await?;
+
+        Ok(sqlx::query_as::<_, EventCheckIn>(
+            "INSERT INTO EventAttendance (event_id, member_id, checked_in_at)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (event_id, member_id) DO UPDATE
+                SET checked_in_at = EventAttendance.checked_in_at
+             RETURNING *",
+        )
+        .bind(input.event_id)
+        .bind(input.member_id)
+        .bind(now)
+        .fetch_one(pool.as_ref())
+        .await?)
+    }
+}
+
+/// Fails unless the member is in the event's audience of years and groups.
+async fn check_invited(pool: &PgPool, event_id: i32, member_id: i32) -> Result<()> {
+    let invited = sqlx::query_scalar::<_, bool>(
+        "SELECT (CARDINALITY(ev.audience_years) = 0 OR mem.year = ANY(ev.audience_years))
+            AND (CARDINALITY(ev.audience_group_ids) = 0
+                 OR mem.group_id = ANY(ev.audience_group_ids))
+         FROM Event ev, Member mem
+         WHERE ev.event_id = $1 AND mem.member_id = $2",
+    )
+    .bind(event_id)
+    .bind(member_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| RootError::NotFound(format!("No event {} or member {}", event_id, member_id)))?;
+    if !invited {
+        return Err(RootError::Validation {
+            field: Some("memberId"),
+            message: format!(
+                "Member {} isn't in the audience of event {}",
+                member_id, event_id
+            ),
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Object};
+use sqlx::PgPool;
+
+use crate::auth::AdminGuard;
+use crate::error::{Result, RootError};
+use crate::models::{
+    group::{CreateGroupInput, Group, MoveMembersInput, RenameGroupInput, SetGroupMentorsInput},
+    member::Member,
+};
+
+#[derive(Default)]
+pub struct GroupMutations;
+
+#[Object]
+impl GroupMutations {
+    #[graphql(name = "createGroup", guard = "AdminGuard")]
+    async fn create_group(&self, ctx: &Context<'_>, input: CreateGroupInput) -> Result<Group> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let mut tx = pool.begin().await?;
+        let group = sqlx::query_as::<_, Group>(
+            "INSERT INTO MemberGroup (name, description, discord_channel_id)
+            VALUES ($1, $2, $3) RETURNING *",
+        )
+        .bind(&input.name)
+        .bind(&input.description)
+        .bind(&input.discord_channel_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if let Some(mentor_ids) = input.mentor_ids {
+            sqlx::query(
+                "INSERT INTO GroupMentor (group_id, member_id)
+                SELECT $1, UNNEST($2::INT[])",
+            )
+            .bind(group.group_id)
+            .bind(mentor_ids)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(group)
+    }
+
+    #[graphql(name = "renameGroup", guard = "AdminGuard")]
+    async fn rename_group(&self, ctx: &Context<'_>, input: RenameGroupInput) -> Result<Group> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let group = sqlx::query_as::<_, Group>(
+            "UPDATE MemberGroup SET name = $1 WHERE group_id = $2 RETURNING *",
+        )
+        .bind(&input.name)
+        .bind(input.group_id)
+        .fetch_optional(pool.as_ref())
+        .await?
+        .ok_or_else(|| RootError::NotFound(format!("Group {} does not exist", input.group_id)))?;
+
+        Ok(group)
+    }
+
+    /// Moves every listed member into the target group and returns the updated members.
+    #[graphql(name = "moveMembers", guard = "AdminGuard")]
+    async fn move_members(
+        &self,
+        ctx: &Context<'_>,
+        input: MoveMembersInput,
+    ) -> Result<Vec<Member>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let members = sqlx::query_as::<_, Member>(
+            "UPDATE Member SET group_id = $1 WHERE member_id = ANY($2) RETURNING *",
+        )
+        .bind(input.group_id)
+        .bind(&input.member_ids)
+        .fetch_all(pool.as_ref())
+        .await?;
+
+        Ok(members)
+    }
+
+    /// Replaces the group's mentors with the given members.
+    #[graphql(name = "setGroupMentors", guard = "AdminGuard")]
+    async fn set_group_mentors(
+        &self,
+        ctx: &Context<'_>,
+        input: SetGroupMentorsInput,
+    ) -> Result<Group> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let mut tx = pool.begin().await?;
+        let group = sqlx::query_as::<_, Group>("SELECT * FROM MemberGroup WHERE group_id = $1")
+            .bind(input.group_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| {
+                RootError::NotFound(format!("Group {} does not exist", input.group_id))
+            })?;
+
+        sqlx::query("DELETE FROM GroupMentor WHERE group_id = $1")
+            .bind(input.group_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "INSERT INTO GroupMentor (group_id, member_id)
+            SELECT $1, UNNEST($2::INT[])",
+        )
+        .bind(input.group_id)
+        .bind(&input.mentor_ids)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(group)
+    }
+}
@@ -1,11 +1,20 @@
 use std::sync::Arc;
 
-use async_graphql::{Context, Object, Result};
-use chrono::Local;
-use chrono_tz::Asia::Kolkata;
+use async_graphql::{Context, Object, Upload};
 use sqlx::PgPool;
 
-use crate::models::member::{CreateMemberInput, Member};
+use crate::academic_year;
+use crate::auth::{caller_member_id, AdminGuard, MemberGuard};
+use crate::clock::ClubClock;
+use crate::config::AttendancePolicy;
+use crate::email;
+use crate::error::{Result, RootError};
+use crate::member_import::import_members;
+use crate::models::member::{
+    CreateMemberInput, ImportReport, Member, ProfileChangeRequest, ProfileChangeStatus,
+    ProfileUpdate, RolloverInput, RolloverReport, UpdateProfileInput,
+};
+use crate::webhooks::{self, Event};
 
 #[derive(Default)]
 pub struct MemberMutations;
@@ -15,8 +24,17 @@ impl MemberMutations {
     #[graphql(name = "createMember")]
     async fn create_member(&self, ctx: &Context<'_>, input: CreateMemberInput) -> Result<Member> {
         let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let policy = ctx
+            .data::<AttendancePolicy>()
+            .expect("Attendance policy must be in context.");
+        let input = input.validate(policy.final_year)?;
 
-        let now = Local::now().with_timezone(&Kolkata).date_naive();
+        let now = ctx
+            .data::<ClubClock>()
+            .expect("Clock must be in context.")
+            .now()
+            .naive_local();
+        let mut tx = pool.begin().await?;
         let member = sqlx::query_as::<_, Member>(
             "INSERT INTO Member (roll_no, name, email, sex, year, hostel, mac_address, discord_id, group_id, created_at)
             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING *"
@@ -31,9 +49,236 @@ impl MemberMutations {
         .bind(&input.discord_id)
         .bind(input.group_id)
         .bind(now)
-        .fetch_one(pool.as_ref())
+        .fetch_one(&mut *tx)
         .await?;
+        webhooks::enqueue(&mut *tx, Event::member_created(&member)).await?;
+        email::enqueue_welcome(&mut *tx, member.member_id).await?;
+        tx.commit().await?;
 
         Ok(member)
     }
+
+    /// Promotes all active members to the next year and graduates final-years into alumni.
+    #[graphql(name = "rolloverAcademicYear", guard = "AdminGuard")]
+    async fn rollover_academic_year(
+        &self,
+        ctx: &Context<'_>,
+        input: RolloverInput,
+    ) -> Result<RolloverReport> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        if academic_year::has_rolled_over(pool.as_ref(), input.academic_year).await? {
+            return Err(RootError::Conflict {
+                field: Some("academicYear"),
+                message: format!(
+                    "Academic year {} has already been rolled over",
+                    input.academic_year
+                ),
+            });
+        }
+
+        let policy = ctx
+            .data::<AttendancePolicy>()
+            .expect("Attendance policy must be in context.");
+        Ok(academic_year::rollover(
+            pool.as_ref(),
+            input.academic_year,
+            policy.final_year,
+            input.dry_run,
+        )
+        .await?)
+    }
+
+    /// Imports members from a CSV upload whose columns match `CreateMemberInput`.
+    /// Valid rows are inserted in one transaction and every invalid row is reported.
+    #[graphql(name = "bulkImportMembers", guard = "AdminGuard")]
+    async fn bulk_import_members(
+        &self,
+        ctx: &Context<'_>,
+        file: Upload,
+        #[graphql(default)] dry_run: bool,
+    ) -> Result<ImportReport> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let policy = ctx
+            .data::<AttendancePolicy>()
+            .expect("Attendance policy must be in context.");
+
+        let clock = ctx.data::<ClubClock>().expect("Clock must be in context.");
+
+        let upload = file.value(ctx)?;
+        Ok(import_members(
+            pool.as_ref(),
+            clock,
+            upload.content,
+            policy.final_year,
+            dry_run,
+        )
+        .await?)
+    }
+
+    /// Stops or resumes a member's attendance reports by email. Reports already queued for them
+    /// are dropped when they opt out.
+    #[graphql(name = "setEmailOptOut", guard = "MemberGuard::new(member_id)")]
+    async fn set_email_opt_out(
+        &self,
+        ctx: &Context<'_>,
+        member_id: i32,
+        opt_out: bool,
+    ) -> Result<Member> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let mut tx = pool.begin().await?;
+        let member = sqlx::query_as::<_, Member>(
+            "UPDATE Member SET email_opt_out = $2 WHERE member_id = $1 RETURNING *",
+        )
+        .bind(member_id)
+        .bind(opt_out)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| RootError::NotFound(format!("Member {} does not exist", member_id)))?;
+        if opt_out {
+            sqlx::query(
+                "DELETE FROM EmailOutbox
+                 WHERE member_id = $1 AND status = 'pending' AND kind <> 'welcome'",
+            )
+            .bind(member_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(member)
+    }
+
+    /// Changes the caller's own hostel and Discord ID right away. A new MAC address waits for an
+    /// admin's approval instead, replacing any earlier one still waiting, and asking for the
+    /// current address withdraws it.
+    #[graphql(name = "updateMyProfile")]
+    async fn update_my_profile(
+        &self,
+        ctx: &Context<'_>,
+        input: UpdateProfileInput,
+    ) -> Result<ProfileUpdate> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let member_id = caller_member_id(ctx)?;
+        let input = input.validate()?;
+        let now = ctx
+            .data::<ClubClock>()
+            .expect("Clock must be in context.")
+            .now()
+            .to_utc();
+
+        let mut tx = pool.begin().await?;
+        let member = sqlx::query_as::<_, Member>(
+            "UPDATE Member SET
+                hostel = COALESCE($2, hostel),
+                discord_id = COALESCE($3, discord_id)
+             WHERE member_id = $1 RETURNING *",
+        )
+        .bind(member_id)
+        .bind(&input.hostel)
+        .bind(&input.discord_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| RootError::NotFound(format!("Member {} does not exist", member_id)))?;
+
+        match input.mac_address {
+            Some(mac_address) if mac_address == member.mac_address => {
+                sqlx::query(
+                    "DELETE FROM ProfileChangeRequest WHERE member_id = $1 AND status = 'pending'",
+                )
+                .bind(member_id)
+                .execute(&mut *tx)
+                .await?;
+            }
+            Some(mac_address) => {
+                let taken = sqlx::query_scalar::<_, bool>(
+                    "SELECT EXISTS (SELECT 1 FROM MemberDevice WHERE mac_address = $1)",
+                )
+                .bind(&mac_address)
+                .fetch_one(&mut *tx)
+                .await?;
+                if taken {
+                    return Err(RootError::Conflict {
+                        field: Some("macAddress"),
+                        message: "A device with this MAC address is already registered".to_string(),
+                    });
+                }
+
+                sqlx::query(
+                    "INSERT INTO ProfileChangeRequest (member_id, mac_address, requested_at)
+                     VALUES ($1, $2, $3)
+                     ON CONFLICT (member_id) WHERE status = 'pending' DO UPDATE
+                        SET mac_address = EXCLUDED.mac_address,
+                            requested_at = EXCLUDED.requested_at",
+                )
+                .bind(member_id)
+                .bind(&mac_address)
+                .bind(now)
+                .execute(&mut *tx)
+                .await?;
+            }
+            None => {}
+        }
+
+        let pending_change = sqlx::query_as::<_, ProfileChangeRequest>(
+            "SELECT * FROM ProfileChangeRequest WHERE member_id = $1 AND status = 'pending'",
+        )
+        .bind(member_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(ProfileUpdate {
+            member,
+            pending_change,
+        })
+    }
+
+    /// Approves or rejects a pending profile change. Approving applies it to the member.
+    #[graphql(name = "reviewProfileChange", guard = "AdminGuard")]
+    async fn review_profile_change(
+        &self,
+        ctx: &Context<'_>,
+        change_id: i32,
+        approve: bool,
+    ) -> Result<ProfileChangeRequest> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let now = ctx
+            .data::<ClubClock>()
+            .expect("Clock must be in context.")
+            .now()
+            .to_utc();
+
+        let mut tx = pool.begin().await?;
+        let change = sqlx::query_as::<_, ProfileChangeRequest>(
+            "UPDATE ProfileChangeRequest
+             SET status = $2, reviewed_at = $3
+             WHERE change_id = $1 AND status = 'pending'
+             RETURNING *",
+        )
+        .bind(change_id)
+        .bind(if approve {
+            ProfileChangeStatus::Approved
+        } else {
+            ProfileChangeStatus::Rejected
+        })
+        .bind(now)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| {
+            RootError::NotFound(format!("No pending profile change {} to review", change_id))
+        })?;
+        if approve {
+            sqlx::query("UPDATE Member SET mac_address = $2 WHERE member_id = $1")
+                .bind(change.member_id)
+                .bind(&change.mac_address)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        Ok(change)
+    }
 }
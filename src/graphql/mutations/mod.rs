@@ -1,9 +1,19 @@
 pub mod attendance_mutations;
+pub mod calendar_mutations;
+pub mod device_mutations;
+pub mod event_mutations;
+pub mod group_mutations;
 pub mod member_mutations;
 pub mod project_mutations;
 pub mod streak_mutations;
+pub mod webhook_mutations;
 
 pub use attendance_mutations::AttendanceMutations;
+pub use calendar_mutations::CalendarMutations;
+pub use device_mutations::DeviceMutations;
+pub use event_mutations::EventMutations;
+pub use group_mutations::GroupMutations;
 pub use member_mutations::MemberMutations;
 pub use project_mutations::ProjectMutations;
 pub use streak_mutations::StreakMutations;
+pub use webhook_mutations::WebhookMutations;
@@ -1,9 +1,11 @@
 use std::sync::Arc;
 
-use async_graphql::{Context, Object, Result};
+use async_graphql::{Context, Object};
 use sqlx::PgPool;
 
+use crate::error::Result;
 use crate::models::project::{Project, SetProjectInput};
+use crate::webhooks::{self, Event};
 
 #[derive(Default)]
 pub struct ProjectMutations;
@@ -16,13 +18,16 @@ impl ProjectMutations {
             .data::<Arc<PgPool>>()
             .expect("Pool must be found in context");
 
+        let mut tx = pool.begin().await?;
         let project = sqlx::query_as::<_, Project>(
-            "INSERT INTO Project (member_id, project_title) VALUES ($1, $2) RETURNING * ",
+            "INSERT INTO Project (member_id, title) VALUES ($1, $2) RETURNING * ",
         )
         .bind(input.member_id)
         .bind(input.title)
-        .fetch_one(pool.as_ref())
+        .fetch_one(&mut *tx)
         .await?;
+        webhooks::enqueue(&mut *tx, Event::project_updated(&project)).await?;
+        tx.commit().await?;
         Ok(project)
     }
 }
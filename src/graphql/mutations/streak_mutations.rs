@@ -1,9 +1,12 @@
 use std::sync::Arc;
 
-use async_graphql::{Context, Object, Result};
+use async_graphql::{Context, Object};
 use sqlx::PgPool;
 
+use crate::clock::ClubClock;
+use crate::error::Result;
 use crate::models::status_update_streak::{StatusUpdateStreak as Streak, StreakInput};
+use crate::webhooks::{self, Event};
 
 #[derive(Default)]
 pub struct StreakMutations;
@@ -13,12 +16,17 @@ impl StreakMutations {
     #[graphql(name = "incrementStreak")]
     async fn increment_streak(&self, ctx: &Context<'_>, input: StreakInput) -> Result<Streak> {
         let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let today = ctx
+            .data::<ClubClock>()
+            .expect("Clock must be in context.")
+            .today();
 
         let query = sqlx::query_as::<_, Streak>(
             "
-        INSERT INTO StatusUpdateStreak (member_id, current_streak, max_streak)
-        VALUES ($1, 1, 1)
+        INSERT INTO StatusUpdateStreak (member_id, current_streak, max_streak, updated_on)
+        VALUES ($1, 1, 1, $2)
         ON CONFLICT (member_id) DO UPDATE SET 
+            updated_on = EXCLUDED.updated_on,
             current_streak = CASE
                 WHEN StatusUpdateStreak.current_streak >= 0 THEN StatusUpdateStreak.current_streak + 1
                 ELSE 1
@@ -26,7 +34,8 @@ impl StreakMutations {
             max_streak = GREATEST(StatusUpdateStreak.max_streak, StatusUpdateStreak.current_streak + 1)
         RETURNING *",
         )
-        .bind(input.member_id);
+        .bind(input.member_id)
+        .bind(today);
 
         let updated_streak = query.fetch_one(pool.as_ref()).await?;
 
@@ -35,21 +44,30 @@ impl StreakMutations {
 
     async fn reset_streak(&self, ctx: &Context<'_>, input: StreakInput) -> Result<Streak> {
         let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let today = ctx
+            .data::<ClubClock>()
+            .expect("Clock must be in context.")
+            .today();
 
         let query = sqlx::query_as::<_, Streak>(
             "
-        INSERT INTO StatusUpdateStreak (member_id, current_streak, max_streak)
-        VALUES ($1, 0, 0)
+        INSERT INTO StatusUpdateStreak (member_id, current_streak, max_streak, updated_on)
+        VALUES ($1, 0, 0, $2)
         ON CONFLICT (member_id) DO UPDATE
-            SET current_streak = CASE
+            SET updated_on = EXCLUDED.updated_on,
+            current_streak = CASE
                 WHEN StatusUpdateStreak.current_streak > 0 THEN 0
                 ELSE StatusUpdateStreak.current_streak - 1 
             END
         RETURNING *",
         )
-        .bind(input.member_id);
+        .bind(input.member_id)
+        .bind(today);
 
-        let updated_streak = query.fetch_one(pool.as_ref()).await?;
+        let mut tx = pool.begin().await?;
+        let updated_streak = query.fetch_one(&mut *tx).await?;
+        webhooks::enqueue(&mut *tx, Event::streak_reset(&updated_streak)).await?;
+        tx.commit().await?;
         Ok(updated_streak)
     }
 }
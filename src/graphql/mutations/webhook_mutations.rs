@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Object};
+use sqlx::PgPool;
+
+use crate::auth::AdminGuard;
+use crate::error::{Result, RootError};
+use crate::models::webhook::{
+    RegisterWebhookInput, RegisteredWebhook, UpdateWebhookInput, Webhook, WebhookDelivery,
+};
+use crate::webhooks;
+
+#[derive(Default)]
+pub struct WebhookMutations;
+
+#[Object]
+impl WebhookMutations {
+    /// Subscribes an endpoint to events. The returned secret is needed to verify payloads and
+    /// can't be looked up again.
+    #[graphql(name = "registerWebhook", guard = "AdminGuard")]
+    async fn register_webhook(
+        &self,
+        ctx: &Context<'_>,
+        input: RegisterWebhookInput,
+    ) -> Result<RegisteredWebhook> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let input = input.validate()?;
+
+        let secret = webhooks::generate_secret();
+        let webhook = sqlx::query_as::<_, Webhook>(
+            "INSERT INTO Webhook (url, description, events, secret)
+             VALUES ($1, $2, $3, $4) RETURNING *",
+        )
+        .bind(&input.url)
+        .bind(&input.description)
+        .bind(&input.events)
+        .bind(&secret)
+        .fetch_one(pool.as_ref())
+        .await?;
+
+        Ok(RegisteredWebhook { webhook, secret })
+    }
+
+    /// Changes the given fields of a webhook. Deactivated webhooks neither receive new events nor
+    /// retry pending ones until they are activated again.
+    #[graphql(name = "updateWebhook", guard = "AdminGuard")]
+    async fn update_webhook(
+        &self,
+        ctx: &Context<'_>,
+        input: UpdateWebhookInput,
+    ) -> Result<Webhook> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let input = input.validate()?;
+
+        sqlx::query_as::<_, Webhook>(
+            "UPDATE Webhook SET
+                url = COALESCE($2, url),
+                events = COALESCE($3, events),
+                description = COALESCE($4, description),
+                active = COALESCE($5, active)
+             WHERE webhook_id = $1 RETURNING *",
+        )
+        .bind(input.webhook_id)
+        .bind(&input.url)
+        .bind(&input.events)
+        .bind(&input.description)
+        .bind(input.active)
+        .fetch_optional(pool.as_ref())
+        .await?
+        .ok_or_else(|| RootError::NotFound(format!("Webhook {} does not exist", input.webhook_id)))
+    }
+
+    /// Removes a webhook along with its pending deliveries and delivery log.
+    #[graphql(name = "deleteWebhook", guard = "AdminGuard")]
+    async fn delete_webhook(&self, ctx: &Context<'_>, webhook_id: i32) -> Result<Webhook> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        sqlx::query_as::<_, Webhook>("DELETE FROM Webhook WHERE webhook_id = $1 RETURNING *")
+            .bind(webhook_id)
+            .fetch_optional(pool.as_ref())
+            .await?
+            .ok_or_else(|| RootError::NotFound(format!("Webhook {} does not exist", webhook_id)))
+    }
+
+    /// Queues a failed delivery to be attempted again, as soon as possible.
+    #[graphql(name = "retryWebhookDelivery", guard = "AdminGuard")]
+    async fn retry_webhook_delivery(
+        &self,
+        ctx: &Context<'_>,
+        delivery_id: i64,
+    ) -> Result<WebhookDelivery> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        sqlx::query_as::<_, WebhookDelivery>(
+            "UPDATE WebhookDelivery del
+             SET status = 'pending', attempts = 0, next_attempt_at = NULL
+             FROM WebhookEvent ev
+             WHERE del.delivery_id = $1 AND del.status = 'failed' AND ev.event_id = del.event_id
+             RETURNING del.delivery_id, del.webhook_id, del.event_id, ev.event_type, del.status,
+                       del.attempts, del.next_attempt_at, del.last_response_status,
+                       del.last_error, del.delivered_at",
+        )
+        .bind(delivery_id)
+        .fetch_optional(pool.as_ref())
+        .await?
+        .ok_or_else(|| RootError::NotFound(format!("No failed webhook delivery {}", delivery_id)))
+    }
+}
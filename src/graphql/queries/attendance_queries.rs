@@ -1,7 +1,8 @@
 use std::sync::Arc;
 
+use crate::error::Result;
 use crate::models::attendance::{Attendance, AttendanceWithMember};
-use async_graphql::{Context, Object, Result};
+use async_graphql::{Context, Object};
 use chrono::NaiveDate;
 use sqlx::PgPool;
 
@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Object};
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+use crate::error::Result;
+use crate::models::calendar::CalendarDate;
+
+#[derive(Default)]
+pub struct CalendarQueries;
+
+#[Object]
+impl CalendarQueries {
+    /// Holidays and deadlines in the given range, earliest first. With a `groupId`, only the
+    /// club's and that group's.
+    #[graphql(name = "calendarDates")]
+    async fn calendar_dates(
+        &self,
+        ctx: &Context<'_>,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        group_id: Option<i32>,
+    ) -> Result<Vec<CalendarDate>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(sqlx::query_as::<_, CalendarDate>(
+            "SELECT * FROM CalendarDate
+             WHERE ($1::DATE IS NULL OR date >= $1)
+             AND ($2::DATE IS NULL OR date <= $2)
+             AND ($3::INT IS NULL OR group_id IS NULL OR group_id = $3)
+             ORDER BY date, date_id",
+        )
+        .bind(from)
+        .bind(to)
+        .bind(group_id)
+        .fetch_all(pool.as_ref())
+        .await?)
+    }
+}
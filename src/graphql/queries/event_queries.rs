@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use async_graphql::{ComplexObject, Context, Object};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::error::{Result, RootError};
+use crate::models::{
+    event::{Event, EventAttendanceCounts, EventCheckIn, EventRsvp},
+    member::Member,
+};
+
+#[derive(Default)]
+pub struct EventQueries;
+
+#[Object]
+impl EventQueries {
+    /// Events that overlap the given range, earliest first.
+    async fn events(
+        &self,
+        ctx: &Context<'_>,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Event>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(sqlx::query_as::<_, Event>(
+            "SELECT * FROM Event
+             WHERE ($1::TIMESTAMPTZ IS NULL OR ends_at > $1)
+             AND ($2::TIMESTAMPTZ IS NULL OR starts_at < $2)
+             ORDER BY starts_at, event_id",
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_all(pool.as_ref())
+        .await?)
+    }
+
+    async fn event(&self, ctx: &Context<'_>, event_id: i32) -> Result<Event> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        sqlx::query_as::<_, Event>("SELECT * FROM Event WHERE event_id = $1")
+            .bind(event_id)
+            .fetch_optional(pool.as_ref())
+            .await?
+            .ok_or_else(|| RootError::NotFound(format!("Event {} does not exist", event_id)))
+    }
+}
+
+#[ComplexObject]
+impl Event {
+    async fn organizer(&self, ctx: &Context<'_>) -> Result<Option<Member>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(
+            sqlx::query_as::<_, Member>("SELECT * FROM Member WHERE member_id = $1")
+                .bind(self.organizer_id)
+                .fetch_optional(pool.as_ref())
+                .await?,
+        )
+    }
+
+    #[graphql(complexity = "crate::limits::NESTED_LIST_FACTOR * child_complexity")]
+    async fn rsvps(&self, ctx: &Context<'_>) -> Result<Vec<EventRsvp>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(sqlx::query_as::<_, EventRsvp>(
+            "SELECT * FROM EventRsvp WHERE event_id = $1 ORDER BY responded_at",
+        )
+        .bind(self.event_id)
+        .fetch_all(pool.as_ref())
+        .await?)
+    }
+
+    #[graphql(
+        name = "checkIns",
+        complexity = "crate::limits::NESTED_LIST_FACTOR * child_complexity"
+    )]
+    async fn check_ins(&self, ctx: &Context<'_>) -> Result<Vec<EventCheckIn>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(sqlx::query_as::<_, EventCheckIn>(
+            "SELECT * FROM EventAttendance WHERE event_id = $1 ORDER BY checked_in_at",
+        )
+        .bind(self.event_id)
+        .fetch_all(pool.as_ref())
+        .await?)
+    }
+
+    /// How many members responded each way, and how many checked in.
+    #[graphql(name = "attendanceCounts")]
+    async fn attendance_counts(&self, ctx: &Context<'_>) -> Result<EventAttendanceCounts> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(sqlx::query_as::<_, EventAttendanceCounts>(
+            "SELECT
+                COUNT(*) FILTER (WHERE status = 'going')::INT AS going,
+                COUNT(*) FILTER (WHERE status = 'maybe')::INT AS maybe,
+                COUNT(*) FILTER (WHERE status = 'not_going')::INT AS not_going,
+                (SELECT COUNT(*) FROM EventAttendance WHERE event_id = $1)::INT AS checked_in
+             FROM EventRsvp WHERE event_id = $1",
+        )
+        .bind(self.event_id)
+        .fetch_one(pool.as_ref())
+        .await?)
+    }
+}
@@ -0,0 +1,100 @@
+use async_graphql::{ComplexObject, Context, Object};
+use chrono::NaiveDate;
+use sqlx::PgPool;
+use std::sync::Arc;
+
+use crate::error::{Result, RootError};
+use crate::models::{
+    group::{Group, GroupAttendanceStats},
+    member::Member,
+};
+
+#[derive(Default)]
+pub struct GroupQueries;
+
+#[Object]
+impl GroupQueries {
+    async fn groups(&self, ctx: &Context<'_>) -> Result<Vec<Group>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(
+            sqlx::query_as::<_, Group>("SELECT * FROM MemberGroup ORDER BY group_id")
+                .fetch_all(pool.as_ref())
+                .await?,
+        )
+    }
+
+    async fn group(&self, ctx: &Context<'_>, group_id: i32) -> Result<Group> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        sqlx::query_as::<_, Group>("SELECT * FROM MemberGroup WHERE group_id = $1")
+            .bind(group_id)
+            .fetch_optional(pool.as_ref())
+            .await?
+            .ok_or_else(|| RootError::NotFound(format!("Group {} does not exist", group_id)))
+    }
+}
+
+#[ComplexObject]
+impl Group {
+    #[graphql(complexity = "crate::limits::NESTED_LIST_FACTOR * child_complexity")]
+    async fn members(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] include_alumni: bool,
+    ) -> Result<Vec<Member>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(sqlx::query_as::<_, Member>(
+            "SELECT * FROM Member WHERE group_id = $1 AND ($2 OR status = 'active')",
+        )
+        .bind(self.group_id)
+        .bind(include_alumni)
+        .fetch_all(pool.as_ref())
+        .await?)
+    }
+
+    #[graphql(complexity = "crate::limits::NESTED_LIST_FACTOR * child_complexity")]
+    async fn mentors(&self, ctx: &Context<'_>) -> Result<Vec<Member>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(sqlx::query_as::<_, Member>(
+            "SELECT mem.* FROM Member mem
+             JOIN GroupMentor gm ON gm.member_id = mem.member_id
+             WHERE gm.group_id = $1",
+        )
+        .bind(self.group_id)
+        .fetch_all(pool.as_ref())
+        .await?)
+    }
+
+    /// Attendance totals for the group's active members, optionally limited to a date range.
+    #[graphql(name = "attendanceStats")]
+    async fn attendance_stats(
+        &self,
+        ctx: &Context<'_>,
+        start_date: Option<NaiveDate>,
+        end_date: Option<NaiveDate>,
+    ) -> Result<GroupAttendanceStats> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(sqlx::query_as::<_, GroupAttendanceStats>(
+            "SELECT
+                (SELECT COUNT(*) FROM Member WHERE group_id = $1 AND status = 'active')::INT
+                    AS member_count,
+                COUNT(att.attendance_id) FILTER (WHERE att.is_present)::INT AS days_present,
+                COUNT(att.attendance_id)::INT AS days_recorded
+             FROM Attendance att
+             JOIN Member mem ON att.member_id = mem.member_id
+             WHERE mem.group_id = $1
+             AND mem.status = 'active'
+             AND ($2::DATE IS NULL OR att.date >= $2)
+             AND ($3::DATE IS NULL OR att.date <= $3)",
+        )
+        .bind(self.group_id)
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_one(pool.as_ref())
+        .await?)
+    }
+}
@@ -1,13 +1,19 @@
-use async_graphql::{ComplexObject, Context, Object, Result};
-use sqlx::PgPool;
+use async_graphql::{ComplexObject, Context, Object};
+use sqlx::{PgPool, Postgres, QueryBuilder};
 use std::sync::Arc;
 
+use crate::auth::{caller_member_id, AdminGuard, MemberGuard};
+use crate::error::{Result, RootError};
 use crate::models::{
     attendance::{AttendanceInfo, AttendanceSummaryInfo},
-    member::Member,
+    device::MemberDevice,
+    event::MemberEvent,
+    group::Group,
+    member::{Member, ProfileChangeRequest, ProfileChangeStatus},
     project::Project,
     status_update_streak::StatusUpdateStreakInfo,
 };
+use crate::validation;
 
 #[derive(Default)]
 pub struct MemberQueries;
@@ -19,20 +25,11 @@ impl MemberQueries {
         ctx: &Context<'_>,
         year: Option<i32>,
         group_id: Option<i32>,
+        #[graphql(default)] include_alumni: bool,
     ) -> Result<Vec<Member>> {
         let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
 
-        let mut query = sqlx::QueryBuilder::new("SELECT * FROM Member WHERE 1=1");
-
-        if let Some(y) = year {
-            query.push(" AND year = ");
-            query.push_bind(y);
-        }
-
-        if let Some(g) = group_id {
-            query.push(" AND group_id = ");
-            query.push_bind(g);
-        }
+        let mut query = members_query(year, group_id, include_alumni);
 
         let members = query
             .build_query_as::<Member>()
@@ -41,54 +38,201 @@ impl MemberQueries {
 
         Ok(members)
     }
+
+    /// The member the caller's API key was issued to.
+    async fn me(&self, ctx: &Context<'_>) -> Result<Member> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let member_id = caller_member_id(ctx)?;
+
+        sqlx::query_as::<_, Member>("SELECT * FROM Member WHERE member_id = $1")
+            .bind(member_id)
+            .fetch_optional(pool.as_ref())
+            .await?
+            .ok_or_else(|| RootError::NotFound(format!("Member {} does not exist", member_id)))
+    }
+
+    /// The member a verified device belongs to, for Presense to resolve the MAC addresses it
+    /// scans. Admins only, since it tells anyone who walked past a scanner.
+    #[graphql(name = "memberByMac", guard = "AdminGuard")]
+    async fn member_by_mac(&self, ctx: &Context<'_>, mac: String) -> Result<Option<Member>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let mac = validation::mac_address(&mac).map_err(|message| RootError::Validation {
+            field: Some("mac"),
+            message,
+        })?;
+
+        Ok(sqlx::query_as::<_, Member>(
+            "SELECT mem.* FROM Member mem
+             JOIN MemberDevice dev ON dev.member_id = mem.member_id
+             WHERE dev.mac_address = $1 AND dev.is_verified",
+        )
+        .bind(mac)
+        .fetch_optional(pool.as_ref())
+        .await?)
+    }
+
+    /// Members' requested profile changes, oldest first, for admins to review.
+    #[graphql(name = "profileChangeRequests", guard = "AdminGuard")]
+    async fn profile_change_requests(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default_with = "Some(ProfileChangeStatus::Pending)")] status: Option<
+            ProfileChangeStatus,
+        >,
+    ) -> Result<Vec<ProfileChangeRequest>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(sqlx::query_as::<_, ProfileChangeRequest>(
+            "SELECT * FROM ProfileChangeRequest
+             WHERE ($1::profile_change_status IS NULL OR status = $1)
+             ORDER BY requested_at, change_id",
+        )
+        .bind(status)
+        .fetch_all(pool.as_ref())
+        .await?)
+    }
+}
+
+/// Builds the `members` query. Shared with the member export so both filter identically.
+pub fn members_query(
+    year: Option<i32>,
+    group_id: Option<i32>,
+    include_alumni: bool,
+) -> QueryBuilder<'static, Postgres> {
+    let mut query = QueryBuilder::new("SELECT * FROM Member WHERE 1=1");
+
+    if !include_alumni {
+        query.push(" AND status = 'active'");
+    }
+
+    if let Some(y) = year {
+        query.push(" AND year = ");
+        query.push_bind(y);
+    }
+
+    if let Some(g) = group_id {
+        query.push(" AND group_id = ");
+        query.push_bind(g);
+    }
+
+    query
 }
 
 #[ComplexObject]
 impl Member {
-    async fn attendance(&self, ctx: &Context<'_>) -> Vec<AttendanceInfo> {
+    #[graphql(complexity = "crate::limits::NESTED_LIST_FACTOR * child_complexity")]
+    async fn attendance(&self, ctx: &Context<'_>) -> Result<Vec<AttendanceInfo>> {
         let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
 
-        sqlx::query_as::<_, AttendanceInfo>(
+        Ok(sqlx::query_as::<_, AttendanceInfo>(
             "SELECT date, is_present, time_in, time_out FROM Attendance WHERE member_id = $1",
         )
         .bind(self.member_id)
         .fetch_all(pool.as_ref())
-        .await
-        .unwrap_or_default()
+        .await?)
+    }
+
+    async fn group(&self, ctx: &Context<'_>) -> Result<Group> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(
+            sqlx::query_as::<_, Group>("SELECT * FROM MemberGroup WHERE group_id = $1")
+                .bind(self.group_id)
+                .fetch_one(pool.as_ref())
+                .await?,
+        )
     }
 
-    #[graphql(name = "attendanceSummary")]
-    async fn attendance_summary(&self, ctx: &Context<'_>) -> Vec<AttendanceSummaryInfo> {
+    #[graphql(
+        name = "attendanceSummary",
+        complexity = "crate::limits::NESTED_LIST_FACTOR * child_complexity"
+    )]
+    async fn attendance_summary(&self, ctx: &Context<'_>) -> Result<Vec<AttendanceSummaryInfo>> {
         let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
 
-        sqlx::query_as::<_, AttendanceSummaryInfo>(
+        Ok(sqlx::query_as::<_, AttendanceSummaryInfo>(
             "SELECT year, month, days_attended FROM AttendanceSummary WHERE member_id = $1",
         )
         .bind(self.member_id)
         .fetch_all(pool.as_ref())
-        .await
-        .unwrap_or_default()
+        .await?)
     }
 
-    async fn streak(&self, ctx: &Context<'_>) -> Vec<StatusUpdateStreakInfo> {
+    async fn streak(&self, ctx: &Context<'_>) -> Result<Vec<StatusUpdateStreakInfo>> {
         let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
 
-        sqlx::query_as::<_, StatusUpdateStreakInfo>(
+        Ok(sqlx::query_as::<_, StatusUpdateStreakInfo>(
             "SELECT current_streak, max_streak FROM StatusUpdateStreak WHERE member_id = $1",
         )
         .bind(self.member_id)
         .fetch_all(pool.as_ref())
-        .await
-        .unwrap_or_default()
+        .await?)
     }
 
-    async fn projects(&self, ctx: &Context<'_>) -> Vec<Project> {
+    /// Events the member responded to or checked in at, latest first.
+    #[graphql(complexity = "crate::limits::NESTED_LIST_FACTOR * child_complexity")]
+    async fn events(&self, ctx: &Context<'_>) -> Result<Vec<MemberEvent>> {
         let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
 
-        sqlx::query_as::<_, Project>("SELECT project_id, title FROM Project WHERE member_id = $1")
-            .bind(self.member_id)
-            .fetch_all(pool.as_ref())
-            .await
-            .unwrap_or_default()
+        Ok(sqlx::query_as::<_, MemberEvent>(
+            "SELECT ev.*, rsvp.status AS rsvp, att.checked_in_at
+             FROM Event ev
+             LEFT JOIN EventRsvp rsvp ON rsvp.event_id = ev.event_id AND rsvp.member_id = $1
+             LEFT JOIN EventAttendance att ON att.event_id = ev.event_id AND att.member_id = $1
+             WHERE rsvp.member_id IS NOT NULL OR att.member_id IS NOT NULL
+             ORDER BY ev.starts_at DESC",
+        )
+        .bind(self.member_id)
+        .fetch_all(pool.as_ref())
+        .await?)
+    }
+
+    /// The primary device first, then the rest in the order they were added. Only the member and
+    /// admins can see them, since they tell where the member has been.
+    #[graphql(
+        complexity = "crate::limits::NESTED_LIST_FACTOR * child_complexity",
+        guard = "MemberGuard::new(self.member_id)"
+    )]
+    async fn devices(&self, ctx: &Context<'_>) -> Result<Vec<MemberDevice>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(sqlx::query_as::<_, MemberDevice>(
+            "SELECT * FROM MemberDevice WHERE member_id = $1
+             ORDER BY is_primary DESC, device_id",
+        )
+        .bind(self.member_id)
+        .fetch_all(pool.as_ref())
+        .await?)
+    }
+
+    /// The MAC address change waiting for an admin's approval, if any, for the member and admins.
+    #[graphql(
+        name = "pendingProfileChange",
+        guard = "MemberGuard::new(self.member_id)"
+    )]
+    async fn pending_profile_change(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<Option<ProfileChangeRequest>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(sqlx::query_as::<_, ProfileChangeRequest>(
+            "SELECT * FROM ProfileChangeRequest WHERE member_id = $1 AND status = 'pending'",
+        )
+        .bind(self.member_id)
+        .fetch_optional(pool.as_ref())
+        .await?)
+    }
+
+    #[graphql(complexity = "crate::limits::NESTED_LIST_FACTOR * child_complexity")]
+    async fn projects(&self, ctx: &Context<'_>) -> Result<Vec<Project>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(
+            sqlx::query_as::<_, Project>("SELECT * FROM Project WHERE member_id = $1")
+                .bind(self.member_id)
+                .fetch_all(pool.as_ref())
+                .await?,
+        )
     }
 }
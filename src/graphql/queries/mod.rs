@@ -1,9 +1,17 @@
 pub mod attendance_queries;
+pub mod calendar_queries;
+pub mod event_queries;
+pub mod group_queries;
 pub mod member_queries;
 pub mod project_queries;
 pub mod streak_queries;
+pub mod webhook_queries;
 
 pub use attendance_queries::AttendanceQueries;
+pub use calendar_queries::CalendarQueries;
+pub use event_queries::EventQueries;
+pub use group_queries::GroupQueries;
 pub use member_queries::MemberQueries;
 pub use project_queries::ProjectQueries;
 pub use streak_queries::StreakQueries;
+pub use webhook_queries::WebhookQueries;
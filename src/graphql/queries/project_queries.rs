@@ -1,7 +1,8 @@
 use std::sync::Arc;
 
+use crate::error::Result;
 use crate::models::project::Project;
-use async_graphql::{Context, Object, Result};
+use async_graphql::{Context, Object};
 use sqlx::PgPool;
 
 #[derive(Default)]
@@ -1,7 +1,8 @@
 use std::sync::Arc;
 
+use crate::error::{Result, RootError};
 use crate::models::status_update_streak::StatusUpdateStreak as Streak;
-use async_graphql::{Context, Object, Result};
+use async_graphql::{Context, Object};
 use sqlx::PgPool;
 
 #[derive(Default)]
@@ -12,12 +13,11 @@ impl StreakQueries {
     async fn streak(&self, ctx: &Context<'_>, member_id: i32) -> Result<Streak> {
         let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
 
-        Ok(sqlx::query_as::<_, Streak>(
-            "SELECT current_streak, max_streak FROM StatusUpdateStreak WHERE member_id = $1",
-        )
-        .bind(member_id)
-        .fetch_one(pool.as_ref())
-        .await?)
+        sqlx::query_as::<_, Streak>("SELECT * FROM StatusUpdateStreak WHERE member_id = $1")
+            .bind(member_id)
+            .fetch_optional(pool.as_ref())
+            .await?
+            .ok_or_else(|| RootError::NotFound(format!("No streak for member {}", member_id)))
     }
 
     async fn streaks(&self, ctx: &Context<'_>) -> Result<Vec<Streak>> {
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use async_graphql::{ComplexObject, Context, Object};
+use sqlx::PgPool;
+
+use crate::auth::AdminGuard;
+use crate::error::Result;
+use crate::models::webhook::{
+    Webhook, WebhookDelivery, WebhookDeliveryAttempt, WebhookDeliveryStatus,
+};
+
+#[derive(Default)]
+pub struct WebhookQueries;
+
+#[Object]
+impl WebhookQueries {
+    #[graphql(guard = "AdminGuard")]
+    async fn webhooks(&self, ctx: &Context<'_>) -> Result<Vec<Webhook>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(
+            sqlx::query_as::<_, Webhook>("SELECT * FROM Webhook ORDER BY webhook_id")
+                .fetch_all(pool.as_ref())
+                .await?,
+        )
+    }
+
+    /// A webhook's most recent deliveries, newest first.
+    #[graphql(name = "webhookDeliveries", guard = "AdminGuard")]
+    async fn webhook_deliveries(
+        &self,
+        ctx: &Context<'_>,
+        webhook_id: i32,
+        status: Option<WebhookDeliveryStatus>,
+        #[graphql(default = 50, validator(minimum = 1, maximum = 500))] limit: i64,
+    ) -> Result<Vec<WebhookDelivery>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(sqlx::query_as::<_, WebhookDelivery>(
+            "SELECT del.delivery_id, del.webhook_id, del.event_id, ev.event_type, del.status,
+                    del.attempts, del.next_attempt_at, del.last_response_status, del.last_error,
+                    del.delivered_at
+             FROM WebhookDelivery del
+             JOIN WebhookEvent ev ON ev.event_id = del.event_id
+             WHERE del.webhook_id = $1 AND ($2::webhook_delivery_status IS NULL OR del.status = $2)
+             ORDER BY del.delivery_id DESC
+             LIMIT $3",
+        )
+        .bind(webhook_id)
+        .bind(status)
+        .bind(limit)
+        .fetch_all(pool.as_ref())
+        .await?)
+    }
+}
+
+#[ComplexObject]
+impl WebhookDelivery {
+    /// Every attempt made so far, oldest first.
+    #[graphql(name = "attemptLog")]
+    async fn attempt_log(&self, ctx: &Context<'_>) -> Result<Vec<WebhookDeliveryAttempt>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(sqlx::query_as::<_, WebhookDeliveryAttempt>(
+            "SELECT attempted_at, response_status, error, duration_ms
+             FROM WebhookDeliveryAttempt WHERE delivery_id = $1 ORDER BY attempt_id",
+        )
+        .bind(self.delivery_id)
+        .fetch_all(pool.as_ref())
+        .await?)
+    }
+}
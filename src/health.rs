@@ -0,0 +1,84 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde_json::json;
+use tracing::warn;
+
+use crate::routes::AppState;
+use crate::MIGRATOR;
+
+/// Probes shouldn't hang for the pool's full acquire timeout when the database is down.
+const DATABASE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Liveness: the process is up and serving requests.
+pub async fn health() -> impl IntoResponse {
+    Json(json!({ "status": "ok" }))
+}
+
+/// Readiness: the database is reachable, every migration has been applied, every background task
+/// is running and Root isn't shutting down. Responds with 503 and the failing checks otherwise.
+pub async fn ready(State(state): State<AppState>) -> impl IntoResponse {
+    let applied = tokio::time::timeout(
+        DATABASE_TIMEOUT,
+        sqlx::query_scalar::<_, i64>("SELECT version FROM _sqlx_migrations WHERE success")
+            .fetch_all(state.pool.as_ref()),
+    )
+    .await;
+
+    let (database, migrations) = match applied {
+        Ok(Ok(applied)) => {
+            let applied: HashSet<i64> = applied.into_iter().collect();
+            let pending = MIGRATOR
+                .iter()
+                .filter(|migration| !migration.migration_type.is_down_migration())
+                .filter(|migration| !applied.contains(&migration.version))
+                .count();
+            let migrations = match pending {
+                0 => "ok".to_string(),
+                pending => format!("{} pending", pending),
+            };
+            ("ok", migrations)
+        }
+        Ok(Err(e)) => {
+            warn!("Readiness check could not query the database: {:?}", e);
+            ("unreachable", "unknown".to_string())
+        }
+        Err(_) => ("timed out", "unknown".to_string()),
+    };
+
+    let shutting_down = state.supervisor.is_shutting_down();
+    let is_ready =
+        database == "ok" && migrations == "ok" && state.supervisor.is_healthy() && !shutting_down;
+    let status = if is_ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status,
+        Json(json!({
+            "status": match (is_ready, shutting_down) {
+                (true, _) => "ready",
+                (false, true) => "shutting down",
+                (false, false) => "not ready",
+            },
+            "checks": {
+                "database": database,
+                "migrations": migrations,
+                "tasks": state.supervisor.health(),
+            },
+        })),
+    )
+}
+
+/// The running build, and when the daily task last finished. `lastDailyTaskRun` is null until it
+/// first runs after a restart.
+pub async fn version(State(state): State<AppState>) -> impl IntoResponse {
+    Json(json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "commit": env!("ROOT_GIT_COMMIT"),
+        "lastDailyTaskRun": state.daily_task.last_run(),
+    }))
+}
@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use async_graphql::{EmptySubscription, Schema};
+use sqlx::{migrate::Migrator, PgPool};
+use tracing::info;
+
+use clock::ClubClock;
+use config::Config;
+use graphql::{Mutation, Query};
+use limits::QueryLimits;
+use persisted_queries::PersistedQueries;
+
+pub mod academic_year;
+pub mod auth;
+pub mod calendar;
+pub mod cli;
+pub mod clock;
+pub mod config;
+pub mod daily_task;
+pub mod email;
+pub mod error;
+pub mod export;
+pub mod graphql;
+pub mod health;
+pub mod limits;
+pub mod logging;
+pub mod member_import;
+pub mod metrics;
+pub mod models;
+pub mod notifier;
+pub mod persisted_queries;
+pub mod presence;
+pub mod routes;
+pub mod seed;
+pub mod supervisor;
+pub mod telemetry;
+pub mod validation;
+pub mod webhooks;
+
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Builds the schema served at `/`, with everything resolvers expect in its context. Resolvers
+/// tell the time with `clock`.
+pub fn build_graphql_schema(
+    pool: Arc<PgPool>,
+    config: &Config,
+    clock: ClubClock,
+) -> Schema<Query, Mutation, EmptySubscription> {
+    let persisted_queries =
+        PersistedQueries::new(&config.persisted_queries).unwrap_or_else(|e| panic!("{}", e));
+    if config.persisted_queries.allowlist_only {
+        info!(
+            "Only the {} registered operations can be run without an admin API key.",
+            persisted_queries.registered_operations()
+        );
+    }
+
+    let operation_names = persisted_queries.operation_names();
+
+    let mut schema = Schema::build(Query::default(), Mutation::default(), EmptySubscription)
+        .data(pool)
+        .data(config.secret.clone())
+        .data(config.attendance.clone())
+        .data(clock)
+        .extension(persisted_queries)
+        .extension(metrics::GraphQLMetrics::new(operation_names))
+        .extension(QueryLimits {
+            max_depth: config.limits.max_depth,
+            max_complexity: config.limits.max_complexity,
+        });
+    // A span per resolved field is only worth its overhead when someone is looking at traces.
+    if config.telemetry.enabled {
+        schema = schema.extension(async_graphql::extensions::Tracing);
+    }
+    schema.finish()
+}
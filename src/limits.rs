@@ -0,0 +1,186 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::num::NonZeroU32;
+use std::sync::Arc;
+
+use async_graphql::{
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextValidation},
+    ErrorExtensionValues, ServerError, ValidationResult,
+};
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::header::AUTHORIZATION,
+    middleware::Next,
+    response::Response,
+};
+use governor::{
+    clock::{Clock, DefaultClock, QuantaInstant},
+    DefaultKeyedRateLimiter, NotUntil, Quota,
+};
+
+use crate::auth::Caller;
+use crate::config::LimitsConfig;
+use crate::error::RootError;
+use crate::routes::AppState;
+
+/// How many items a nested list field is assumed to return when estimating query complexity.
+/// Without it, `members { attendance { ... } }` would cost the same as fetching a single member.
+pub const NESTED_LIST_FACTOR: usize = 10;
+
+/// Rejects queries that are nested deeper or estimated to be more expensive than configured,
+/// telling the client which limit was hit, by how much, and what the limit is.
+pub struct QueryLimits {
+    pub max_depth: usize,
+    pub max_complexity: usize,
+}
+
+impl ExtensionFactory for QueryLimits {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(QueryLimits {
+            max_depth: self.max_depth,
+            max_complexity: self.max_complexity,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for QueryLimits {
+    async fn validation(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        next: NextValidation<'_>,
+    ) -> Result<ValidationResult, Vec<ServerError>> {
+        let result = next.run(ctx).await?;
+
+        if result.depth > self.max_depth {
+            return Err(vec![limit_exceeded(
+                "depth",
+                format!(
+                    "Query is nested {} levels deep, more than the limit of {}",
+                    result.depth, self.max_depth
+                ),
+            )]);
+        }
+        if result.complexity > self.max_complexity {
+            return Err(vec![limit_exceeded(
+                "complexity",
+                format!(
+                    "Query has an estimated complexity of {}, more than the limit of {}. Request fewer fields or nested lists at once.",
+                    result.complexity, self.max_complexity
+                ),
+            )]);
+        }
+        Ok(result)
+    }
+}
+
+fn limit_exceeded(limit: &str, message: String) -> ServerError {
+    let mut extensions = ErrorExtensionValues::default();
+    extensions.set("code", "LIMIT_EXCEEDED");
+    extensions.set("limit", limit);
+
+    let mut error = ServerError::new(message, None);
+    error.extensions = Some(extensions);
+    error
+}
+
+#[derive(Clone, Hash, PartialEq, Eq)]
+enum ClientKey {
+    ApiKey(i32),
+    Address(IpAddr),
+}
+
+/// Per-client request budgets. Callers with an API key are limited per key, everyone else per IP
+/// address with a smaller budget. Requests with a key are also limited per address before the key
+/// is looked up, so guessing keys is no cheaper than spending one.
+pub struct RateLimiter {
+    authenticated: DefaultKeyedRateLimiter<ClientKey>,
+    anonymous: DefaultKeyedRateLimiter<ClientKey>,
+    key_lookups: DefaultKeyedRateLimiter<ClientKey>,
+    trust_forwarded_for: bool,
+}
+
+impl RateLimiter {
+    pub fn new(config: &LimitsConfig) -> Self {
+        let per_minute = |n: u32| Quota::per_minute(NonZeroU32::new(n).expect("Validated on load"));
+        Self {
+            authenticated: governor::RateLimiter::keyed(per_minute(
+                config.authenticated_requests_per_minute,
+            )),
+            anonymous: governor::RateLimiter::keyed(per_minute(
+                config.anonymous_requests_per_minute,
+            )),
+            key_lookups: governor::RateLimiter::keyed(per_minute(
+                config.authenticated_requests_per_minute,
+            )),
+            trust_forwarded_for: config.trust_forwarded_for,
+        }
+    }
+
+    /// Forgets clients whose budget has fully refilled, so the limiter doesn't grow without bound.
+    pub fn retain_recent(&self) {
+        self.authenticated.retain_recent();
+        self.anonymous.retain_recent();
+        self.key_lookups.retain_recent();
+    }
+
+    fn client_address(&self, request: &Request) -> IpAddr {
+        let forwarded = self
+            .trust_forwarded_for
+            .then(|| request.headers().get("x-forwarded-for"))
+            .flatten()
+            .and_then(|value| value.to_str().ok())
+            // The first address is the client's, the rest are proxies it passed through.
+            .and_then(|value| value.split(',').next())
+            .and_then(|address| address.trim().parse().ok());
+
+        forwarded
+            .or_else(|| {
+                request
+                    .extensions()
+                    .get::<ConnectInfo<SocketAddr>>()
+                    .map(|ConnectInfo(address)| address.ip())
+            })
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+    }
+}
+
+/// Responds with 429 and a `Retry-After` header once an address has used up its budget. Must run
+/// before `auth::identify_caller`, so keys aren't looked up for addresses that are over budget.
+pub async fn limit_address(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, RootError> {
+    let limiter = &state.rate_limiter;
+    let address = ClientKey::Address(limiter.client_address(&request));
+    let checked = if request.headers().contains_key(AUTHORIZATION) {
+        limiter.key_lookups.check_key(&address)
+    } else {
+        limiter.anonymous.check_key(&address)
+    };
+    limited(checked)?;
+    Ok(next.run(request).await)
+}
+
+/// Responds with 429 and a `Retry-After` header once an API key has used up its budget. Must run
+/// after `auth::identify_caller`, which finds the caller's key.
+pub async fn limit_api_key(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, RootError> {
+    if let Some(caller) = request.extensions().get::<Caller>() {
+        let key = ClientKey::ApiKey(caller.api_key_id);
+        limited(state.rate_limiter.authenticated.check_key(&key))?;
+    }
+    Ok(next.run(request).await)
+}
+
+fn limited(checked: Result<(), NotUntil<QuantaInstant>>) -> Result<(), RootError> {
+    checked.map_err(|not_until| {
+        let wait = not_until.wait_time_from(DefaultClock::default().now());
+        RootError::RateLimited {
+            retry_after_secs: wait.as_secs().max(1),
+        }
+    })
+}
@@ -0,0 +1,165 @@
+use std::io;
+
+use chrono_tz::Tz;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use rolling_file::{BasicRollingFileAppender, RollingConditionBasic};
+use tracing::{info, Subscriber};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::{
+    field::RecordFields,
+    fmt::{
+        self,
+        format::{PrettyFields, Writer},
+        time::FormatTime,
+        FormatFields, MakeWriter,
+    },
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+    EnvFilter, Layer, Registry,
+};
+
+use crate::config::{Config, LogFormat, LogRotation, LoggingConfig};
+use crate::telemetry;
+
+/// Stamps log lines in the club's timezone. The offset is looked up for each line, so the logs
+/// follow daylight saving changes without a restart.
+#[derive(Clone, Copy)]
+struct Timer(Tz);
+
+impl FormatTime for Timer {
+    fn format_time(&self, w: &mut Writer<'_>) -> std::fmt::Result {
+        let now = chrono::Utc::now().with_timezone(&self.0);
+        write!(w, "{}", now.to_rfc2822())
+    }
+}
+
+/// Flushes buffered log lines and exported spans once Root is done.
+pub struct TracingGuard {
+    _file: WorkerGuard,
+    tracer_provider: Option<SdkTracerProvider>,
+}
+
+impl TracingGuard {
+    pub async fn shutdown(self) {
+        if let Some(provider) = self.tracer_provider {
+            // Flushing blocks until the exporter is done, which needs the runtime to make progress.
+            let flushed = tokio::task::spawn_blocking(move || provider.shutdown()).await;
+            if let Ok(Err(e)) = flushed {
+                eprintln!("Failed to flush traces: {}", e);
+            }
+        }
+    }
+}
+
+/// Logs to the configured file, and to stdout if enabled. The file is appended to and rotated
+/// rather than truncated on start. Traces are exported too if telemetry is enabled.
+///
+/// Writes to the file happen on a background thread, so the returned guard must be held until
+/// Root exits.
+pub fn setup_tracing(config: &Config) -> TracingGuard {
+    let timer = Timer(config.attendance.timezone);
+
+    let file = log_file(&config.logging).unwrap_or_else(|e| {
+        panic!(
+            "Could not open log file {}: {}",
+            config.logging.file.display(),
+            e
+        )
+    });
+    let (file, file_guard) = tracing_appender::non_blocking(file);
+
+    // Each layer filters for itself, so exported spans don't depend on the log level.
+    let mut layers: Vec<Box<dyn Layer<Registry> + Send + Sync>> = Vec::new();
+    if config.log_to_stdout() {
+        layers.push(
+            format_layer(config.logging.format, timer, io::stdout, true)
+                .with_filter(EnvFilter::new(config.log_level()))
+                .boxed(),
+        );
+    }
+    // ANSI encodings are unreadable in the raw file.
+    layers.push(
+        format_layer(config.logging.format, timer, file, false)
+            .with_filter(EnvFilter::new(config.log_level()))
+            .boxed(),
+    );
+
+    let tracer_provider = config
+        .telemetry
+        .enabled
+        .then(|| telemetry::tracer_provider(&config.telemetry).unwrap_or_else(|e| panic!("{}", e)));
+    if let Some(provider) = &tracer_provider {
+        layers.push(
+            telemetry::layer(provider)
+                .with_filter(EnvFilter::new(&config.telemetry.level))
+                .boxed(),
+        );
+    }
+
+    tracing_subscriber::registry().with(layers).init();
+
+    if config.is_dev() {
+        info!("Running in development mode.");
+    } else {
+        info!("Running in production mode.");
+    }
+    if config.telemetry.enabled {
+        info!("Exporting traces to {}", config.telemetry.endpoint);
+    }
+
+    TracingGuard {
+        _file: file_guard,
+        tracer_provider,
+    }
+}
+
+fn format_layer<S, W>(
+    format: LogFormat,
+    timer: Timer,
+    writer: W,
+    ansi: bool,
+) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = fmt::layer()
+        .with_timer(timer)
+        .with_ansi(ansi)
+        .with_writer(writer);
+    match format {
+        LogFormat::Pretty if ansi => layer.pretty().boxed(),
+        LogFormat::Pretty => layer
+            .pretty()
+            .fmt_fields(PlainFields(PrettyFields::new()))
+            .boxed(),
+        LogFormat::Json => layer.json().boxed(),
+    }
+}
+
+/// Span fields are formatted once per formatter type and cached on the span. Without a type of
+/// its own, the file would reuse the ANSI-coloured fields formatted for stdout.
+struct PlainFields(PrettyFields);
+
+impl<'writer> FormatFields<'writer> for PlainFields {
+    fn format_fields<R: RecordFields>(
+        &self,
+        writer: Writer<'writer>,
+        fields: R,
+    ) -> std::fmt::Result {
+        self.0.format_fields(writer, fields)
+    }
+}
+
+fn log_file(config: &LoggingConfig) -> io::Result<BasicRollingFileAppender> {
+    let condition = match config.rotation {
+        // Never rolls over, but still appends instead of truncating.
+        LogRotation::Never => RollingConditionBasic::new(),
+        LogRotation::Daily => RollingConditionBasic::new().daily(),
+        LogRotation::Size => {
+            RollingConditionBasic::new().max_size(config.max_file_size_mb * 1024 * 1024)
+        }
+    };
+    BasicRollingFileAppender::new(&config.file, condition, config.retained_files)
+}
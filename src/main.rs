@@ -1,136 +1,423 @@
-use async_graphql::EmptySubscription;
 use axum::http::{HeaderValue, Method};
-use sqlx::PgPool;
+use chrono::NaiveDate;
+use clap::Parser;
+use sqlx::{
+    migrate::{Migrate, MigrateError},
+    PgPool,
+};
 use std::sync::Arc;
-use time::UtcOffset;
+use std::time::Duration;
 use tower_http::cors::CorsLayer;
-use tracing::info;
-use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
-
-use daily_task::run_daily_task_at_midnight;
-use graphql::{Mutation, Query};
-use routes::setup_router;
-
-pub mod daily_task;
-pub mod graphql;
-pub mod models;
-pub mod routes;
-
-/// Handles all over environment variables in one place.
-// TODO: Replace with `Config.rs` crate.
-struct Config {
-    env: String,
-    secret_key: String,
-    database_url: String,
-    port: String,
-}
+use tracing::{info, warn};
+
+use root::cli::{Cli, Command, Job, MigrateAction};
+use root::clock::ClubClock;
+use root::config::{Config, CorsConfig, DatabaseConfig};
+use root::daily_task::{self, run_daily_task_at_midnight, DailyTaskStatus};
+use root::email::{EmailDispatcher, EmailTemplates, SmtpMailer};
+use root::limits::RateLimiter;
+use root::notifier::Notifier;
+use root::routes::{setup_router, AppState};
+use root::supervisor::Supervisor;
+use root::webhooks::WebhookDispatcher;
+use root::{auth, build_graphql_schema, export, logging, member_import, metrics, seed, MIGRATOR};
 
-impl Config {
-    fn from_env() -> Self {
-        let _ = dotenv::dotenv();
-        Self {
-            env: std::env::var("ROOT_ENV").unwrap_or_else(|_| "development".to_string()),
-            secret_key: std::env::var("ROOT_SECRET").expect("ROOT_SECRET must be set."),
-            database_url: std::env::var("DATABASE_URL").expect("DATABASE_URL must be set."),
-            port: std::env::var("ROOT_PORT").expect("ROOT_PORT must be set."),
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    let config = match Config::load(cli.config.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Invalid configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if cli.print_config {
+        print!("{}", config.to_redacted_toml());
+        return;
+    }
+
+    match cli.command {
+        None | Some(Command::Serve) => serve(config).await,
+        Some(Command::Migrate { action }) => migrate(config, action).await,
+        Some(Command::RunJob { job, date }) => run_job(config, job, date).await,
+        Some(Command::Seed) => seed_database(config).await,
+        Some(Command::Export {
+            table,
+            format,
+            output,
+            filter,
+        }) => {
+            let pool = setup_database(&config.database).await;
+            let written = match output {
+                Some(path) => {
+                    let mut file = std::fs::File::create(&path)
+                        .unwrap_or_else(|e| panic!("Could not create {}: {}", path.display(), e));
+                    export::write_export(pool, table, &filter, format, &mut file).await
+                }
+                None => {
+                    let mut stdout = std::io::stdout().lock();
+                    export::write_export(pool, table, &filter, format, &mut stdout).await
+                }
+            };
+            if let Err(e) = written {
+                eprintln!("Export failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Command::ImportMembers { path, dry_run }) => {
+            import_members_from_file(config, path, dry_run).await
+        }
+        Some(Command::CreateApiKey {
+            name,
+            role,
+            member_id,
+        }) => {
+            let pool = setup_database(&config.database).await;
+            let key = auth::create_api_key(&pool, &name, role, member_id)
+                .await
+                .expect("Failed to create API key.");
+            println!("{}", key);
         }
     }
 }
 
-#[tokio::main]
-async fn main() {
-    let config = Config::from_env();
-    setup_tracing(&config.env);
+async fn serve(config: Config) {
+    let tracing_guard = logging::setup_tracing(&config);
+    metrics::init();
 
-    let pool = setup_database(&config.database_url).await;
-    let schema = build_graphql_schema(pool.clone(), config.secret_key);
+    let pool = setup_database(&config.database).await;
+    let clock = ClubClock::system(config.attendance.timezone);
+    let schema = build_graphql_schema(pool.clone(), &config, clock.clone());
 
-    tokio::task::spawn(async {
-        run_daily_task_at_midnight(pool).await;
+    let supervisor = Supervisor::default();
+    let daily_task = Arc::new(DailyTaskStatus::default());
+    if config.scheduler.enabled {
+        let task_pool = pool.clone();
+        let run_at = config.scheduler.run_at;
+        let task_clock = clock.clone();
+        let notifier = config
+            .notifier
+            .enabled
+            .then(|| Arc::new(Notifier::from_config(pool.clone(), &config.notifier)));
+        let status = daily_task.clone();
+        supervisor.spawn("dailyTask", move |shutdown| {
+            run_daily_task_at_midnight(
+                task_pool.clone(),
+                run_at,
+                task_clock.clone(),
+                notifier.clone(),
+                status.clone(),
+                shutdown,
+            )
+        });
+    }
+
+    if config.webhooks.enabled {
+        let dispatcher = Arc::new(WebhookDispatcher::new(
+            pool.clone(),
+            clock.clone(),
+            &config.webhooks,
+        ));
+        supervisor.spawn("webhookDelivery", move |shutdown| {
+            let dispatcher = dispatcher.clone();
+            async move { dispatcher.run(shutdown).await }
+        });
+    }
+
+    if config.email.enabled {
+        let templates = match EmailTemplates::load(config.email.templates_dir.as_deref()) {
+            Ok(templates) => templates,
+            Err(e) => {
+                eprintln!("Invalid email templates: {}", e);
+                std::process::exit(1);
+            }
+        };
+        let mailer = match SmtpMailer::new(&config.email) {
+            Ok(mailer) => mailer,
+            Err(e) => {
+                eprintln!("Invalid SMTP settings: {}", e);
+                std::process::exit(1);
+            }
+        };
+        let dispatcher = Arc::new(EmailDispatcher::new(
+            pool.clone(),
+            clock.clone(),
+            Arc::new(mailer),
+            templates,
+            &config.email,
+        ));
+        supervisor.spawn("emailDelivery", move |shutdown| {
+            let dispatcher = dispatcher.clone();
+            async move { dispatcher.run(shutdown).await }
+        });
+    }
+
+    let rate_limiter = Arc::new(RateLimiter::new(&config.limits));
+    let cleanup_limiter = rate_limiter.clone();
+    supervisor.spawn("rateLimitCleanup", move |shutdown| {
+        let limiter = cleanup_limiter.clone();
+        async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60));
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => limiter.retain_recent(),
+                    _ = shutdown.cancelled() => return,
+                }
+            }
+        }
     });
 
-    let cors = setup_cors();
-    let router = setup_router(schema, cors, config.env == "development");
+    let cors = setup_cors(&config.cors);
+    let state = AppState {
+        schema,
+        pool: pool.clone(),
+        daily_task,
+        supervisor: supervisor.clone(),
+        rate_limiter,
+        clock,
+        secret: config.secret.clone(),
+        attendance: config.attendance.clone(),
+    };
+    let router = setup_router(state, cors, config.is_dev());
 
     info!("Starting Root...");
-    let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", config.port))
+    let listener = tokio::net::TcpListener::bind((config.server.host.as_str(), config.server.port))
         .await
-        .unwrap();
-    axum::serve(listener, router).await.unwrap();
+        .unwrap_or_else(|e| {
+            panic!(
+                "Could not listen on {}:{}: {}",
+                config.server.host, config.server.port, e
+            )
+        });
+
+    let signal_supervisor = supervisor.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        info!("Shutting down, waiting for in-flight requests to finish...");
+        signal_supervisor.shutdown();
+    });
+
+    // The drain is bounded so a stuck request can't hold up a deploy forever.
+    let shutdown_timeout = Duration::from_secs(config.server.shutdown_timeout_secs);
+    let server = axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .with_graceful_shutdown(supervisor.shutdown_requested());
+    tokio::select! {
+        result = server => result.expect("Server failed."),
+        _ = async {
+            supervisor.shutdown_requested().await;
+            tokio::time::sleep(shutdown_timeout).await;
+        } => warn!("In-flight requests did not finish in time and were dropped."),
+    }
+
+    supervisor.join(shutdown_timeout).await;
+    pool.close().await;
+    info!("Root stopped.");
+    tracing_guard.shutdown().await;
 }
 
-fn setup_tracing(env: &str) {
-    let kolkata_offset = UtcOffset::from_hms(5, 30, 0).expect("Hardcoded offset must be correct");
-    let timer = fmt::time::OffsetTime::new(
-        kolkata_offset,
-        time::format_description::well_known::Rfc2822,
+/// Resolves on Ctrl+C, or SIGTERM on Unix, which is what container orchestrators send.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to listen for Ctrl+C.");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM.")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+async fn import_members_from_file(config: Config, path: std::path::PathBuf, dry_run: bool) {
+    let file = std::fs::File::open(&path)
+        .unwrap_or_else(|e| panic!("Could not open {}: {}", path.display(), e));
+    let pool = setup_database(&config.database).await;
+
+    let clock = ClubClock::system(config.attendance.timezone);
+
+    let report =
+        member_import::import_members(&pool, &clock, file, config.attendance.final_year, dry_run)
+            .await
+            .expect("Import must not fail on database errors.");
+
+    for error in &report.errors {
+        match &error.field {
+            Some(field) => eprintln!("row {}: {}: {}", error.row, field, error.message),
+            None => eprintln!("row {}: {}", error.row, error.message),
+        }
+    }
+    let verb = if dry_run { "Would import" } else { "Imported" };
+    println!(
+        "{} {} of {} rows.",
+        verb,
+        report.imported.len(),
+        report.total_rows
     );
-    if env == "production" {
-        tracing_subscriber::registry()
-            // Don't waste resources writing to unmonitored stdout in production
-            .with(
-                fmt::layer()
-                    .event_format(fmt::format().with_timer(timer.clone()))
-                    .pretty()
-                    .with_ansi(false) // ANSI encodings are unreadable in the raw file.
-                    .with_writer(std::fs::File::create("root.log").unwrap()),
-            )
-            .with(EnvFilter::new("info"))
-            .init();
-        info!("Running in production mode.")
-    } else {
-        tracing_subscriber::registry()
-            .with(
-                fmt::layer()
-                    .event_format(fmt::format().with_timer(timer.clone()))
-                    .pretty()
-                    .with_writer(std::io::stdout),
-            )
-            .with(
-                fmt::layer()
-                    .event_format(fmt::format().with_timer(timer.clone()))
-                    .pretty()
-                    .with_ansi(false)
-                    .with_writer(std::fs::File::create("root.log").unwrap()),
-            )
-            .with(EnvFilter::new("trace"))
-            .init();
-        info!("Running in development mode.");
+
+    if !report.errors.is_empty() {
+        std::process::exit(1);
     }
 }
 
-async fn setup_database(database_url: &str) -> Arc<PgPool> {
-    let pool = sqlx::postgres::PgPoolOptions::new()
-        .min_connections(2)
-        .max_connections(3)
-        .connect(database_url)
+/// Applies every pending migration, reverts the latest ones or lists them all.
+async fn migrate(config: Config, action: MigrateAction) {
+    let pool = connect_database(&config.database).await;
+    let fail = |e: MigrateError| -> ! {
+        eprintln!("Migration failed: {}", e);
+        std::process::exit(1);
+    };
+
+    match action {
+        MigrateAction::Run => {
+            MIGRATOR
+                .run(pool.as_ref())
+                .await
+                .unwrap_or_else(|e| fail(e));
+            println!("Every migration has been applied.");
+        }
+        MigrateAction::Revert { target } => {
+            let applied = applied_migrations(&pool).await.unwrap_or_else(|e| fail(e));
+            let mut versions: Vec<i64> = applied.keys().copied().collect();
+            versions.sort_unstable();
+            let target = match target {
+                Some(target) => target,
+                // Everything newer than the second latest, i.e. just the latest.
+                None if !versions.is_empty() => {
+                    versions.len().checked_sub(2).map_or(0, |i| versions[i])
+                }
+                None => {
+                    println!("No migrations have been applied.");
+                    return;
+                }
+            };
+
+            MIGRATOR
+                .undo(pool.as_ref(), target)
+                .await
+                .unwrap_or_else(|e| fail(e));
+            for version in versions.iter().rev().filter(|&&version| version > target) {
+                println!("Reverted {}", version);
+            }
+        }
+        MigrateAction::Status => {
+            let applied = applied_migrations(&pool).await.unwrap_or_else(|e| fail(e));
+            for migration in MIGRATOR
+                .iter()
+                .filter(|migration| !migration.migration_type.is_down_migration())
+            {
+                let status = match applied.get(&migration.version) {
+                    Some(checksum) if *checksum == migration.checksum => "applied",
+                    Some(_) => "applied, but changed since",
+                    None => "pending",
+                };
+                println!(
+                    "{}  {:<32}  {}",
+                    migration.version, migration.description, status
+                );
+            }
+        }
+    }
+}
+
+/// The checksum of every applied migration, by version.
+async fn applied_migrations(
+    pool: &PgPool,
+) -> Result<std::collections::HashMap<i64, std::borrow::Cow<'static, [u8]>>, MigrateError> {
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    Ok(conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|migration| (migration.version, migration.checksum))
+        .collect())
+}
+
+/// Runs `job` for `date`, or today in the club's timezone, instead of waiting for the scheduler.
+async fn run_job(config: Config, job: Job, date: Option<NaiveDate>) {
+    let tracing_guard = logging::setup_tracing(&config);
+    let pool = setup_database(&config.database).await;
+    let date = date.unwrap_or_else(|| ClubClock::system(config.attendance.timezone).today());
+
+    let succeeded = match job {
+        Job::DailyTask => daily_task::execute_daily_task(pool, date).await,
+        Job::Digests => {
+            Notifier::from_config(pool, &config.notifier)
+                .send_digests(date)
+                .await
+        }
+    };
+    tracing_guard.shutdown().await;
+
+    if !succeeded {
+        eprintln!("The job did not complete, see the log for details.");
+        std::process::exit(1);
+    }
+}
+
+async fn seed_database(config: Config) {
+    if !config.is_dev() {
+        eprintln!("Refusing to seed a production database. Set `env = \"development\"` to seed.");
+        std::process::exit(1);
+    }
+
+    let pool = setup_database(&config.database).await;
+    let today = ClubClock::system(config.attendance.timezone).today();
+    let report = seed::seed(&pool, today)
         .await
-        .expect("Pool must be initialized properly.");
+        .expect("Seeding must not fail on a development database.");
 
-    sqlx::migrate!()
-        .run(&pool)
+    println!(
+        "Seeded {} groups, {} members and {} new attendance records.",
+        report.groups, report.members, report.attendance_records
+    );
+}
+
+/// Connects and applies any pending migrations.
+async fn setup_database(config: &DatabaseConfig) -> Arc<PgPool> {
+    let pool = connect_database(config).await;
+
+    MIGRATOR
+        .run(pool.as_ref())
         .await
         .expect("Failed to run migrations.");
 
-    Arc::new(pool)
+    pool
 }
 
-fn build_graphql_schema(
-    pool: Arc<PgPool>,
-    secret_key: String,
-) -> async_graphql::Schema<Query, Mutation, EmptySubscription> {
-    async_graphql::Schema::build(Query::default(), Mutation::default(), EmptySubscription)
-        .data(pool)
-        .data(secret_key)
-        .finish()
+async fn connect_database(config: &DatabaseConfig) -> Arc<PgPool> {
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .min_connections(config.min_connections)
+        .max_connections(config.max_connections)
+        .connect(&config.url)
+        .await
+        .expect("Pool must be initialized properly.");
+
+    Arc::new(pool)
 }
 
-fn setup_cors() -> CorsLayer {
-    // TODO: Replace hardcoded strings
-    let origins: [HeaderValue; 2] = [
-        "http://127.0.0.1:3000".parse().unwrap(),
-        "https://home.amfoss.in".parse().unwrap(),
-    ];
+fn setup_cors(config: &CorsConfig) -> CorsLayer {
+    let origins: Vec<HeaderValue> = config
+        .allowed_origins
+        .iter()
+        .map(|origin| origin.parse().expect("Origins are validated on load"))
+        .collect();
 
     CorsLayer::new()
         .allow_origin(origins)
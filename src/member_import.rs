@@ -0,0 +1,259 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+
+use serde::Deserialize;
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::clock::ClubClock;
+use crate::email;
+use crate::models::member::{CreateMemberInput, ImportReport, ImportRowError, Member, Sex};
+use crate::validation;
+use crate::webhooks::{self, Event};
+
+/// A CSV record with the same columns as `CreateMemberInput`. Everything is read as text so
+/// that a bad value is reported against its row instead of aborting the whole file.
+#[derive(Deserialize)]
+struct ImportRow {
+    roll_no: String,
+    name: String,
+    email: String,
+    sex: String,
+    year: String,
+    hostel: String,
+    mac_address: String,
+    discord_id: String,
+    group_id: String,
+}
+
+/// Values that must be unique across members, keyed by column name.
+type SeenValues = HashMap<&'static str, HashMap<String, i32>>;
+
+/// Validates every row of `reader` and inserts the valid ones in a single transaction.
+///
+/// Rows are checked for unparseable sex, year and group values, unknown groups, malformed roll
+/// numbers, emails, MAC addresses or Discord IDs, and for any of those four that duplicate an
+/// existing member or an earlier row. A dry run inserts and then rolls back, so the report is identical to a real run.
+pub async fn import_members<R: Read>(
+    pool: &PgPool,
+    clock: &ClubClock,
+    reader: R,
+    final_year: i32,
+    dry_run: bool,
+) -> Result<ImportReport, sqlx::Error> {
+    let mut seen = existing_values(pool).await?;
+    let groups: HashSet<i32> = sqlx::query_scalar("SELECT group_id FROM MemberGroup")
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .collect();
+
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    let mut total_rows = 0;
+    let mut valid = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, record) in csv_reader.deserialize::<ImportRow>().enumerate() {
+        // The header occupies line 1.
+        let row = index as i32 + 2;
+        total_rows += 1;
+
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                errors.push(ImportRowError {
+                    row,
+                    field: None,
+                    message: format!("Malformed row: {}", e),
+                });
+                continue;
+            }
+        };
+
+        match validate_row(row, record, final_year, &groups, &mut seen) {
+            Ok(input) => valid.push(input),
+            Err(row_errors) => errors.extend(row_errors),
+        }
+    }
+
+    let now = clock.now().naive_local();
+    let mut tx = pool.begin().await?;
+    let mut imported = Vec::with_capacity(valid.len());
+    for input in valid {
+        let member = sqlx::query_as::<_, Member>(
+            "INSERT INTO Member (roll_no, name, email, sex, year, hostel, mac_address, discord_id, group_id, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING *",
+        )
+        .bind(&input.roll_no)
+        .bind(&input.name)
+        .bind(&input.email)
+        .bind(input.sex)
+        .bind(input.year)
+        .bind(&input.hostel)
+        .bind(&input.mac_address)
+        .bind(&input.discord_id)
+        .bind(input.group_id)
+        .bind(now)
+        .fetch_one(&mut *tx)
+        .await?;
+        webhooks::enqueue(&mut *tx, Event::member_created(&member)).await?;
+        email::enqueue_welcome(&mut *tx, member.member_id).await?;
+        imported.push(member);
+    }
+
+    if dry_run {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+        info!(
+            "Imported {} of {} members from CSV.",
+            imported.len(),
+            total_rows
+        );
+    }
+
+    Ok(ImportReport {
+        dry_run,
+        total_rows,
+        imported,
+        errors,
+    })
+}
+
+async fn existing_values(pool: &PgPool) -> Result<SeenValues, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (String, String, String)>(
+        "SELECT roll_no, email, discord_id FROM Member",
+    )
+    .fetch_all(pool)
+    .await?;
+    // Members' primary MAC addresses are among their devices, so this covers both.
+    let mac_addresses = sqlx::query_scalar::<_, String>("SELECT mac_address FROM MemberDevice")
+        .fetch_all(pool)
+        .await?;
+
+    let mut seen = SeenValues::new();
+    for (roll_no, email, discord_id) in rows {
+        // Existing members are recorded as row 0 so the error can tell them apart from the file.
+        seen.entry("roll_no").or_default().insert(roll_no, 0);
+        seen.entry("email").or_default().insert(email, 0);
+        seen.entry("discord_id").or_default().insert(discord_id, 0);
+    }
+    for mac_address in mac_addresses {
+        seen.entry("mac_address")
+            .or_default()
+            .insert(mac_address, 0);
+    }
+
+    Ok(seen)
+}
+
+fn validate_row(
+    row: i32,
+    record: ImportRow,
+    final_year: i32,
+    groups: &HashSet<i32>,
+    seen: &mut SeenValues,
+) -> Result<CreateMemberInput, Vec<ImportRowError>> {
+    let mut errors = Vec::new();
+    let mut error = |field: &str, message: String| {
+        errors.push(ImportRowError {
+            row,
+            field: Some(field.to_string()),
+            message,
+        })
+    };
+
+    let sex = match record.sex.as_str() {
+        "M" => Some(Sex::M),
+        "F" => Some(Sex::F),
+        "Other" => Some(Sex::Other),
+        other => {
+            error("sex", format!("'{}' is not one of M, F or Other", other));
+            None
+        }
+    };
+
+    let year = match record.year.parse::<i32>() {
+        Ok(year) if (1..=final_year).contains(&year) => Some(year),
+        _ => {
+            error(
+                "year",
+                format!(
+                    "'{}' is not a year between 1 and {}",
+                    record.year, final_year
+                ),
+            );
+            None
+        }
+    };
+
+    let group_id = match record.group_id.parse::<i32>() {
+        Ok(group_id) if groups.contains(&group_id) => Some(group_id),
+        Ok(group_id) => {
+            error("group_id", format!("Group {} does not exist", group_id));
+            None
+        }
+        Err(_) => {
+            error(
+                "group_id",
+                format!("'{}' is not a valid group ID", record.group_id),
+            );
+            None
+        }
+    };
+
+    let mut normalise =
+        |field: &'static str, value: &str, check: fn(&str) -> Result<String, String>| {
+            check(value).map_err(|message| error(field, message)).ok()
+        };
+    let roll_no = normalise("roll_no", &record.roll_no, validation::roll_no);
+    let email = normalise("email", &record.email, validation::email);
+    let mac_address = normalise("mac_address", &record.mac_address, validation::mac_address);
+    let discord_id = normalise("discord_id", &record.discord_id, validation::discord_id);
+
+    let (Some(roll_no), Some(email), Some(mac_address), Some(discord_id)) =
+        (roll_no, email, mac_address, discord_id)
+    else {
+        return Err(errors);
+    };
+
+    let unique_fields = [
+        ("roll_no", &roll_no),
+        ("email", &email),
+        ("mac_address", &mac_address),
+        ("discord_id", &discord_id),
+    ];
+    for (field, value) in unique_fields {
+        match seen.get(field).and_then(|values| values.get(value)) {
+            Some(0) => error(field, format!("'{}' belongs to an existing member", value)),
+            Some(first) => error(field, format!("'{}' duplicates row {}", value, first)),
+            None => {}
+        }
+    }
+
+    let (Some(sex), Some(year), Some(group_id)) = (sex, year, group_id) else {
+        return Err(errors);
+    };
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    // Only rows that will be inserted claim their unique values.
+    for (field, value) in unique_fields {
+        seen.entry(field).or_default().insert(value.clone(), row);
+    }
+
+    Ok(CreateMemberInput {
+        roll_no,
+        name: record.name,
+        email,
+        sex,
+        year,
+        hostel: record.hostel,
+        mac_address,
+        discord_id,
+        group_id,
+    })
+}
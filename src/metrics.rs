@@ -0,0 +1,275 @@
+use std::collections::HashSet;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Instant;
+
+use async_graphql::{
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextExecute, NextParseQuery},
+    parser::types::ExecutableDocument,
+    Response as GraphQLResponse, ServerResult, Variables,
+};
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::header::CONTENT_TYPE,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+
+use crate::routes::AppState;
+
+/// Root's own registry, so nothing registered by a dependency ends up on `/metrics` unnoticed.
+static REGISTRY: LazyLock<Registry> =
+    LazyLock::new(|| Registry::new_custom(Some("root".to_string()), None).unwrap());
+
+static HTTP_REQUEST_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(HistogramVec::new(
+        HistogramOpts::new(
+            "http_request_duration_seconds",
+            "Time taken to respond to HTTP requests",
+        ),
+        &["method", "route", "status"],
+    ))
+});
+
+static GRAPHQL_OPERATION_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(HistogramVec::new(
+        HistogramOpts::new(
+            "graphql_operation_duration_seconds",
+            "Time taken to execute GraphQL operations",
+        ),
+        &["operation", "outcome"],
+    ))
+});
+
+static DB_POOL_CONNECTIONS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register(IntGaugeVec::new(
+        Opts::new("db_pool_connections", "Open database connections"),
+        &["state"],
+    ))
+});
+
+static DB_POOL_MAX_CONNECTIONS: LazyLock<IntGauge> = LazyLock::new(|| {
+    register(IntGauge::new(
+        "db_pool_max_connections",
+        "Connections the database pool may open",
+    ))
+});
+
+static ATTENDANCE_MARKS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "attendance_marks_total",
+            "Attendance marks by outcome, from markAttendance and scan batches",
+        ),
+        &["outcome"],
+    ))
+});
+
+static WEBHOOK_DELIVERIES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "webhook_delivery_attempts_total",
+            "Webhook delivery attempts by outcome",
+        ),
+        &["outcome"],
+    ))
+});
+
+static EMAIL_SENDS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "email_send_attempts_total",
+            "Email send attempts by outcome",
+        ),
+        &["outcome"],
+    ))
+});
+
+static DAILY_TASK_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(HistogramVec::new(
+        HistogramOpts::new(
+            "daily_task_duration_seconds",
+            "Time taken by the daily task",
+        )
+        .buckets(vec![1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0]),
+        &["outcome"],
+    ))
+});
+
+static DAILY_TASK_LAST_SUCCESS: LazyLock<IntGauge> = LazyLock::new(|| {
+    register(IntGauge::new(
+        "daily_task_last_success_timestamp_seconds",
+        "Unix time at which the daily task last succeeded",
+    ))
+});
+
+fn register<T: prometheus::core::Collector + Clone + 'static>(
+    collector: prometheus::Result<T>,
+) -> T {
+    let collector = collector.expect("Metric definitions must be valid");
+    REGISTRY
+        .register(Box::new(collector.clone()))
+        .expect("Metrics must only be registered once");
+    collector
+}
+
+/// Registers every metric up front, with zeroed outcomes, so series exist before anything happens
+/// and rates over them aren't missing data after a restart.
+pub fn init() {
+    for outcome in [
+        "success",
+        "hmac_failure",
+        "not_found",
+        "unknown_device",
+        "invalid",
+        "error",
+    ] {
+        ATTENDANCE_MARKS.with_label_values(&[outcome]);
+    }
+    for outcome in ["delivered", "retrying", "failed"] {
+        WEBHOOK_DELIVERIES.with_label_values(&[outcome]);
+    }
+    for outcome in ["sent", "retrying", "failed"] {
+        EMAIL_SENDS.with_label_values(&[outcome]);
+    }
+    for outcome in ["success", "failure"] {
+        DAILY_TASK_DURATION.with_label_values(&[outcome]);
+    }
+    LazyLock::force(&DAILY_TASK_LAST_SUCCESS);
+    LazyLock::force(&HTTP_REQUEST_DURATION);
+    LazyLock::force(&GRAPHQL_OPERATION_DURATION);
+}
+
+/// Serves every metric in the Prometheus text format. Pool gauges are sampled on each scrape.
+pub async fn metrics(State(state): State<AppState>) -> Response {
+    let size = state.pool.size() as i64;
+    let idle = state.pool.num_idle() as i64;
+    DB_POOL_CONNECTIONS.with_label_values(&["idle"]).set(idle);
+    DB_POOL_CONNECTIONS
+        .with_label_values(&["in_use"])
+        .set(size - idle);
+    DB_POOL_MAX_CONNECTIONS.set(state.pool.options().get_max_connections() as i64);
+
+    let encoder = TextEncoder::new();
+    let mut body = Vec::new();
+    if let Err(e) = encoder.encode(&REGISTRY.gather(), &mut body) {
+        tracing::error!("Failed to encode metrics: {:?}", e);
+        return crate::error::RootError::Internal.into_response();
+    }
+
+    ([(CONTENT_TYPE, encoder.format_type().to_string())], body).into_response()
+}
+
+/// Times every HTTP request. Requests are labelled by their route rather than their path, so
+/// query strings and unknown paths don't each get their own series.
+pub async fn track_http_requests(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let start = Instant::now();
+
+    let response = next.run(request).await;
+
+    HTTP_REQUEST_DURATION
+        .with_label_values(&[&method, &route, response.status().as_str()])
+        .observe(start.elapsed().as_secs_f64());
+    response
+}
+
+pub fn observe_attendance_mark(outcome: &str) {
+    ATTENDANCE_MARKS.with_label_values(&[outcome]).inc();
+}
+
+/// `outcome` is `delivered`, `retrying` after a failure, or `failed` for good.
+pub fn observe_webhook_delivery(outcome: &str) {
+    WEBHOOK_DELIVERIES.with_label_values(&[outcome]).inc();
+}
+
+/// `outcome` is `sent`, `retrying` after a failure, or `failed` for good.
+pub fn observe_email_send(outcome: &str) {
+    EMAIL_SENDS.with_label_values(&[outcome]).inc();
+}
+
+pub fn observe_daily_task(start: Instant, succeeded: bool) {
+    let outcome = if succeeded { "success" } else { "failure" };
+    DAILY_TASK_DURATION
+        .with_label_values(&[outcome])
+        .observe(start.elapsed().as_secs_f64());
+    if succeeded {
+        DAILY_TASK_LAST_SUCCESS.set(chrono::Utc::now().timestamp());
+    }
+}
+
+/// Times GraphQL operations by name. Clients choose the names, so only operations registered in
+/// the persisted query manifest keep theirs; other named operations are labelled `other`, and
+/// those without a name `anonymous`.
+pub struct GraphQLMetrics {
+    known_operations: Arc<HashSet<String>>,
+}
+
+impl GraphQLMetrics {
+    pub fn new(known_operations: Arc<HashSet<String>>) -> Self {
+        Self { known_operations }
+    }
+}
+
+impl ExtensionFactory for GraphQLMetrics {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(GraphQLMetricsExtension {
+            known_operations: self.known_operations.clone(),
+            document_operation: Mutex::default(),
+        })
+    }
+}
+
+struct GraphQLMetricsExtension {
+    known_operations: Arc<HashSet<String>>,
+    /// Clients often name their operation in the document without sending `operationName`.
+    document_operation: Mutex<Option<String>>,
+}
+
+#[async_trait::async_trait]
+impl Extension for GraphQLMetricsExtension {
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+        let mut operations = document.operations.iter();
+        if let (Some((Some(name), _)), None) = (operations.next(), operations.next()) {
+            *self.document_operation.lock().unwrap() = Some(name.to_string());
+        }
+        Ok(document)
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> GraphQLResponse {
+        let start = Instant::now();
+        let response = next.run(ctx, operation_name).await;
+
+        let document_operation = self.document_operation.lock().unwrap().clone();
+        let operation = match operation_name.map(str::to_string).or(document_operation) {
+            Some(name) if self.known_operations.contains(&name) => name,
+            Some(_) => "other".to_string(),
+            None => "anonymous".to_string(),
+        };
+        let outcome = if response.is_ok() { "success" } else { "error" };
+        GRAPHQL_OPERATION_DURATION
+            .with_label_values(&[&operation, outcome])
+            .observe(start.elapsed().as_secs_f64());
+        response
+    }
+}
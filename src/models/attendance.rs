@@ -1,8 +1,9 @@
 use async_graphql::{InputObject, SimpleObject};
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use serde::Serialize;
 use sqlx::FromRow;
 
-#[derive(SimpleObject, FromRow)]
+#[derive(SimpleObject, FromRow, Serialize)]
 pub struct Attendance {
     pub attendance_id: i32,
     pub member_id: i32,
@@ -11,12 +12,14 @@ pub struct Attendance {
     pub time_in: Option<NaiveTime>,
     pub time_out: Option<NaiveTime>,
     #[graphql(skip)] // Don't expose internal fields/meta-data
+    #[serde(skip)]
     pub created_at: NaiveDateTime,
     #[graphql(skip)]
+    #[serde(skip)]
     pub updated_at: NaiveDateTime,
 }
 
-#[derive(SimpleObject, FromRow)]
+#[derive(SimpleObject, FromRow, Serialize)]
 pub struct AttendanceSummary {
     pub member_id: i32,
     pub year: i32,
@@ -46,7 +49,7 @@ pub struct MarkAttendanceInput {
     pub hmac_signature: String,
 }
 
-#[derive(SimpleObject, FromRow)]
+#[derive(SimpleObject, FromRow, Serialize)]
 pub struct AttendanceWithMember {
     pub attendance_id: i32,
     pub member_id: i32,
@@ -0,0 +1,49 @@
+use async_graphql::{Enum, InputObject, SimpleObject};
+use chrono::{NaiveDate, NaiveDateTime};
+use sqlx::FromRow;
+
+use crate::validation::ValidationError;
+
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "calendar_date_kind", rename_all = "lowercase")]
+pub enum CalendarDateKind {
+    Holiday,
+    Deadline,
+}
+
+/// A holiday or deadline, shown as an all-day entry in the calendar feeds.
+#[derive(SimpleObject, FromRow)]
+pub struct CalendarDate {
+    pub date_id: i32,
+    pub date: NaiveDate,
+    pub title: String,
+    pub description: Option<String>,
+    pub kind: CalendarDateKind,
+    /// The group it applies to, or `None` for the whole club.
+    pub group_id: Option<i32>,
+    #[graphql(skip)] // Don't expose internal fields/meta-data
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(InputObject)]
+pub struct AddCalendarDateInput {
+    pub date: NaiveDate,
+    pub title: String,
+    pub description: Option<String>,
+    pub kind: CalendarDateKind,
+    pub group_id: Option<i32>,
+}
+
+impl AddCalendarDateInput {
+    pub fn validate(mut self) -> Result<Self, ValidationError> {
+        self.title = self.title.trim().to_string();
+        if self.title.is_empty() {
+            return Err(ValidationError::new("title", "Dates need a title"));
+        }
+        // All-day entries end on the following day, so there has to be one.
+        if self.date == NaiveDate::MAX {
+            return Err(ValidationError::new("date", "Date is out of range"));
+        }
+        Ok(self)
+    }
+}
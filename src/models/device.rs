@@ -0,0 +1,41 @@
+use async_graphql::{InputObject, SimpleObject};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::FromRow;
+
+use crate::validation::{self, ValidationError};
+
+/// A phone, laptop or other device Presense can see a member by.
+#[derive(SimpleObject, FromRow)]
+pub struct MemberDevice {
+    pub device_id: i32,
+    pub member_id: i32,
+    pub mac_address: String,
+    pub label: String,
+    /// Whether this is the member's `macAddress`, which changes with it.
+    pub is_primary: bool,
+    /// Only verified devices are matched to their member.
+    pub is_verified: bool,
+    pub last_seen_at: Option<DateTime<Utc>>,
+    #[graphql(skip)] // Don't expose internal fields/meta-data
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(InputObject)]
+pub struct AddDeviceInput {
+    pub member_id: i32,
+    pub mac_address: String,
+    /// What the device is, e.g. "Phone".
+    pub label: String,
+}
+
+impl AddDeviceInput {
+    pub fn validate(mut self) -> Result<Self, ValidationError> {
+        self.mac_address = validation::mac_address(&self.mac_address)
+            .map_err(|e| ValidationError::new("macAddress", e))?;
+        self.label = self.label.trim().to_string();
+        if self.label.is_empty() {
+            return Err(ValidationError::new("label", "Devices need a label"));
+        }
+        Ok(self)
+    }
+}
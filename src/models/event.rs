@@ -0,0 +1,160 @@
+use async_graphql::{Enum, InputObject, SimpleObject};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::FromRow;
+
+use crate::validation::ValidationError;
+
+#[derive(Enum, Copy, Clone, Debug, Default, Eq, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "event_kind", rename_all = "lowercase")]
+pub enum EventKind {
+    Talk,
+    Workshop,
+    Meeting,
+    #[default]
+    Other,
+}
+
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "rsvp_status", rename_all = "snake_case")]
+pub enum RsvpStatus {
+    Going,
+    Maybe,
+    NotGoing,
+}
+
+/// A talk, workshop or meeting. Empty audience lists don't limit who is invited.
+#[derive(SimpleObject, FromRow, Clone)]
+#[graphql(complex)]
+pub struct Event {
+    pub event_id: i32,
+    pub title: String,
+    pub description: Option<String>,
+    pub kind: EventKind,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub venue: Option<String>,
+    pub organizer_id: Option<i32>,
+    /// Years invited, or every year if empty.
+    pub audience_years: Vec<i32>,
+    /// Groups invited, or every group if empty.
+    pub audience_group_ids: Vec<i32>,
+    #[graphql(skip)] // Don't expose internal fields/meta-data
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(SimpleObject, FromRow)]
+pub struct EventRsvp {
+    pub event_id: i32,
+    pub member_id: i32,
+    pub status: RsvpStatus,
+    pub responded_at: DateTime<Utc>,
+}
+
+#[derive(SimpleObject, FromRow)]
+pub struct EventCheckIn {
+    pub event_id: i32,
+    pub member_id: i32,
+    pub checked_in_at: DateTime<Utc>,
+}
+
+#[derive(SimpleObject, FromRow)]
+pub struct EventAttendanceCounts {
+    pub going: i32,
+    pub maybe: i32,
+    pub not_going: i32,
+    pub checked_in: i32,
+}
+
+/// An event a member responded to or checked in at.
+#[derive(SimpleObject, FromRow)]
+pub struct MemberEvent {
+    #[sqlx(flatten)]
+    pub event: Event,
+    pub rsvp: Option<RsvpStatus>,
+    pub checked_in_at: Option<DateTime<Utc>>,
+}
+
+#[derive(InputObject)]
+pub struct CreateEventInput {
+    pub title: String,
+    pub description: Option<String>,
+    #[graphql(default)]
+    pub kind: EventKind,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub venue: Option<String>,
+    pub organizer_id: Option<i32>,
+    #[graphql(default)]
+    pub audience_years: Vec<i32>,
+    #[graphql(default)]
+    pub audience_group_ids: Vec<i32>,
+}
+
+impl CreateEventInput {
+    pub fn validate(mut self) -> Result<Self, ValidationError> {
+        self.title = title(&self.title)?;
+        check_times(self.starts_at, self.ends_at)?;
+        Ok(self)
+    }
+}
+
+#[derive(InputObject)]
+pub struct UpdateEventInput {
+    pub event_id: i32,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub kind: Option<EventKind>,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub venue: Option<String>,
+    pub organizer_id: Option<i32>,
+    pub audience_years: Option<Vec<i32>>,
+    pub audience_group_ids: Option<Vec<i32>>,
+}
+
+impl UpdateEventInput {
+    /// Unlike the other fields, the times are checked against the event they'll end up on.
+    pub fn validate(mut self) -> Result<Self, ValidationError> {
+        self.title = self.title.as_deref().map(title).transpose()?;
+        Ok(self)
+    }
+}
+
+#[derive(InputObject)]
+pub struct RsvpInput {
+    pub event_id: i32,
+    pub member_id: i32,
+    pub status: RsvpStatus,
+}
+
+#[derive(InputObject)]
+pub struct EventCheckInInput {
+    pub event_id: i32,
+    pub member_id: i32,
+    /// When the scanner signed the check-in. It must be within a few minutes of Root's clock.
+    pub signed_at: DateTime<Utc>,
+    /// The hex HMAC-SHA256 of `<eventId>:<memberId>:<signedAt as Unix seconds>`, keyed with the
+    /// root secret.
+    pub hmac_signature: String,
+}
+
+fn title(value: &str) -> Result<String, ValidationError> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(ValidationError::new("title", "Events need a title"));
+    }
+    Ok(value.to_string())
+}
+
+pub fn check_times(
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+) -> Result<(), ValidationError> {
+    if ends_at <= starts_at {
+        return Err(ValidationError::new(
+            "endsAt",
+            "Events must end after they start",
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use async_graphql::{InputObject, SimpleObject};
+use chrono::NaiveDateTime;
+use sqlx::FromRow;
+
+#[derive(SimpleObject, FromRow)]
+#[graphql(complex)]
+pub struct Group {
+    pub group_id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub discord_channel_id: Option<String>,
+    #[graphql(skip)] // Don't expose internal fields/meta-data
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(SimpleObject, FromRow)]
+pub struct GroupAttendanceStats {
+    pub member_count: i32,
+    pub days_present: i32,
+    pub days_recorded: i32,
+}
+
+#[derive(InputObject)]
+pub struct CreateGroupInput {
+    pub name: String,
+    pub description: Option<String>,
+    pub discord_channel_id: Option<String>,
+    pub mentor_ids: Option<Vec<i32>>,
+}
+
+#[derive(InputObject)]
+pub struct RenameGroupInput {
+    pub group_id: i32,
+    pub name: String,
+}
+
+#[derive(InputObject)]
+pub struct MoveMembersInput {
+    pub member_ids: Vec<i32>,
+    pub group_id: i32,
+}
+
+#[derive(InputObject)]
+pub struct SetGroupMentorsInput {
+    pub group_id: i32,
+    pub mentor_ids: Vec<i32>,
+}
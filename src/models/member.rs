@@ -1,8 +1,11 @@
 use async_graphql::{Enum, InputObject, SimpleObject};
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
 use sqlx::FromRow;
 
-#[derive(Enum, Copy, Clone, Eq, PartialEq, sqlx::Type)]
+use crate::validation::{self, ValidationError};
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Serialize, sqlx::Type)]
 #[sqlx(type_name = "sex_type")]
 pub enum Sex {
     M,
@@ -10,7 +13,15 @@ pub enum Sex {
     Other,
 }
 
-#[derive(SimpleObject, FromRow)]
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Serialize, sqlx::Type)]
+#[sqlx(type_name = "member_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum MemberStatus {
+    Active,
+    Alumni,
+}
+
+#[derive(SimpleObject, FromRow, Serialize)]
 #[graphql(complex)]
 pub struct Member {
     pub member_id: i32,
@@ -24,7 +35,12 @@ pub struct Member {
     pub discord_id: String,
     pub group_id: i32,
     #[graphql(skip)] // Don't expose internal fields/meta-data
+    #[serde(skip)]
     pub created_at: NaiveDateTime,
+    pub status: MemberStatus,
+    pub graduation_year: Option<i32>,
+    /// Whether the member has asked not to get attendance reports by email.
+    pub email_opt_out: bool,
 }
 
 #[derive(InputObject)]
@@ -39,3 +55,117 @@ pub struct CreateMemberInput {
     pub discord_id: String,
     pub group_id: i32,
 }
+
+impl CreateMemberInput {
+    /// Checks the fields other services rely on and rewrites them into their canonical form.
+    pub fn validate(mut self, final_year: i32) -> Result<Self, ValidationError> {
+        self.roll_no =
+            validation::roll_no(&self.roll_no).map_err(|e| ValidationError::new("rollNo", e))?;
+        self.email =
+            validation::email(&self.email).map_err(|e| ValidationError::new("email", e))?;
+        self.mac_address = validation::mac_address(&self.mac_address)
+            .map_err(|e| ValidationError::new("macAddress", e))?;
+        self.discord_id = validation::discord_id(&self.discord_id)
+            .map_err(|e| ValidationError::new("discordId", e))?;
+        if !(1..=final_year).contains(&self.year) {
+            return Err(ValidationError::new(
+                "year",
+                format!("{} is not a year between 1 and {}", self.year, final_year),
+            ));
+        }
+        Ok(self)
+    }
+}
+
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "profile_change_status", rename_all = "lowercase")]
+pub enum ProfileChangeStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// A member's request to change their MAC address, which waits for an admin's approval.
+#[derive(SimpleObject, FromRow)]
+pub struct ProfileChangeRequest {
+    pub change_id: i32,
+    pub member_id: i32,
+    pub mac_address: String,
+    pub status: ProfileChangeStatus,
+    pub requested_at: DateTime<Utc>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+}
+
+/// The fields members may change themselves. Omitted fields are left as they are.
+#[derive(InputObject)]
+pub struct UpdateProfileInput {
+    pub hostel: Option<String>,
+    pub discord_id: Option<String>,
+    /// Takes effect once an admin approves it.
+    pub mac_address: Option<String>,
+}
+
+impl UpdateProfileInput {
+    pub fn validate(mut self) -> Result<Self, ValidationError> {
+        if let Some(hostel) = &self.hostel {
+            let hostel = hostel.trim();
+            if hostel.is_empty() {
+                return Err(ValidationError::new("hostel", "Hostel cannot be empty"));
+            }
+            self.hostel = Some(hostel.to_string());
+        }
+        self.discord_id = self
+            .discord_id
+            .as_deref()
+            .map(validation::discord_id)
+            .transpose()
+            .map_err(|e| ValidationError::new("discordId", e))?;
+        self.mac_address = self
+            .mac_address
+            .as_deref()
+            .map(validation::mac_address)
+            .transpose()
+            .map_err(|e| ValidationError::new("macAddress", e))?;
+        Ok(self)
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct ProfileUpdate {
+    /// The member with the changes that took effect right away.
+    pub member: Member,
+    /// The MAC address change waiting for approval, if any.
+    pub pending_change: Option<ProfileChangeRequest>,
+}
+
+#[derive(InputObject)]
+pub struct RolloverInput {
+    /// The calendar year the ending academic year graduates in, e.g. 2025 for 2024-25.
+    pub academic_year: i32,
+    #[graphql(default)]
+    pub dry_run: bool,
+}
+
+#[derive(SimpleObject)]
+pub struct RolloverReport {
+    pub academic_year: i32,
+    pub dry_run: bool,
+    pub promoted: Vec<Member>,
+    pub graduated: Vec<Member>,
+}
+
+#[derive(SimpleObject)]
+pub struct ImportRowError {
+    /// Line number in the uploaded file, counting the header as line 1.
+    pub row: i32,
+    pub field: Option<String>,
+    pub message: String,
+}
+
+#[derive(SimpleObject)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub total_rows: i32,
+    pub imported: Vec<Member>,
+    pub errors: Vec<ImportRowError>,
+}
@@ -1,4 +1,9 @@
 pub mod attendance;
+pub mod calendar;
+pub mod device;
+pub mod event;
+pub mod group;
 pub mod member;
 pub mod project;
 pub mod status_update_streak;
+pub mod webhook;
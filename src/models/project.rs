@@ -1,7 +1,8 @@
 use async_graphql::{InputObject, SimpleObject};
+use serde::Serialize;
 use sqlx::FromRow;
 
-#[derive(FromRow, SimpleObject)]
+#[derive(FromRow, SimpleObject, Serialize)]
 pub struct Project {
     pub project_id: i32,
     pub member_id: i32,
@@ -1,7 +1,8 @@
 use async_graphql::{InputObject, SimpleObject};
+use serde::Serialize;
 use sqlx::FromRow;
 
-#[derive(SimpleObject, FromRow)]
+#[derive(SimpleObject, FromRow, Serialize)]
 pub struct StatusUpdateStreak {
     pub member_id: i32,
     pub current_streak: i32,
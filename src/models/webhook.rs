@@ -0,0 +1,150 @@
+use async_graphql::{Enum, InputObject, SimpleObject};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+
+use crate::validation::ValidationError;
+
+/// Something that happened in Root which other services can subscribe to.
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq, Serialize, sqlx::Type)]
+#[sqlx(type_name = "webhook_event")]
+pub enum WebhookEventType {
+    #[sqlx(rename = "member.created")]
+    #[serde(rename = "member.created")]
+    MemberCreated,
+    #[sqlx(rename = "attendance.marked")]
+    #[serde(rename = "attendance.marked")]
+    AttendanceMarked,
+    #[sqlx(rename = "streak.reset")]
+    #[serde(rename = "streak.reset")]
+    StreakReset,
+    #[sqlx(rename = "project.updated")]
+    #[serde(rename = "project.updated")]
+    ProjectUpdated,
+    #[sqlx(rename = "dailytask.completed")]
+    #[serde(rename = "dailytask.completed")]
+    DailyTaskCompleted,
+}
+
+impl WebhookEventType {
+    /// The name receivers see in the payload and the `X-Root-Event` header.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEventType::MemberCreated => "member.created",
+            WebhookEventType::AttendanceMarked => "attendance.marked",
+            WebhookEventType::StreakReset => "streak.reset",
+            WebhookEventType::ProjectUpdated => "project.updated",
+            WebhookEventType::DailyTaskCompleted => "dailytask.completed",
+        }
+    }
+}
+
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "webhook_delivery_status", rename_all = "lowercase")]
+pub enum WebhookDeliveryStatus {
+    /// Not delivered yet, but will be tried (again).
+    Pending,
+    Delivered,
+    /// Given up on after too many attempts.
+    Failed,
+}
+
+#[derive(SimpleObject, FromRow)]
+pub struct Webhook {
+    pub webhook_id: i32,
+    pub url: String,
+    pub description: Option<String>,
+    pub events: Vec<WebhookEventType>,
+    #[graphql(skip)]
+    pub secret: String,
+    pub active: bool,
+    #[graphql(skip)] // Don't expose internal fields/meta-data
+    pub created_at: NaiveDateTime,
+}
+
+/// A newly registered webhook. The secret its payloads are signed with is only shown here.
+#[derive(SimpleObject)]
+pub struct RegisteredWebhook {
+    pub webhook: Webhook,
+    pub secret: String,
+}
+
+#[derive(SimpleObject, FromRow)]
+#[graphql(complex)]
+pub struct WebhookDelivery {
+    pub delivery_id: i64,
+    pub webhook_id: i32,
+    pub event_id: i64,
+    pub event_type: WebhookEventType,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub last_response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+#[derive(SimpleObject, FromRow)]
+pub struct WebhookDeliveryAttempt {
+    pub attempted_at: DateTime<Utc>,
+    /// `None` if no response was received.
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    pub duration_ms: i32,
+}
+
+#[derive(InputObject)]
+pub struct RegisterWebhookInput {
+    pub url: String,
+    pub events: Vec<WebhookEventType>,
+    pub description: Option<String>,
+}
+
+impl RegisterWebhookInput {
+    pub fn validate(mut self) -> Result<Self, ValidationError> {
+        self.url = endpoint_url(&self.url)?;
+        check_events(&self.events)?;
+        Ok(self)
+    }
+}
+
+#[derive(InputObject)]
+pub struct UpdateWebhookInput {
+    pub webhook_id: i32,
+    pub url: Option<String>,
+    pub events: Option<Vec<WebhookEventType>>,
+    pub description: Option<String>,
+    pub active: Option<bool>,
+}
+
+impl UpdateWebhookInput {
+    pub fn validate(mut self) -> Result<Self, ValidationError> {
+        self.url = self.url.as_deref().map(endpoint_url).transpose()?;
+        if let Some(events) = &self.events {
+            check_events(events)?;
+        }
+        Ok(self)
+    }
+}
+
+fn endpoint_url(value: &str) -> Result<String, ValidationError> {
+    let url = reqwest::Url::parse(value.trim())
+        .map_err(|e| ValidationError::new("url", format!("'{}' is not a URL: {}", value, e)))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(ValidationError::new(
+            "url",
+            "Webhooks must use http or https",
+        ));
+    }
+    Ok(url.to_string())
+}
+
+fn check_events(events: &[WebhookEventType]) -> Result<(), ValidationError> {
+    if events.is_empty() {
+        return Err(ValidationError::new(
+            "events",
+            "Subscribe to at least one event",
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,268 @@
+//! Daily digests for each group's Discord channel: members who have been absent several days in a
+//! row, status update streaks that break without an update today, and members who joined
+//! yesterday.
+//!
+//! Digests are built after the daily task runs for a day, so they cover the day before, and are
+//! handed to a [`NotificationSink`] to send.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::sync::Arc;
+
+use chrono::{Days, NaiveDate};
+use sqlx::{FromRow, PgPool};
+use tracing::{debug, error, instrument};
+
+use crate::config::{NotifierConfig, NotifierSinkKind, NotifierTemplates};
+
+mod sink;
+
+pub use sink::{DiscordWebhookSink, LogSink, MockSink, Notification, NotificationSink};
+
+#[derive(Debug, Clone, FromRow)]
+pub struct Absentee {
+    pub member_id: i32,
+    pub name: String,
+    pub discord_id: String,
+    pub group_id: i32,
+    /// Attendance records in a row, up to and including yesterday's, without the member present.
+    pub days: i32,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct StreakAtRisk {
+    pub member_id: i32,
+    pub name: String,
+    pub discord_id: String,
+    pub group_id: i32,
+    pub current_streak: i32,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct NewMember {
+    pub member_id: i32,
+    pub name: String,
+    pub discord_id: String,
+    pub group_id: i32,
+    pub year: i32,
+}
+
+/// What one group's channel hears about.
+#[derive(Debug, Clone)]
+pub struct GroupDigest {
+    pub group_id: i32,
+    pub group_name: String,
+    pub new_members: Vec<NewMember>,
+    pub absentees: Vec<Absentee>,
+    pub streaks_at_risk: Vec<StreakAtRisk>,
+}
+
+impl GroupDigest {
+    pub fn is_empty(&self) -> bool {
+        self.new_members.is_empty() && self.absentees.is_empty() && self.streaks_at_risk.is_empty()
+    }
+
+    /// The digest as a message, one line per member after the header.
+    pub fn render(&self, templates: &NotifierTemplates, today: NaiveDate) -> String {
+        let mut lines = vec![fill(
+            &templates.header,
+            &[("group", &self.group_name), ("date", &yesterday(today))],
+        )];
+        lines.extend(self.new_members.iter().map(|member| {
+            fill(
+                &templates.new_member,
+                &[
+                    ("name", &member.name),
+                    ("mention", &mention(&member.discord_id)),
+                    ("year", &member.year),
+                ],
+            )
+        }));
+        lines.extend(self.absentees.iter().map(|absentee| {
+            fill(
+                &templates.absentee,
+                &[
+                    ("name", &absentee.name),
+                    ("mention", &mention(&absentee.discord_id)),
+                    ("days", &absentee.days),
+                ],
+            )
+        }));
+        lines.extend(self.streaks_at_risk.iter().map(|streak| {
+            fill(
+                &templates.streak_at_risk,
+                &[
+                    ("name", &streak.name),
+                    ("mention", &mention(&streak.discord_id)),
+                    ("streak", &streak.current_streak),
+                ],
+            )
+        }));
+        lines.join("\n")
+    }
+}
+
+/// Builds each group's digest and sends it through a sink.
+pub struct Notifier {
+    pool: Arc<PgPool>,
+    sink: Arc<dyn NotificationSink>,
+    config: NotifierConfig,
+}
+
+impl Notifier {
+    pub fn new(
+        pool: Arc<PgPool>,
+        sink: Arc<dyn NotificationSink>,
+        config: &NotifierConfig,
+    ) -> Self {
+        Self {
+            pool,
+            sink,
+            config: config.clone(),
+        }
+    }
+
+    /// A notifier sending through the sink `config` asks for.
+    pub fn from_config(pool: Arc<PgPool>, config: &NotifierConfig) -> Self {
+        let sink: Arc<dyn NotificationSink> = match config.sink {
+            NotifierSinkKind::Discord => Arc::new(DiscordWebhookSink::new(config)),
+            NotifierSinkKind::Log => Arc::new(LogSink),
+        };
+        Self::new(pool, sink, config)
+    }
+
+    /// Sends the digest of every group with something to report, for the day before `today`.
+    /// Returns whether every digest could be built and sent.
+    #[instrument(name = "notifier", skip(self))]
+    pub async fn send_digests(&self, today: NaiveDate) -> bool {
+        let digests = match self.build_digests(today).await {
+            Ok(digests) => digests,
+            Err(e) => {
+                error!("Failed to build digests: {:?}", e);
+                return false;
+            }
+        };
+
+        let mut succeeded = true;
+        for digest in digests.iter().filter(|digest| !digest.is_empty()) {
+            let notification = Notification {
+                group_id: digest.group_id,
+                group_name: digest.group_name.clone(),
+                content: digest.render(&self.config.templates, today),
+            };
+            match self.sink.send(&notification).await {
+                Ok(()) => debug!("Sent the digest for group {}", digest.group_id),
+                Err(e) => {
+                    error!(
+                        "Failed to send the digest for group {}: {}",
+                        digest.group_id, e
+                    );
+                    succeeded = false;
+                }
+            }
+        }
+        succeeded
+    }
+
+    /// Every group's digest for the day before `today`, including empty ones, ordered by group.
+    pub async fn build_digests(&self, today: NaiveDate) -> Result<Vec<GroupDigest>, sqlx::Error> {
+        let pool = self.pool.as_ref();
+        let yesterday = yesterday(today);
+
+        let mut digests: BTreeMap<i32, GroupDigest> =
+            sqlx::query_as::<_, (i32, String)>("SELECT group_id, name FROM MemberGroup")
+                .fetch_all(pool)
+                .await?
+                .into_iter()
+                .map(|(group_id, group_name)| {
+                    let digest = GroupDigest {
+                        group_id,
+                        group_name,
+                        new_members: Vec::new(),
+                        absentees: Vec::new(),
+                        streaks_at_risk: Vec::new(),
+                    };
+                    (group_id, digest)
+                })
+                .collect();
+
+        let new_members = sqlx::query_as::<_, NewMember>(
+            "SELECT member_id, name, discord_id, group_id, year FROM Member
+             WHERE status = 'active' AND created_at >= $1 AND created_at < $2
+             ORDER BY name",
+        )
+        .bind(yesterday)
+        .bind(today)
+        .fetch_all(pool)
+        .await?;
+
+        // Only an absence yesterday counts, so members stop being listed once they're back.
+        let absentees = sqlx::query_as::<_, Absentee>(
+            "SELECT mem.member_id, mem.name, mem.discord_id, mem.group_id, COUNT(*)::INT AS days
+             FROM Member mem
+             JOIN Attendance att ON att.member_id = mem.member_id
+             WHERE mem.status = 'active' AND NOT att.is_present AND att.date <= $1
+             AND att.date > COALESCE(
+                (SELECT MAX(present.date) FROM Attendance present
+                 WHERE present.member_id = mem.member_id AND present.is_present
+                 AND present.date <= $1),
+                '-infinity'
+             )
+             GROUP BY mem.member_id
+             HAVING COUNT(*) >= $2 AND BOOL_OR(att.date = $1)
+             ORDER BY days DESC, mem.name",
+        )
+        .bind(yesterday)
+        .bind(self.config.absent_days)
+        .fetch_all(pool)
+        .await?;
+
+        // A streak last extended before yesterday ends if today passes without an update too.
+        let streaks_at_risk = sqlx::query_as::<_, StreakAtRisk>(
+            "SELECT mem.member_id, mem.name, mem.discord_id, mem.group_id, str.current_streak
+             FROM StatusUpdateStreak str
+             JOIN Member mem ON mem.member_id = str.member_id
+             WHERE mem.status = 'active' AND str.current_streak >= $2
+             AND (str.updated_on IS NULL OR str.updated_on < $1)
+             ORDER BY str.current_streak DESC, mem.name",
+        )
+        .bind(yesterday)
+        .bind(self.config.streak_at_risk_min)
+        .fetch_all(pool)
+        .await?;
+
+        for member in new_members {
+            if let Some(digest) = digests.get_mut(&member.group_id) {
+                digest.new_members.push(member);
+            }
+        }
+        for absentee in absentees {
+            if let Some(digest) = digests.get_mut(&absentee.group_id) {
+                digest.absentees.push(absentee);
+            }
+        }
+        for streak in streaks_at_risk {
+            if let Some(digest) = digests.get_mut(&streak.group_id) {
+                digest.streaks_at_risk.push(streak);
+            }
+        }
+        Ok(digests.into_values().collect())
+    }
+}
+
+fn yesterday(today: NaiveDate) -> NaiveDate {
+    today - Days::new(1)
+}
+
+fn mention(discord_id: &str) -> String {
+    format!("<@{}>", discord_id)
+}
+
+/// Replaces each `{key}` in `template` with its value. Unknown placeholders are left as they are.
+fn fill(template: &str, values: &[(&str, &dyn Display)]) -> String {
+    values
+        .iter()
+        .fold(template.to_string(), |filled, (key, value)| {
+            filled.replace(&format!("{{{}}}", key), &value.to_string())
+        })
+}
@@ -0,0 +1,156 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tracing::{debug, info};
+
+use crate::config::{DiscordConfig, NotifierConfig};
+
+/// Discord rejects messages longer than this many characters.
+const DISCORD_MESSAGE_LIMIT: usize = 2000;
+
+/// A rendered digest for one group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub group_id: i32,
+    pub group_name: String,
+    pub content: String,
+}
+
+/// Somewhere digests go.
+#[async_trait]
+pub trait NotificationSink: Send + Sync {
+    async fn send(&self, notification: &Notification) -> Result<(), String>;
+}
+
+/// Posts each group's digest to its Discord webhook, or the default one.
+pub struct DiscordWebhookSink {
+    client: reqwest::Client,
+    config: DiscordConfig,
+}
+
+impl DiscordWebhookSink {
+    pub fn new(config: &NotifierConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .user_agent(concat!("Root/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .expect("Discord HTTP client must build");
+
+        Self {
+            client,
+            config: config.discord.clone(),
+        }
+    }
+
+    fn webhook_url(&self, group_id: i32) -> Option<&str> {
+        self.config
+            .group_webhook_urls
+            .get(&group_id.to_string())
+            .or(self.config.default_webhook_url.as_ref())
+            .map(String::as_str)
+    }
+}
+
+#[async_trait]
+impl NotificationSink for DiscordWebhookSink {
+    async fn send(&self, notification: &Notification) -> Result<(), String> {
+        let Some(url) = self.webhook_url(notification.group_id) else {
+            debug!(
+                "No Discord webhook for group {}, skipping its digest",
+                notification.group_id
+            );
+            return Ok(());
+        };
+
+        for content in split_message(&notification.content, DISCORD_MESSAGE_LIMIT) {
+            let response = self
+                .client
+                .post(url)
+                .json(&serde_json::json!({
+                    "content": content,
+                    // Ping the members mentioned, but never @everyone or roles.
+                    "allowed_mentions": { "parse": ["users"] },
+                }))
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            if !response.status().is_success() {
+                return Err(format!("Discord responded with {}", response.status()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes digests to the log, for running without Discord.
+pub struct LogSink;
+
+#[async_trait]
+impl NotificationSink for LogSink {
+    async fn send(&self, notification: &Notification) -> Result<(), String> {
+        info!(
+            "Digest for {}:\n{}",
+            notification.group_name, notification.content
+        );
+        Ok(())
+    }
+}
+
+/// Keeps what it's sent, for tests.
+#[derive(Default)]
+pub struct MockSink {
+    sent: Mutex<Vec<Notification>>,
+}
+
+impl MockSink {
+    /// Everything sent so far, in order.
+    pub fn sent(&self) -> Vec<Notification> {
+        self.sent.lock().expect("Mock sink lock poisoned").clone()
+    }
+}
+
+#[async_trait]
+impl NotificationSink for MockSink {
+    async fn send(&self, notification: &Notification) -> Result<(), String> {
+        self.sent
+            .lock()
+            .expect("Mock sink lock poisoned")
+            .push(notification.clone());
+        Ok(())
+    }
+}
+
+/// Splits `content` into messages of at most `limit` characters, between lines where possible.
+fn split_message(content: &str, limit: usize) -> Vec<String> {
+    let mut messages = Vec::new();
+    let mut current = String::new();
+    for line in content.lines() {
+        let mut line = line;
+        // A single line too long for a message is cut wherever the limit falls.
+        while line.chars().count() > limit {
+            let cut = line
+                .char_indices()
+                .nth(limit)
+                .map_or(line.len(), |(i, _)| i);
+            if !current.is_empty() {
+                messages.push(std::mem::take(&mut current));
+            }
+            messages.push(line[..cut].to_string());
+            line = &line[cut..];
+        }
+
+        let separator = usize::from(!current.is_empty());
+        if current.chars().count() + separator + line.chars().count() > limit {
+            messages.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(line);
+    }
+    if !current.is_empty() {
+        messages.push(current);
+    }
+    messages
+}
@@ -0,0 +1,228 @@
+use std::any::TypeId;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use async_graphql::{
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextPrepareRequest},
+    from_value, ErrorExtensionValues, Request, ServerError, ServerResult,
+};
+use hashlink::LruCache;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::auth::Caller;
+use crate::config::PersistedQueriesConfig;
+
+/// Apollo's persisted query manifest, as written by `@apollo/generate-persisted-query-manifest`.
+#[derive(Deserialize)]
+struct Manifest {
+    operations: Vec<ManifestOperation>,
+}
+
+#[derive(Deserialize)]
+struct ManifestOperation {
+    /// The SHA-256 hash of `body`, which clients send in place of the query.
+    id: String,
+    name: String,
+    body: String,
+}
+
+/// The `persistedQuery` request extension sent by Apollo clients.
+#[derive(Deserialize)]
+struct PersistedQuery {
+    version: i32,
+    #[serde(rename = "sha256Hash")]
+    sha256_hash: String,
+}
+
+/// Lets clients send the SHA-256 hash of a query in place of its text, either for an operation
+/// registered in the manifest or one they sent in full before (Apollo's automatic persisted
+/// queries). In allowlist mode, only admins can run operations that aren't registered.
+pub struct PersistedQueries {
+    registered: Arc<HashMap<String, String>>,
+    /// Names of the registered operations.
+    names: Arc<HashSet<String>>,
+    /// Queries learnt from clients. `None` when automatic persisted queries are disabled.
+    cache: Option<Arc<Mutex<LruCache<String, String>>>>,
+    allowlist_only: bool,
+}
+
+impl PersistedQueries {
+    pub fn new(config: &PersistedQueriesConfig) -> Result<Self, String> {
+        let operations = match &config.manifest {
+            Some(path) => load_manifest(path)?,
+            None => Vec::new(),
+        };
+        let names = operations.iter().map(|o| o.name.clone()).collect();
+        let registered = operations.into_iter().map(|o| (o.id, o.body)).collect();
+
+        Ok(Self {
+            registered: Arc::new(registered),
+            names: Arc::new(names),
+            cache: config
+                .automatic
+                .then(|| Arc::new(Mutex::new(LruCache::new(config.cache_size)))),
+            allowlist_only: config.allowlist_only,
+        })
+    }
+
+    pub fn registered_operations(&self) -> usize {
+        self.registered.len()
+    }
+
+    pub fn operation_names(&self) -> Arc<HashSet<String>> {
+        self.names.clone()
+    }
+
+    fn find(&self, requested_hash: &str, restricted: bool) -> ServerResult<String> {
+        if let Some(query) = self.registered.get(requested_hash) {
+            return Ok(query.clone());
+        }
+        if restricted {
+            return Err(not_allowed());
+        }
+
+        let Some(cache) = &self.cache else {
+            // Tells Apollo clients to stop sending hashes.
+            return Err(persisted_query_error(
+                "PERSISTED_QUERY_NOT_SUPPORTED",
+                "PersistedQueryNotSupported",
+            ));
+        };
+        cache
+            .lock()
+            .expect("Persisted query cache lock poisoned")
+            .get(requested_hash)
+            .cloned()
+            // Apollo clients retry with the full query on this exact message.
+            .ok_or_else(|| {
+                persisted_query_error("PERSISTED_QUERY_NOT_FOUND", "PersistedQueryNotFound")
+            })
+    }
+}
+
+fn load_manifest(path: &Path) -> Result<Vec<ManifestOperation>, String> {
+    let file = std::fs::File::open(path).map_err(|e| {
+        format!(
+            "Could not open persisted query manifest {}: {}",
+            path.display(),
+            e
+        )
+    })?;
+    let manifest: Manifest = serde_json::from_reader(file)
+        .map_err(|e| format!("Invalid persisted query manifest {}: {}", path.display(), e))?;
+
+    for operation in &manifest.operations {
+        if hash(&operation.body) != operation.id {
+            return Err(format!(
+                "Operation '{}' in {} does not match its hash",
+                operation.name,
+                path.display()
+            ));
+        }
+    }
+    Ok(manifest.operations)
+}
+
+fn hash(query: &str) -> String {
+    hex::encode(Sha256::digest(query.as_bytes()))
+}
+
+impl ExtensionFactory for PersistedQueries {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(PersistedQueries {
+            registered: self.registered.clone(),
+            names: self.names.clone(),
+            cache: self.cache.clone(),
+            allowlist_only: self.allowlist_only,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for PersistedQueries {
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        mut request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        let requested_hash = match request.extensions.remove("persistedQuery") {
+            Some(value) => {
+                let persisted: PersistedQuery = from_value(value).map_err(|_| {
+                    persisted_query_error(
+                        "PERSISTED_QUERY_INVALID",
+                        "Invalid persistedQuery extension",
+                    )
+                })?;
+                if persisted.version != 1 {
+                    return Err(persisted_query_error(
+                        "PERSISTED_QUERY_INVALID",
+                        "Only version 1 of the persistedQuery extension is supported",
+                    ));
+                }
+                Some(persisted.sha256_hash)
+            }
+            None => None,
+        };
+
+        let restricted = self.allowlist_only && !is_admin(&request);
+
+        if request.query.is_empty() {
+            if let Some(requested_hash) = requested_hash {
+                request.query = self.find(&requested_hash, restricted)?;
+            }
+        } else {
+            let query_hash = hash(&request.query);
+            if requested_hash
+                .as_ref()
+                .is_some_and(|requested| *requested != query_hash)
+            {
+                return Err(persisted_query_error(
+                    "PERSISTED_QUERY_HASH_MISMATCH",
+                    "The sha256Hash does not match the query",
+                ));
+            }
+
+            if !self.registered.contains_key(&query_hash) {
+                if restricted {
+                    return Err(not_allowed());
+                }
+                // Clients send the query along with its hash after a `PersistedQueryNotFound`.
+                if let (Some(cache), Some(_)) = (&self.cache, requested_hash) {
+                    cache
+                        .lock()
+                        .expect("Persisted query cache lock poisoned")
+                        .insert(query_hash, request.query.clone());
+                }
+            }
+        }
+
+        next.run(ctx, request).await
+    }
+}
+
+fn is_admin(request: &Request) -> bool {
+    request
+        .data
+        .get(&TypeId::of::<Caller>())
+        .and_then(|caller| caller.downcast_ref::<Caller>())
+        .is_some_and(Caller::is_admin)
+}
+
+fn not_allowed() -> ServerError {
+    persisted_query_error(
+        "PERSISTED_QUERY_NOT_ALLOWED",
+        "Only registered operations can be run without an admin API key",
+    )
+}
+
+fn persisted_query_error(code: &str, message: &str) -> ServerError {
+    let mut extensions = ErrorExtensionValues::default();
+    extensions.set("code", code);
+
+    let mut error = ServerError::new(message, None);
+    error.extensions = Some(extensions);
+    error
+}
@@ -0,0 +1,241 @@
+//! Batched attendance from Presense's scanners. Instead of signing a `markAttendance` call per
+//! member, a scanner sends every MAC address it saw in one signed request to `/presence/scans`,
+//! and gets back what came of each one.
+
+use std::collections::BTreeMap;
+
+use axum::{extract::State, http::HeaderMap, Json};
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::auth::verify_signature;
+use crate::clock::ClubClock;
+use crate::config::AttendancePolicy;
+use crate::error::RootError;
+use crate::metrics::observe_attendance_mark;
+use crate::models::attendance::Attendance;
+use crate::routes::AppState;
+use crate::validation;
+use crate::webhooks::{self, Event};
+
+/// The most scans one request can carry, comfortably more than a full lab.
+pub const MAX_SCANS: usize = 1000;
+
+/// How far a scanner's clock may be from Root's before its batches and scans are refused. A
+/// captured batch can only be replayed this long after it was sent, to no effect.
+const MAX_CLOCK_SKEW: TimeDelta = TimeDelta::minutes(5);
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanBatch {
+    /// Which scanner sent the batch, for the logs.
+    pub scanner: Option<String>,
+    /// When the scanner sent the batch, by its own clock.
+    pub sent_at: DateTime<Utc>,
+    pub scans: Vec<Scan>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Scan {
+    pub mac_address: String,
+    pub seen_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, Eq, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ScanStatus {
+    /// The member was marked present.
+    Marked,
+    /// The address isn't a verified device of any member.
+    UnknownDevice,
+    /// The member has no attendance record for the day, usually because the daily task hasn't
+    /// added it yet.
+    NoRecord,
+    /// The scan itself was malformed.
+    Invalid,
+}
+
+/// What came of one scan, in the same position as the scan in the batch.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanResult {
+    pub mac_address: String,
+    pub status: ScanStatus,
+    pub member_id: Option<i32>,
+    /// The day the scan counted towards, in the club's timezone.
+    pub date: Option<NaiveDate>,
+    /// The member's times for the day after the scan, if they were marked.
+    pub time_in: Option<NaiveTime>,
+    pub time_out: Option<NaiveTime>,
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ScanBatchResult {
+    pub results: Vec<ScanResult>,
+}
+
+/// Takes a batch signed with the root secret: its `X-Root-Signature` header must be the hex
+/// HMAC-SHA256 of the body. Scans only ever widen a day's time in and out, so a batch sent twice
+/// changes nothing the second time.
+pub async fn ingest_scans(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<ScanBatchResult>, RootError> {
+    let signature = headers
+        .get("x-root-signature")
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| RootError::Unauthenticated("Missing X-Root-Signature".to_string()))?;
+    verify_signature(&state.secret, &body, signature)
+        .inspect_err(|_| observe_attendance_mark("hmac_failure"))?;
+
+    let batch: ScanBatch = serde_json::from_str(&body).map_err(|e| RootError::Validation {
+        field: None,
+        message: format!("Invalid scan batch: {}", e),
+    })?;
+    if batch.scans.len() > MAX_SCANS {
+        return Err(RootError::Validation {
+            field: Some("scans"),
+            message: format!("Send at most {} scans at a time", MAX_SCANS),
+        });
+    }
+    if (state.clock.now().to_utc() - batch.sent_at).abs() > MAX_CLOCK_SKEW {
+        return Err(RootError::Validation {
+            field: Some("sentAt"),
+            message: format!(
+                "Batches must be sent within {} minutes of Root's clock",
+                MAX_CLOCK_SKEW.num_minutes()
+            ),
+        });
+    }
+
+    let results = record_scans(&state.pool, &state.clock, &state.attendance, &batch.scans)
+        .await
+        .inspect_err(|_| observe_attendance_mark("error"))?;
+    info!(
+        "Recorded {} scans from {}",
+        results.len(),
+        batch.scanner.as_deref().unwrap_or("an unnamed scanner")
+    );
+    Ok(Json(ScanBatchResult { results }))
+}
+
+/// Marks the members seen in `scans` present, all in one transaction, and returns the result of
+/// each scan. A scan can only move a day's time in earlier and its time out later.
+pub async fn record_scans(
+    pool: &PgPool,
+    clock: &ClubClock,
+    policy: &AttendancePolicy,
+    scans: &[Scan],
+) -> Result<Vec<ScanResult>, sqlx::Error> {
+    let now = clock.now().to_utc();
+    let earliest = now - TimeDelta::hours(policy.max_scan_age_hours.into());
+    let latest = now + MAX_CLOCK_SKEW;
+    let mut tx = pool.begin().await?;
+    let mut results = Vec::with_capacity(scans.len());
+    // Each record changed, as it ended up, so receivers get one event per member per day.
+    let mut marked = BTreeMap::new();
+
+    for scan in scans {
+        let mut result = ScanResult {
+            mac_address: scan.mac_address.clone(),
+            status: ScanStatus::Invalid,
+            member_id: None,
+            date: None,
+            time_in: None,
+            time_out: None,
+            message: None,
+        };
+        let mac_address = match validation::mac_address(&scan.mac_address) {
+            Ok(mac_address) => mac_address,
+            Err(message) => {
+                result.message = Some(message);
+                results.push(result);
+                continue;
+            }
+        };
+        result.mac_address = mac_address.clone();
+        if scan.seen_at > latest {
+            result.message = Some(format!("{} is in the future", scan.seen_at));
+            results.push(result);
+            continue;
+        }
+        if scan.seen_at < earliest {
+            result.message = Some(format!(
+                "{} is more than {} hours ago",
+                scan.seen_at, policy.max_scan_age_hours
+            ));
+            results.push(result);
+            continue;
+        }
+
+        // Unverified devices are still recorded as seen, which helps admins verify them.
+        let member_id = sqlx::query_scalar::<_, Option<i32>>(
+            "UPDATE MemberDevice SET last_seen_at = GREATEST(last_seen_at, $2)
+             WHERE mac_address = $1
+             RETURNING CASE WHEN is_verified THEN member_id END",
+        )
+        .bind(&mac_address)
+        .bind(scan.seen_at)
+        .fetch_optional(&mut *tx)
+        .await?
+        .flatten();
+        let Some(member_id) = member_id else {
+            result.status = ScanStatus::UnknownDevice;
+            results.push(result);
+            continue;
+        };
+        let seen_at = scan.seen_at.with_timezone(&clock.timezone());
+        result.member_id = Some(member_id);
+        result.date = Some(seen_at.date_naive());
+
+        let attendance = sqlx::query_as::<_, Attendance>(
+            "UPDATE Attendance SET
+                time_in = LEAST(time_in, $3),
+                time_out = GREATEST(time_out, $3),
+                is_present = TRUE
+             WHERE member_id = $1 AND date = $2 RETURNING *",
+        )
+        .bind(member_id)
+        .bind(seen_at.date_naive())
+        .bind(seen_at.time())
+        .fetch_optional(&mut *tx)
+        .await?;
+        match attendance {
+            Some(attendance) => {
+                result.status = ScanStatus::Marked;
+                result.time_in = attendance.time_in;
+                result.time_out = attendance.time_out;
+                marked.insert(attendance.attendance_id, attendance);
+            }
+            None => {
+                result.status = ScanStatus::NoRecord;
+                result.message = Some(format!(
+                    "No attendance record for member {} on {}",
+                    member_id,
+                    seen_at.date_naive()
+                ));
+            }
+        }
+        results.push(result);
+    }
+
+    for attendance in marked.values() {
+        webhooks::enqueue(&mut *tx, Event::attendance_marked(attendance)).await?;
+    }
+    tx.commit().await?;
+
+    for result in &results {
+        observe_attendance_mark(match result.status {
+            ScanStatus::Marked => "success",
+            ScanStatus::UnknownDevice => "unknown_device",
+            ScanStatus::NoRecord => "not_found",
+            ScanStatus::Invalid => "invalid",
+        });
+    }
+    Ok(results)
+}
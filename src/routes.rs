@@ -1,32 +1,135 @@
+use std::sync::Arc;
+
 use async_graphql::{http::GraphiQLSource, EmptySubscription, Schema};
-use async_graphql_axum::GraphQL;
+use async_graphql_axum::{GraphQLBatchRequest, GraphQLResponse};
 use axum::{
+    extract::{Request, State},
+    middleware,
     response::{Html, IntoResponse},
-    routing::get,
+    routing::{get, post},
     Router,
 };
-use tower_http::cors::CorsLayer;
+use sqlx::PgPool;
+use tower::ServiceBuilder;
+use tower_http::{
+    cors::CorsLayer,
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::{DefaultOnResponse, TraceLayer},
+};
+use tracing::{info_span, Level, Span};
 
+use crate::auth::Caller;
+use crate::clock::ClubClock;
+use crate::config::AttendancePolicy;
+use crate::daily_task::DailyTaskStatus;
 use crate::graphql::{Mutation, Query};
+use crate::limits::RateLimiter;
+use crate::supervisor::Supervisor;
+use crate::{auth, calendar, export, health, limits, metrics, presence};
 
-pub fn setup_router(
-    schema: Schema<Query, Mutation, EmptySubscription>,
-    cors: CorsLayer,
-    is_dev: bool,
-) -> Router {
-    let router = Router::new()
-        .route_service("/", GraphQL::new(schema.clone()))
-        .layer(cors);
+/// Shared state for the HTTP routes. GraphQL resolvers get theirs from the schema instead.
+#[derive(Clone)]
+pub struct AppState {
+    pub schema: Schema<Query, Mutation, EmptySubscription>,
+    pub pool: Arc<PgPool>,
+    pub daily_task: Arc<DailyTaskStatus>,
+    pub supervisor: Supervisor,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub clock: ClubClock,
+    /// Signs attendance from Presense, as in the schema's context.
+    pub secret: String,
+    pub attendance: AttendancePolicy,
+}
+
+pub fn setup_router(state: AppState, cors: CorsLayer, is_dev: bool) -> Router {
+    let mut api = Router::new()
+        .route("/", get(graphql).post(graphql))
+        .route("/export/members", get(export::export_members))
+        .route("/export/attendance", get(export::export_attendance))
+        .route(
+            "/export/attendance-summary",
+            get(export::export_attendance_summary),
+        )
+        .route("/export/streaks", get(export::export_streaks))
+        .route("/calendar", get(calendar::club_feed))
+        .route("/calendar/groups/{group_id}", get(calendar::group_feed))
+        .route("/calendar/members/{token}", get(calendar::member_feed))
+        .route("/presence/scans", post(presence::ingest_scans));
 
     if is_dev {
         tracing::info!("GraphiQL playground enabled at /graphiql");
-        router.route(
-            "/graphiql",
-            get(graphiql).post_service(GraphQL::new(schema)),
-        )
-    } else {
-        router
+        api = api.route("/graphiql", get(graphiql).post(graphql));
+    }
+
+    let api = api.layer(
+        ServiceBuilder::new()
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                limits::limit_address,
+            ))
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth::identify_caller,
+            ))
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                limits::limit_api_key,
+            )),
+    );
+
+    // Probes and scrapers poll these constantly, so they are exempt from rate limiting.
+    let operations = Router::new()
+        .route("/health", get(health::health))
+        .route("/ready", get(health::ready))
+        .route("/version", get(health::version))
+        .route("/metrics", get(metrics::metrics));
+
+    let router = api
+        .merge(operations)
+        .with_state(state)
+        .layer(middleware::from_fn(metrics::track_http_requests))
+        .layer(cors);
+
+    // Every request gets an `x-request-id`, or keeps the one it came with. It is logged with each
+    // line the request produces and echoed back so clients can quote it in bug reports.
+    router.layer(
+        ServiceBuilder::new()
+            .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+            .layer(
+                TraceLayer::new_for_http()
+                    .make_span_with(request_span)
+                    .on_response(DefaultOnResponse::new().level(Level::INFO)),
+            )
+            .layer(PropagateRequestIdLayer::x_request_id()),
+    )
+}
+
+fn request_span(request: &Request) -> Span {
+    let request_id = request
+        .headers()
+        .get("x-request-id")
+        .and_then(|id| id.to_str().ok())
+        .unwrap_or_default();
+    info_span!(
+        "request",
+        id = %request_id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    )
+}
+
+/// Runs GraphQL requests, passing on the caller identified by `auth::identify_caller` so
+/// extensions and resolvers can tell who is asking.
+async fn graphql(
+    State(state): State<AppState>,
+    caller: Option<Caller>,
+    request: GraphQLBatchRequest,
+) -> GraphQLResponse {
+    let mut request = request.into_inner();
+    if let Some(caller) = caller {
+        request = request.data(caller);
     }
+    state.schema.execute_batch(request).await.into()
 }
 
 async fn graphiql() -> impl IntoResponse {
@@ -0,0 +1,159 @@
+use chrono::{Duration, NaiveDate, NaiveTime};
+use sqlx::PgPool;
+
+use crate::models::member::Sex;
+
+/// Days of attendance history generated before `today`.
+const HISTORY_DAYS: i64 = 14;
+
+const GROUPS: [(&str, &str); 2] = [
+    ("Web", "Home, amD and the club website"),
+    ("Systems", "Root, Presense and the lab infrastructure"),
+];
+
+/// Roll number, name, sex, year, hostel and index into `GROUPS`.
+const MEMBERS: [(&str, &str, Sex, i32, &str, usize); 6] = [
+    ("AM.EN.U4CSE22001", "Asha Nair", Sex::F, 3, "Ganga", 0),
+    ("AM.EN.U4CSE23014", "Rahul Menon", Sex::M, 2, "Yamuna", 0),
+    ("AM.EN.U4CSE24027", "Devika Pillai", Sex::F, 1, "Ganga", 0),
+    ("AM.EN.U4ECE22031", "Arjun Das", Sex::M, 3, "Yamuna", 1),
+    ("AM.EN.U4AIE23042", "Anu Varma", Sex::Other, 2, "Kaveri", 1),
+    ("AM.EN.U4CSE24055", "Meera Iyer", Sex::F, 1, "Ganga", 1),
+];
+
+pub struct SeedReport {
+    pub groups: usize,
+    pub members: usize,
+    pub attendance_records: u64,
+}
+
+/// Fills a development database with groups, members and a fortnight of attendance, streaks and
+/// projects for them. Running it again leaves existing fixtures as they are.
+pub async fn seed(pool: &PgPool, today: NaiveDate) -> Result<SeedReport, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let mut group_ids = Vec::new();
+    for (name, description) in GROUPS {
+        // Updating on conflict, rather than doing nothing, makes `RETURNING` yield existing rows.
+        let group_id = sqlx::query_scalar::<_, i32>(
+            "INSERT INTO MemberGroup (name, description) VALUES ($1, $2)
+             ON CONFLICT (name) DO UPDATE SET name = EXCLUDED.name
+             RETURNING group_id",
+        )
+        .bind(name)
+        .bind(description)
+        .fetch_one(&mut *tx)
+        .await?;
+        group_ids.push(group_id);
+    }
+
+    let mut member_ids = Vec::new();
+    for (i, (roll_no, name, sex, year, hostel, group)) in MEMBERS.into_iter().enumerate() {
+        let first_name = name.split(' ').next().unwrap_or(name).to_lowercase();
+        let member_id = sqlx::query_scalar::<_, i32>(
+            "INSERT INTO Member
+                (roll_no, name, email, sex, year, hostel, mac_address, discord_id, group_id)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+             ON CONFLICT (roll_no) DO UPDATE SET roll_no = EXCLUDED.roll_no
+             RETURNING member_id",
+        )
+        .bind(roll_no)
+        .bind(name)
+        .bind(format!("{}@example.com", first_name))
+        .bind(sex)
+        .bind(year)
+        .bind(hostel)
+        .bind(format!("02:00:00:00:00:{:02X}", i + 1))
+        .bind(format!("{}", 100_000_000_000_000_000u64 + i as u64 + 1))
+        .bind(group_ids[group])
+        .fetch_one(&mut *tx)
+        .await?;
+        member_ids.push(member_id);
+
+        // Third years mentor their group.
+        if year >= 3 {
+            sqlx::query(
+                "INSERT INTO GroupMentor (group_id, member_id) VALUES ($1, $2)
+                 ON CONFLICT DO NOTHING",
+            )
+            .bind(group_ids[group])
+            .bind(member_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query(
+            "INSERT INTO StatusUpdateStreak (member_id, current_streak, max_streak)
+             VALUES ($1, $2, $3)
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(member_id)
+        .bind(i as i32)
+        .bind(i as i32 + 3)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO Project (member_id, title)
+             SELECT $1, $2 WHERE NOT EXISTS (SELECT 1 FROM Project WHERE member_id = $1)",
+        )
+        .bind(member_id)
+        .bind(format!("{}'s project", name))
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let mut attendance_records = 0;
+    for days_ago in 1..=HISTORY_DAYS {
+        let date = today - Duration::days(days_ago);
+        for (i, member_id) in member_ids.iter().enumerate() {
+            // Everyone misses about one day in four, on different days.
+            let is_present = (i as i64 + days_ago) % 4 != 0;
+            let (time_in, time_out) = if is_present {
+                (
+                    NaiveTime::from_hms_opt(9, i as u32 * 5, 0),
+                    NaiveTime::from_hms_opt(17, 30, 0),
+                )
+            } else {
+                (None, None)
+            };
+
+            attendance_records += sqlx::query(
+                "INSERT INTO Attendance (member_id, date, is_present, time_in, time_out)
+                 VALUES ($1, $2, $3, $4, $5)
+                 ON CONFLICT (member_id, date) DO NOTHING",
+            )
+            .bind(member_id)
+            .bind(date)
+            .bind(is_present)
+            .bind(time_in)
+            .bind(time_out)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+    }
+
+    // Derived from the attendance above, so the summaries agree with it.
+    sqlx::query(
+        "INSERT INTO AttendanceSummary (member_id, year, month, days_attended)
+         SELECT member_id, EXTRACT(YEAR FROM date)::INT, EXTRACT(MONTH FROM date)::INT,
+                COUNT(*) FILTER (WHERE is_present)
+         FROM Attendance
+         WHERE member_id = ANY($1)
+         GROUP BY 1, 2, 3
+         ON CONFLICT (member_id, year, month)
+         DO UPDATE SET days_attended = EXCLUDED.days_attended",
+    )
+    .bind(&member_ids)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(SeedReport {
+        groups: group_ids.len(),
+        members: member_ids.len(),
+        attendance_records,
+    })
+}
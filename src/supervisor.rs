@@ -0,0 +1,204 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::task::{AbortHandle, JoinSet};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+/// Crashes in a row after which a task is given up on instead of restarted.
+const MAX_RESTARTS: u32 = 5;
+/// A task that stays up this long is considered healthy again, resetting its restart count.
+const HEALTHY_AFTER: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskState {
+    Running,
+    /// Crashed and waiting to be restarted.
+    Restarting,
+    /// Crashed too many times in a row and won't be restarted.
+    Failed,
+    /// Returned on its own, or was stopped for shutdown.
+    Stopped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskHealth {
+    pub state: TaskState,
+    pub restarts: u32,
+    pub last_error: Option<String>,
+}
+
+/// Runs background tasks, restarting them with a backoff when they panic, and tells them when
+/// Root is shutting down. Their health is reported on `/ready`.
+#[derive(Clone, Default)]
+pub struct Supervisor {
+    tasks: Arc<Mutex<BTreeMap<&'static str, TaskHealth>>>,
+    handles: Arc<Mutex<JoinSet<()>>>,
+    shutdown: CancellationToken,
+}
+
+impl Supervisor {
+    /// Spawns `task`, calling it again whenever it panics. It is given a token that is cancelled on
+    /// shutdown, after which it should return as soon as it is safe to.
+    pub fn spawn<F, Fut>(&self, name: &'static str, task: F)
+    where
+        F: Fn(CancellationToken) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let supervisor = self.clone();
+        self.handles
+            .lock()
+            .expect("Supervisor lock poisoned")
+            .spawn(async move { supervisor.supervise(name, task).await });
+    }
+
+    async fn supervise<F, Fut>(self, name: &'static str, task: F)
+    where
+        F: Fn(CancellationToken) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let mut restarts = 0;
+        loop {
+            self.set_health(name, TaskState::Running, restarts, None);
+            let started = Instant::now();
+
+            // Spawned separately so a panic is caught by its `JoinHandle` instead of unwinding here.
+            // Dropping a `JoinHandle` detaches its task, so the task is aborted along with this one.
+            let handle = tokio::spawn(task(self.shutdown.child_token()));
+            let _abort = AbortOnDrop(handle.abort_handle());
+            let result = handle.await;
+
+            let panic = match result {
+                Ok(()) => {
+                    info!("Background task '{}' stopped.", name);
+                    self.set_health(name, TaskState::Stopped, restarts, None);
+                    return;
+                }
+                Err(e) => panic_message(e),
+            };
+
+            if started.elapsed() >= HEALTHY_AFTER {
+                restarts = 0;
+            }
+            if self.shutdown.is_cancelled() {
+                self.set_health(name, TaskState::Stopped, restarts, Some(panic));
+                return;
+            }
+            if restarts >= MAX_RESTARTS {
+                error!(
+                    "Background task '{}' crashed {} times in a row, giving up: {}",
+                    name,
+                    restarts + 1,
+                    panic
+                );
+                self.set_health(name, TaskState::Failed, restarts, Some(panic));
+                return;
+            }
+
+            let backoff = Duration::from_secs(2u64.pow(restarts));
+            restarts += 1;
+            error!(
+                "Background task '{}' crashed, restarting in {}s: {}",
+                name,
+                backoff.as_secs(),
+                panic
+            );
+            self.set_health(name, TaskState::Restarting, restarts, Some(panic));
+
+            tokio::select! {
+                _ = tokio::time::sleep(backoff) => {}
+                _ = self.shutdown.cancelled() => {
+                    self.set_health(name, TaskState::Stopped, restarts, None);
+                    return;
+                }
+            }
+        }
+    }
+
+    fn set_health(
+        &self,
+        name: &'static str,
+        state: TaskState,
+        restarts: u32,
+        last_error: Option<String>,
+    ) {
+        let mut tasks = self.tasks.lock().expect("Supervisor lock poisoned");
+        let health = tasks.entry(name).or_insert(TaskHealth {
+            state,
+            restarts,
+            last_error: None,
+        });
+        health.state = state;
+        health.restarts = restarts;
+        if last_error.is_some() {
+            health.last_error = last_error;
+        }
+    }
+
+    pub fn health(&self) -> BTreeMap<&'static str, TaskHealth> {
+        self.tasks.lock().expect("Supervisor lock poisoned").clone()
+    }
+
+    /// Whether every task is running. Tasks that stop during shutdown no longer count as healthy.
+    pub fn is_healthy(&self) -> bool {
+        self.health()
+            .values()
+            .all(|health| health.state == TaskState::Running)
+    }
+
+    /// Resolves once shutdown has been requested.
+    pub fn shutdown_requested(&self) -> impl Future<Output = ()> + Send + 'static {
+        self.shutdown.clone().cancelled_owned()
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown.is_cancelled()
+    }
+
+    pub fn shutdown(&self) {
+        self.shutdown.cancel();
+    }
+
+    /// Waits up to `timeout` for every task to stop after `shutdown`, then aborts the rest.
+    pub async fn join(&self, timeout: Duration) {
+        let mut handles =
+            std::mem::take(&mut *self.handles.lock().expect("Supervisor lock poisoned"));
+        let drained = tokio::time::timeout(timeout, async {
+            while handles.join_next().await.is_some() {}
+        })
+        .await;
+
+        if drained.is_err() {
+            warn!(
+                "{} background task(s) did not stop in time and were aborted.",
+                handles.len()
+            );
+            handles.shutdown().await;
+        }
+    }
+}
+
+/// Aborts a task when dropped.
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+fn panic_message(e: tokio::task::JoinError) -> String {
+    match e.try_into_panic() {
+        Ok(panic) => panic
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string()),
+        Err(e) => e.to_string(),
+    }
+}
@@ -0,0 +1,131 @@
+use std::time::{Duration, SystemTime};
+
+use opentelemetry::{
+    trace::{Span, SpanKind, Tracer, TracerProvider},
+    Context, KeyValue,
+};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{
+    runtime,
+    trace::{
+        span_processor_with_async_runtime::BatchSpanProcessor, Sampler, SdkTracer,
+        SdkTracerProvider,
+    },
+    Resource,
+};
+use tracing::{
+    field::{Field, Visit},
+    Event, Subscriber,
+};
+use tracing_subscriber::{layer::Context as LayerContext, registry::LookupSpan, Layer};
+
+use crate::config::TelemetryConfig;
+
+/// Exports spans over OTLP/HTTP in batches. Must be called from within the Tokio runtime.
+pub fn tracer_provider(config: &TelemetryConfig) -> Result<SdkTracerProvider, String> {
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(&config.endpoint)
+        .build()
+        .map_err(|e| format!("Could not create the OTLP exporter: {}", e))?;
+
+    Ok(SdkTracerProvider::builder()
+        .with_span_processor(BatchSpanProcessor::builder(exporter, runtime::Tokio).build())
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio,
+        ))))
+        .with_resource(
+            Resource::builder()
+                .with_service_name(config.service_name.clone())
+                .with_attribute(KeyValue::new("service.version", env!("CARGO_PKG_VERSION")))
+                .build(),
+        )
+        .build())
+}
+
+/// Turns `tracing` spans into OpenTelemetry spans, and sqlx's query events into spans of their
+/// own. Any provider works, so tests can pass one backed by an in-memory exporter.
+pub fn layer<S>(provider: &SdkTracerProvider) -> impl Layer<S> + Send + Sync
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+{
+    let tracer = provider.tracer("root");
+    tracing_opentelemetry::layer()
+        .with_tracer(tracer.clone())
+        .and_then(SqlxQuerySpans { tracer })
+}
+
+/// sqlx logs each query as a single event once it has finished, instead of wrapping it in a span.
+/// This backdates a span for it from the reported duration, under whichever span was current.
+struct SqlxQuerySpans {
+    tracer: SdkTracer,
+}
+
+impl<S: Subscriber> Layer<S> for SqlxQuerySpans {
+    fn on_event(&self, event: &Event<'_>, _ctx: LayerContext<'_, S>) {
+        if event.metadata().target() != "sqlx::query" {
+            return;
+        }
+
+        let mut query = SqlxQuery::default();
+        event.record(&mut query);
+
+        let end = SystemTime::now();
+        let start = end - Duration::from_secs_f64(query.elapsed_secs);
+        let statement = if query.statement.trim().is_empty() {
+            query.summary
+        } else {
+            query.statement.trim().to_string()
+        };
+
+        // The OpenTelemetry layer makes the current span's context current when it is entered.
+        let mut span = self
+            .tracer
+            .span_builder("sqlx.query")
+            .with_kind(SpanKind::Client)
+            .with_start_time(start)
+            .with_attributes([
+                KeyValue::new("db.system", "postgresql"),
+                KeyValue::new("db.statement", statement),
+                KeyValue::new("db.rows_affected", query.rows_affected as i64),
+                KeyValue::new("db.rows_returned", query.rows_returned as i64),
+            ])
+            .start_with_context(&self.tracer, &Context::current());
+        span.end_with_timestamp(end);
+    }
+}
+
+#[derive(Default)]
+struct SqlxQuery {
+    summary: String,
+    statement: String,
+    rows_affected: u64,
+    rows_returned: u64,
+    elapsed_secs: f64,
+}
+
+impl Visit for SqlxQuery {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "summary" => self.summary = value.to_string(),
+            "db.statement" => self.statement = value.to_string(),
+            _ => {}
+        }
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match field.name() {
+            "rows_affected" => self.rows_affected = value,
+            "rows_returned" => self.rows_returned = value,
+            _ => {}
+        }
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        if field.name() == "elapsed_secs" {
+            self.elapsed_secs = value;
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+}
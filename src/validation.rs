@@ -0,0 +1,82 @@
+//! Normalisers for member fields that other services match on.
+//!
+//! Each returns the canonical form of a valid value, or a message describing what is wrong with
+//! it. Callers attach the field name, since GraphQL inputs and CSV columns name fields differently.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// University roll numbers, e.g. `AM.EN.U4CSE22001`.
+static ROLL_NO: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[A-Z]{2}\.[A-Z]{2}\.[A-Z][0-9][A-Z]{2,4}[0-9]{5}$")
+        .expect("Hardcoded regex must be valid")
+});
+
+static EMAIL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[^@\s]+@[^@\s.]+(\.[^@\s.]+)+$").expect("Hardcoded regex must be valid")
+});
+
+/// A failed validation for a single field, surfaced to clients with the field's name.
+#[derive(Debug)]
+pub struct ValidationError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl ValidationError {
+    pub fn new(field: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            field,
+            message: message.into(),
+        }
+    }
+}
+
+pub fn email(value: &str) -> Result<String, String> {
+    let value = value.trim();
+    if value.len() > 255 || !EMAIL.is_match(value) {
+        return Err(format!("'{}' is not a valid email address", value));
+    }
+    Ok(value.to_lowercase())
+}
+
+/// Accepts colon, hyphen, dot or no separators in any case, and returns `AA:BB:CC:DD:EE:FF` so
+/// that a scanned address matches however it was entered.
+pub fn mac_address(value: &str) -> Result<String, String> {
+    let digits: String = value
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ':' | '-' | '.'))
+        .collect();
+    if digits.len() != 12 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("'{}' is not a valid MAC address", value));
+    }
+
+    let digits = digits.to_ascii_uppercase();
+    let octets: Vec<&str> = (0..12).step_by(2).map(|i| &digits[i..i + 2]).collect();
+    Ok(octets.join(":"))
+}
+
+pub fn roll_no(value: &str) -> Result<String, String> {
+    let value = value.trim().to_ascii_uppercase();
+    if !ROLL_NO.is_match(&value) {
+        return Err(format!(
+            "'{}' is not a roll number like AM.EN.U4CSE22001",
+            value
+        ));
+    }
+    Ok(value)
+}
+
+/// Discord IDs are snowflakes: 64-bit integers, 17 to 20 digits long in practice.
+pub fn discord_id(value: &str) -> Result<String, String> {
+    let value = value.trim();
+    if !(17..=20).contains(&value.len())
+        || !value.chars().all(|c| c.is_ascii_digit())
+        || value.parse::<u64>().is_err()
+    {
+        return Err(format!("'{}' is not a Discord user ID", value));
+    }
+    Ok(value.to_string())
+}
@@ -0,0 +1,273 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use futures::future::join_all;
+use reqwest::header::CONTENT_TYPE;
+use sqlx::{FromRow, PgPool};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, warn};
+
+use crate::clock::ClubClock;
+use crate::config::WebhooksConfig;
+use crate::metrics::observe_webhook_delivery;
+use crate::models::webhook::{WebhookDeliveryStatus, WebhookEventType};
+
+/// How long past the HTTP timeout a claimed delivery stays claimed, to cover recording the result.
+const CLAIM_MARGIN: TimeDelta = TimeDelta::seconds(60);
+
+/// A pending delivery whose next attempt is due, with everything needed to make it.
+#[derive(FromRow)]
+struct DueDelivery {
+    delivery_id: i64,
+    attempts: i32,
+    event_id: i64,
+    event_type: WebhookEventType,
+    payload: serde_json::Value,
+    created_at: DateTime<Utc>,
+    url: String,
+    secret: String,
+}
+
+/// What came of POSTing a payload once.
+struct Attempt {
+    attempted_at: DateTime<Utc>,
+    response_status: Option<i32>,
+    error: Option<String>,
+    duration: Duration,
+}
+
+impl Attempt {
+    fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Delivers queued webhook events, see [`crate::webhooks`].
+pub struct WebhookDispatcher {
+    pool: Arc<PgPool>,
+    client: reqwest::Client,
+    clock: ClubClock,
+    config: WebhooksConfig,
+}
+
+impl WebhookDispatcher {
+    pub fn new(pool: Arc<PgPool>, clock: ClubClock, config: &WebhooksConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .user_agent(concat!("Root/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .expect("Webhook HTTP client must build");
+
+        Self {
+            pool,
+            client,
+            clock,
+            config: config.clone(),
+        }
+    }
+
+    /// Checks for due deliveries every `poll_interval_secs` until `shutdown` is cancelled.
+    pub async fn run(&self, shutdown: CancellationToken) {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.config.poll_interval_secs));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.cancelled() => return,
+            }
+
+            // A full batch suggests there's a backlog, so carry on without waiting for the next tick.
+            loop {
+                match self.deliver_due().await {
+                    Ok(attempted) if attempted == self.config.batch_size as usize => continue,
+                    Ok(_) => break,
+                    Err(e) => {
+                        error!("Failed to deliver webhooks: {:?}", e);
+                        break;
+                    }
+                }
+            }
+            if let Err(e) = self.prune().await {
+                error!("Failed to prune webhook events: {:?}", e);
+            }
+        }
+    }
+
+    /// Makes the next attempt at up to `batch_size` due deliveries, concurrently, and returns how
+    /// many were attempted. Deliveries being attempted by another instance are skipped.
+    pub async fn deliver_due(&self) -> Result<usize, sqlx::Error> {
+        let now = self.clock.now().to_utc();
+        // Claimed deliveries aren't due again until the lease runs out, so no row stays locked
+        // while the endpoints are called. If Root stops mid-batch, they are retried after it.
+        let lease_until = now + TimeDelta::seconds(self.config.timeout_secs as i64) + CLAIM_MARGIN;
+
+        let due = sqlx::query_as::<_, DueDelivery>(
+            "WITH claimed AS (
+                SELECT del.delivery_id
+                FROM WebhookDelivery del
+                JOIN Webhook hook ON hook.webhook_id = del.webhook_id
+                WHERE del.status = 'pending'
+                AND (del.next_attempt_at IS NULL OR del.next_attempt_at <= $1)
+                AND hook.active
+                ORDER BY del.delivery_id
+                LIMIT $2
+                FOR UPDATE OF del SKIP LOCKED
+             )
+             UPDATE WebhookDelivery del
+             SET next_attempt_at = $3
+             FROM claimed, WebhookEvent ev, Webhook hook
+             WHERE del.delivery_id = claimed.delivery_id
+             AND ev.event_id = del.event_id
+             AND hook.webhook_id = del.webhook_id
+             RETURNING del.delivery_id, del.attempts, ev.event_id, ev.event_type, ev.payload,
+                       ev.created_at, hook.url, hook.secret",
+        )
+        .bind(now)
+        .bind(i64::from(self.config.batch_size))
+        .bind(lease_until)
+        .fetch_all(self.pool.as_ref())
+        .await?;
+
+        let attempts = join_all(due.iter().map(|delivery| self.attempt(delivery))).await;
+
+        let mut tx = self.pool.begin().await?;
+
+        for (delivery, attempt) in due.iter().zip(&attempts) {
+            let attempts_made = delivery.attempts + 1;
+            let (status, next_attempt_at) = if attempt.succeeded() {
+                (WebhookDeliveryStatus::Delivered, None)
+            } else if attempts_made >= self.config.max_attempts as i32 {
+                (WebhookDeliveryStatus::Failed, None)
+            } else {
+                let retry_at = attempt.attempted_at + self.backoff(attempts_made);
+                (WebhookDeliveryStatus::Pending, Some(retry_at))
+            };
+
+            match status {
+                WebhookDeliveryStatus::Delivered => {
+                    debug!("Delivered webhook delivery #{}", delivery.delivery_id);
+                    observe_webhook_delivery("delivered");
+                }
+                WebhookDeliveryStatus::Pending => observe_webhook_delivery("retrying"),
+                WebhookDeliveryStatus::Failed => {
+                    warn!(
+                        "Giving up on webhook delivery #{} to {} after {} attempts",
+                        delivery.delivery_id, delivery.url, attempts_made
+                    );
+                    observe_webhook_delivery("failed");
+                }
+            }
+
+            sqlx::query(
+                "INSERT INTO WebhookDeliveryAttempt
+                    (delivery_id, attempted_at, response_status, error, duration_ms)
+                 VALUES ($1, $2, $3, $4, $5)",
+            )
+            .bind(delivery.delivery_id)
+            .bind(attempt.attempted_at)
+            .bind(attempt.response_status)
+            .bind(&attempt.error)
+            .bind(attempt.duration.as_millis().min(i32::MAX as u128) as i32)
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query(
+                "UPDATE WebhookDelivery
+                 SET attempts = $2, status = $3, next_attempt_at = $4,
+                     last_response_status = $5, last_error = $6,
+                     delivered_at = CASE WHEN $3 = 'delivered' THEN $7 END
+                 WHERE delivery_id = $1",
+            )
+            .bind(delivery.delivery_id)
+            .bind(attempts_made)
+            .bind(status)
+            .bind(next_attempt_at)
+            .bind(attempt.response_status)
+            .bind(&attempt.error)
+            .bind(attempt.attempted_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(due.len())
+    }
+
+    async fn attempt(&self, delivery: &DueDelivery) -> Attempt {
+        let body = serde_json::to_vec(&serde_json::json!({
+            "id": delivery.event_id,
+            "type": delivery.event_type.as_str(),
+            "created_at": delivery.created_at,
+            "data": delivery.payload,
+        }))
+        .expect("Webhook payloads must serialize to JSON");
+
+        let attempted_at = self.clock.now().to_utc();
+        let timestamp = attempted_at.timestamp();
+        let start = Instant::now();
+        let response = self
+            .client
+            .post(&delivery.url)
+            .header(CONTENT_TYPE, "application/json")
+            .header("X-Root-Event", delivery.event_type.as_str())
+            .header("X-Root-Delivery", delivery.delivery_id)
+            .header("X-Root-Timestamp", timestamp)
+            .header(
+                "X-Root-Signature",
+                super::signature(&delivery.secret, timestamp, &body),
+            )
+            .body(body)
+            .send()
+            .await;
+        let duration = start.elapsed();
+
+        let (response_status, error) = match response {
+            Ok(response) if response.status().is_success() => {
+                (Some(response.status().as_u16() as i32), None)
+            }
+            Ok(response) => (
+                Some(response.status().as_u16() as i32),
+                Some(format!("Responded with {}", response.status())),
+            ),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        Attempt {
+            attempted_at,
+            response_status,
+            error,
+            duration,
+        }
+    }
+
+    /// How long to wait before retrying a delivery that has failed `attempts` times.
+    fn backoff(&self, attempts: i32) -> TimeDelta {
+        let factor = 1u64
+            .checked_shl(attempts.saturating_sub(1) as u32)
+            .unwrap_or(u64::MAX);
+        let secs = self
+            .config
+            .initial_backoff_secs
+            .saturating_mul(factor)
+            .min(self.config.max_backoff_secs);
+        TimeDelta::seconds(secs as i64)
+    }
+
+    /// Deletes events older than `retention_days` that have no deliveries left to make, along
+    /// with their delivery logs.
+    pub async fn prune(&self) -> Result<u64, sqlx::Error> {
+        let cutoff = self.clock.now().to_utc() - TimeDelta::days(self.config.retention_days as i64);
+        let result = sqlx::query(
+            "DELETE FROM WebhookEvent ev
+             WHERE ev.created_at < $1
+             AND NOT EXISTS (
+                SELECT 1 FROM WebhookDelivery del
+                WHERE del.event_id = ev.event_id AND del.status = 'pending'
+             )",
+        )
+        .bind(cutoff)
+        .execute(self.pool.as_ref())
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
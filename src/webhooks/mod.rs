@@ -0,0 +1,113 @@
+//! Notifies other services of changes in Root by POSTing signed JSON to the endpoints admins
+//! register.
+//!
+//! Changes are recorded as events in the same transaction as the change itself, together with a
+//! pending delivery for each subscribed webhook. The [`WebhookDispatcher`] then delivers them in
+//! the background, retrying failures with exponential backoff, so nothing is lost if an endpoint
+//! is down or Root restarts.
+
+use chrono::NaiveDate;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::Serialize;
+use sha2::Sha256;
+use sqlx::PgExecutor;
+
+use crate::models::{
+    attendance::Attendance, member::Member, project::Project,
+    status_update_streak::StatusUpdateStreak, webhook::WebhookEventType,
+};
+
+mod delivery;
+
+pub use delivery::WebhookDispatcher;
+
+/// Something to tell subscribed webhooks about, with the `data` of its payload.
+pub struct Event {
+    event_type: WebhookEventType,
+    data: serde_json::Value,
+}
+
+#[derive(Serialize)]
+struct DailyTaskCompleted {
+    date: NaiveDate,
+    members: usize,
+    succeeded: bool,
+}
+
+impl Event {
+    fn new(event_type: WebhookEventType, data: impl Serialize) -> Self {
+        Self {
+            event_type,
+            data: serde_json::to_value(data).expect("Event payloads must serialize to JSON"),
+        }
+    }
+
+    pub fn member_created(member: &Member) -> Self {
+        Self::new(WebhookEventType::MemberCreated, member)
+    }
+
+    pub fn attendance_marked(attendance: &Attendance) -> Self {
+        Self::new(WebhookEventType::AttendanceMarked, attendance)
+    }
+
+    pub fn streak_reset(streak: &StatusUpdateStreak) -> Self {
+        Self::new(WebhookEventType::StreakReset, streak)
+    }
+
+    pub fn project_updated(project: &Project) -> Self {
+        Self::new(WebhookEventType::ProjectUpdated, project)
+    }
+
+    /// `members` is how many active members the task added attendance records for.
+    pub fn daily_task_completed(date: NaiveDate, members: usize, succeeded: bool) -> Self {
+        Self::new(
+            WebhookEventType::DailyTaskCompleted,
+            DailyTaskCompleted {
+                date,
+                members,
+                succeeded,
+            },
+        )
+    }
+}
+
+/// Queues `event` for delivery to every active webhook subscribed to it. Pass the transaction that
+/// made the change, so the event is recorded if and only if the change is.
+pub async fn enqueue<'c>(executor: impl PgExecutor<'c>, event: Event) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "WITH subscribers AS (
+            SELECT webhook_id FROM Webhook WHERE active AND $1 = ANY(events)
+         ), event AS (
+            INSERT INTO WebhookEvent (event_type, payload)
+            SELECT $1, $2 WHERE EXISTS (SELECT 1 FROM subscribers)
+            RETURNING event_id
+         )
+         INSERT INTO WebhookDelivery (webhook_id, event_id)
+         SELECT subscribers.webhook_id, event.event_id FROM subscribers, event",
+    )
+    .bind(event.event_type)
+    .bind(event.data)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// A new secret for a webhook to verify its payloads with.
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("whsec_{}", hex::encode(bytes))
+}
+
+/// The `X-Root-Signature` of a payload: an HMAC-SHA256 of `<timestamp>.<body>` keyed with the
+/// webhook's secret, where `timestamp` is the `X-Root-Timestamp` header. Including the timestamp
+/// lets receivers reject old payloads being replayed.
+pub fn signature(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use chrono::TimeDelta;
+use root::clock::FixedClock;
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::common::{execute, execute_err, now, schema, schema_with_clock, sign};
+
+fn mark(member_id: i32, date: &str, signature: &str) -> String {
+    format!(
+        r#"mutation {{ markAttendance(input: {{
+            memberId: {}, date: "{}", hmacSignature: "{}"
+        }}) {{ isPresent timeIn timeOut }} }}"#,
+        member_id, date, signature
+    )
+}
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn attendance_lists_records_by_member_and_date(pool: PgPool) {
+    let schema = schema(&pool);
+
+    let data = execute(&schema, "{ attendance(memberId: 2) { date isPresent } }").await;
+    assert_eq!(
+        data["attendance"],
+        json!([
+            { "date": "2025-01-09", "isPresent": false },
+            { "date": "2025-01-10", "isPresent": true },
+        ])
+    );
+
+    let data = execute(
+        &schema,
+        r#"{ attendanceByDate(date: "2025-01-10") { memberId name isPresent } }"#,
+    )
+    .await;
+    let mut records = data["attendanceByDate"].as_array().unwrap().clone();
+    records.sort_by_key(|record| record["memberId"].as_i64());
+    assert_eq!(
+        records,
+        vec![
+            json!({ "memberId": 1, "name": "Asha Nair", "isPresent": false }),
+            json!({ "memberId": 2, "name": "Rahul Menon", "isPresent": true }),
+            json!({ "memberId": 3, "name": "Devika Pillai", "isPresent": true }),
+        ]
+    );
+}
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn mark_attendance_keeps_the_first_time_in(pool: PgPool) {
+    let clock = Arc::new(FixedClock::new(now()));
+    let schema = schema_with_clock(&pool, clock.clone());
+    let signature = sign(2, "2025-01-09");
+
+    let data = execute(&schema, mark(2, "2025-01-09", &signature)).await;
+    assert_eq!(
+        data["markAttendance"],
+        json!({ "isPresent": true, "timeIn": "09:30:00", "timeOut": "09:30:00" })
+    );
+
+    clock.advance(TimeDelta::hours(8));
+    let data = execute(&schema, mark(2, "2025-01-09", &signature)).await;
+    assert_eq!(
+        data["markAttendance"],
+        json!({ "isPresent": true, "timeIn": "09:30:00", "timeOut": "17:30:00" })
+    );
+}
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn mark_attendance_rejects_bad_signatures_and_missing_records(pool: PgPool) {
+    let schema = schema(&pool);
+
+    let error = execute_err(&schema, mark(2, "2025-01-09", &sign(1, "2025-01-09"))).await;
+    assert_eq!(error, json!({ "code": "UNAUTHENTICATED" }));
+
+    let error = execute_err(&schema, mark(2, "2025-01-09", "not hex")).await;
+    assert_eq!(
+        error,
+        json!({ "code": "VALIDATION", "field": "hmacSignature" })
+    );
+
+    let error = execute_err(&schema, mark(2, "2025-02-01", &sign(2, "2025-02-01"))).await;
+    assert_eq!(error, json!({ "code": "NOT_FOUND" }));
+
+    let data = execute(&schema, "{ attendance(memberId: 2) { isPresent } }").await;
+    assert_eq!(data["attendance"][0]["isPresent"], false);
+}
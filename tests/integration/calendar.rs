@@ -0,0 +1,234 @@
+use std::sync::Arc;
+
+use axum::http::{header::CACHE_CONTROL, StatusCode};
+use root::auth::Role;
+use root::calendar::{feed, feed_token_member, FeedScope};
+use root::clock::{ClubClock, FixedClock};
+use serde_json::json;
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+use crate::common::{
+    app_state, as_caller, as_member, execute, execute_err, get, now, router, schema, RootSchema,
+};
+
+fn club_clock(timezone: chrono_tz::Tz) -> ClubClock {
+    ClubClock::new(Arc::new(FixedClock::new(now())), timezone)
+}
+
+async fn admin(schema: &RootSchema, mutation: &str) {
+    execute(schema, as_caller(mutation, Role::Admin)).await;
+}
+
+/// Adds a workshop for everyone, a meeting for group 2, a club holiday and a group 2 deadline.
+async fn add_entries(schema: &RootSchema) {
+    admin(
+        schema,
+        r#"mutation { createEvent(input: {
+            title: "Rust workshop", description: "Bring a laptop; and a charger", kind: WORKSHOP,
+            venue: "Lab 1", startsAt: "2025-01-10T10:30:00Z", endsAt: "2025-01-10T12:30:00Z"
+        }) { eventId } }"#,
+    )
+    .await;
+    admin(
+        schema,
+        r#"mutation { createEvent(input: {
+            title: "Systems sync", kind: MEETING, audienceGroupIds: [2],
+            startsAt: "2025-01-11T10:30:00Z", endsAt: "2025-01-11T11:30:00Z"
+        }) { eventId } }"#,
+    )
+    .await;
+    admin(
+        schema,
+        r#"mutation { addCalendarDate(input: {
+            date: "2025-01-26", title: "Republic Day", kind: HOLIDAY
+        }) { dateId } }"#,
+    )
+    .await;
+    admin(
+        schema,
+        r#"mutation { addCalendarDate(input: {
+            date: "2025-01-31", title: "Kernel patch, reviewed", kind: DEADLINE, groupId: 2
+        }) { dateId } }"#,
+    )
+    .await;
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn club_feed_is_valid_icalendar_in_the_club_timezone(pool: PgPool) {
+    let schema = schema(&pool);
+    add_entries(&schema).await;
+
+    let ics = feed(
+        &pool,
+        &club_clock(chrono_tz::Asia::Kolkata),
+        FeedScope::Club,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    assert!(ics.split("\r\n").all(|line| line.len() <= 75));
+    for expected in [
+        "X-WR-TIMEZONE:Asia/Kolkata",
+        "TZID:Asia/Kolkata",
+        "TZOFFSETTO:+0530",
+        "UID:event-1@root.amfoss.in",
+        "DTSTAMP:20250110T040000Z",
+        "DTSTART;TZID=Asia/Kolkata:20250110T160000",
+        "DTEND;TZID=Asia/Kolkata:20250110T180000",
+        "DESCRIPTION:Bring a laptop\\; and a charger",
+        "CATEGORIES:WORKSHOP",
+        "UID:event-2@root.amfoss.in",
+        "UID:date-1@root.amfoss.in",
+        "DTSTART;VALUE=DATE:20250126",
+        "DTEND;VALUE=DATE:20250127",
+        "CATEGORIES:HOLIDAY",
+    ] {
+        assert!(
+            ics.contains(expected),
+            "{} is missing from\n{}",
+            expected,
+            ics
+        );
+    }
+    // Group deadlines only appear in that group's feeds.
+    assert!(!ics.contains("date-2@"));
+
+    let data = execute(&schema, "{ calendarDates(groupId: 2) { title kind } }").await;
+    assert_eq!(
+        data["calendarDates"],
+        json!([
+            { "title": "Republic Day", "kind": "HOLIDAY" },
+            { "title": "Kernel patch, reviewed", "kind": "DEADLINE" },
+        ])
+    );
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn group_and_member_feeds_only_show_what_applies_to_them(pool: PgPool) {
+    let schema = schema(&pool);
+    let clock = club_clock(chrono_tz::Asia::Kolkata);
+    add_entries(&schema).await;
+
+    let web = feed(&pool, &clock, FeedScope::Group(1))
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(web.contains("X-WR-CALNAME:amFOSS Web"));
+    assert!(web.contains("event-1@") && !web.contains("event-2@"));
+    assert!(web.contains("date-1@") && !web.contains("date-2@"));
+
+    let systems = feed(&pool, &clock, FeedScope::Group(2))
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(systems.contains("event-2@"));
+    assert!(systems.contains("SUMMARY:Kernel patch\\, reviewed"));
+    assert!(feed(&pool, &clock, FeedScope::Group(9))
+        .await
+        .unwrap()
+        .is_none());
+
+    // Devika is in group 2, and declining the workshop takes it off her calendar.
+    execute(
+        &schema,
+        as_member(
+            "mutation { rsvpEvent(input: { eventId: 1, memberId: 3, status: NOT_GOING }) { status } }",
+            3,
+        ),
+    )
+    .await;
+    let devika = feed(&pool, &clock, FeedScope::Member(3))
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!devika.contains("event-1@") && devika.contains("event-2@"));
+    assert!(devika.contains("date-2@"));
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn member_feed_tokens_are_private_and_replaceable(pool: PgPool) {
+    let schema = schema(&pool);
+    let create = "mutation { createCalendarFeedToken(memberId: 2) }";
+
+    let error = execute_err(&schema, as_member(create, 1)).await;
+    assert_eq!(error, json!({ "code": "FORBIDDEN" }));
+
+    let data = execute(&schema, as_member(create, 2)).await;
+    let first = data["createCalendarFeedToken"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let token = first.strip_prefix("/calendar/members/").unwrap();
+    assert_eq!(feed_token_member(&pool, token).await.unwrap(), Some(2));
+    assert_eq!(
+        feed_token_member(&pool, token.trim_end_matches(".ics"))
+            .await
+            .unwrap(),
+        Some(2)
+    );
+
+    // Only the club and group feeds may be kept by shared caches.
+    let router = router(app_state(&pool));
+    for (uri, cache_control) in [
+        (first.as_str(), "private, max-age=900"),
+        ("/calendar", "public, max-age=900"),
+    ] {
+        let response = router.clone().oneshot(get(uri, None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CACHE_CONTROL], cache_control);
+    }
+
+    execute(&schema, as_member(create, 2)).await;
+    assert_eq!(feed_token_member(&pool, token).await.unwrap(), None);
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn timezones_with_daylight_saving_list_each_transition(pool: PgPool) {
+    let schema = schema(&pool);
+    admin(
+        &schema,
+        r#"mutation { createEvent(input: {
+            title: "Winter meetup", startsAt: "2025-01-20T16:00:00Z", endsAt: "2025-01-20T18:00:00Z"
+        }) { eventId } }"#,
+    )
+    .await;
+    admin(
+        &schema,
+        r#"mutation { createEvent(input: {
+            title: "Summer meetup", startsAt: "2025-07-04T16:00:00Z", endsAt: "2025-07-04T18:00:00Z"
+        }) { eventId } }"#,
+    )
+    .await;
+
+    admin(
+        &schema,
+        r#"mutation { createEvent(input: {
+            title: "Far off", startsAt: "2400-07-04T16:00:00Z", endsAt: "2400-07-04T18:00:00Z"
+        }) { eventId } }"#,
+    )
+    .await;
+
+    let ics = feed(
+        &pool,
+        &club_clock(chrono_tz::America::New_York),
+        FeedScope::Club,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    // Changes are only listed a couple of years ahead, however far off the last event is.
+    assert_eq!(ics.matches("BEGIN:DAYLIGHT").count(), 2, "{}", ics);
+    for expected in [
+        "BEGIN:STANDARD\r\nDTSTART:20250119T110000\r\nTZOFFSETFROM:-0500\r\nTZOFFSETTO:-0500",
+        "BEGIN:DAYLIGHT\r\nDTSTART:20250309T020000\r\nTZOFFSETFROM:-0500\r\nTZOFFSETTO:-0400\r\nTZNAME:EDT",
+        "DTSTART;TZID=America/New_York:20250120T110000",
+        "DTSTART;TZID=America/New_York:20250704T120000",
+    ] {
+        assert!(ics.contains(expected), "{} is missing from\n{}", expected, ics);
+    }
+}
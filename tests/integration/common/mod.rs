@@ -0,0 +1,167 @@
+use std::sync::Arc;
+
+use async_graphql::{EmptySubscription, Request, Schema};
+use axum::body::{to_bytes, Body};
+use axum::http::{self, StatusCode};
+use axum::Router;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use root::auth::{Caller, Role};
+use root::clock::{ClubClock, FixedClock};
+use root::config::Config;
+use root::daily_task::DailyTaskStatus;
+use root::graphql::{Mutation, Query};
+use root::limits::RateLimiter;
+use root::routes::{setup_router, AppState};
+use root::supervisor::Supervisor;
+use serde_json::Value;
+use sha2::Sha256;
+use sqlx::PgPool;
+use tower::ServiceExt;
+use tower_http::cors::CorsLayer;
+
+/// The secret attendance mutations are signed with in tests.
+pub const SECRET: &str = "test-secret";
+
+pub type RootSchema = Schema<Query, Mutation, EmptySubscription>;
+
+/// 09:30 on 10 January 2025 in Kolkata, the default club timezone.
+pub fn now() -> DateTime<Utc> {
+    "2025-01-10T04:00:00Z".parse().unwrap()
+}
+
+/// The schema Root serves, backed by the test's own database, with the time stopped at [`now`].
+pub fn schema(pool: &PgPool) -> RootSchema {
+    schema_with_clock(pool, Arc::new(FixedClock::new(now())))
+}
+
+/// Like [`schema`], with a clock the test can move.
+pub fn schema_with_clock(pool: &PgPool, clock: Arc<FixedClock>) -> RootSchema {
+    let config = Config {
+        secret: SECRET.to_string(),
+        ..Config::default()
+    };
+    let clock = ClubClock::new(clock, config.attendance.timezone);
+    root::build_graphql_schema(Arc::new(pool.clone()), &config, clock)
+}
+
+/// The state of Root's HTTP routes, backed by the test's own database, with the time stopped at
+/// [`now`].
+pub fn app_state(pool: &PgPool) -> AppState {
+    app_state_with_config(pool, Config::default())
+}
+
+/// Like [`app_state`], with `config` apart from the secret.
+pub fn app_state_with_config(pool: &PgPool, config: Config) -> AppState {
+    let config = Config {
+        secret: SECRET.to_string(),
+        ..config
+    };
+    let clock = ClubClock::new(Arc::new(FixedClock::new(now())), config.attendance.timezone);
+    let pool = Arc::new(pool.clone());
+    AppState {
+        schema: root::build_graphql_schema(pool.clone(), &config, clock.clone()),
+        pool,
+        daily_task: Arc::new(DailyTaskStatus::default()),
+        supervisor: Supervisor::default(),
+        rate_limiter: Arc::new(RateLimiter::new(&config.limits)),
+        clock,
+        secret: config.secret,
+        attendance: config.attendance,
+    }
+}
+
+/// The routes Root serves, as in production.
+pub fn router(state: AppState) -> Router {
+    setup_router(state, CorsLayer::new(), false)
+}
+
+/// Sends `request` through `router` and returns the response's status and body.
+pub async fn send(router: &Router, request: http::Request<Body>) -> (StatusCode, String) {
+    let response = router.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+/// A GET request to `uri`, with `key` as its bearer token if given.
+pub fn get(uri: &str, key: Option<&str>) -> http::Request<Body> {
+    let mut request = http::Request::get(uri);
+    if let Some(key) = key {
+        request = request.header(http::header::AUTHORIZATION, format!("Bearer {}", key));
+    }
+    request.body(Body::empty()).unwrap()
+}
+
+/// A GraphQL request for `query`, with `key` as its bearer token if given.
+pub fn post_query(query: &str, key: Option<&str>) -> http::Request<Body> {
+    let mut request =
+        http::Request::post("/").header(http::header::CONTENT_TYPE, "application/json");
+    if let Some(key) = key {
+        request = request.header(http::header::AUTHORIZATION, format!("Bearer {}", key));
+    }
+    let body = serde_json::json!({ "query": query }).to_string();
+    request.body(Body::from(body)).unwrap()
+}
+
+/// `request` made with an API key of the given role.
+pub fn as_caller(request: impl Into<Request>, role: Role) -> Request {
+    request.into().data(Caller {
+        api_key_id: 1,
+        role,
+        member_id: None,
+    })
+}
+
+/// `request` made with a member's own API key.
+pub fn as_member(request: impl Into<Request>, member_id: i32) -> Request {
+    request.into().data(Caller {
+        api_key_id: 1,
+        role: Role::Member,
+        member_id: Some(member_id),
+    })
+}
+
+/// Runs `request` and returns its data, failing the test if there are any errors.
+pub async fn execute(schema: &RootSchema, request: impl Into<Request>) -> Value {
+    let response = serde_json::to_value(schema.execute(request).await).unwrap();
+    if let Some(errors) = response.get("errors") {
+        panic!("Unexpected errors: {}", errors);
+    }
+    response["data"].clone()
+}
+
+/// Runs `request`, which must fail, and returns the `extensions` of its first error.
+pub async fn execute_err(schema: &RootSchema, request: impl Into<Request>) -> Value {
+    let response = serde_json::to_value(schema.execute(request).await).unwrap();
+    match response.get("errors") {
+        Some(errors) => errors[0]["extensions"].clone(),
+        None => panic!("Expected an error, got {}", response["data"]),
+    }
+}
+
+/// Signs an attendance mark the way Presense does.
+pub fn sign(member_id: i32, date: &str) -> String {
+    hmac(&format!("{}{}", member_id, date))
+}
+
+/// Signs a check-in at an event.
+pub fn sign_check_in(event_id: i32, member_id: i32, signed_at: DateTime<Utc>) -> String {
+    hmac(&format!(
+        "{}:{}:{}",
+        event_id,
+        member_id,
+        signed_at.timestamp()
+    ))
+}
+
+/// Signs a batch of scans sent to `/presence/scans`.
+pub fn sign_scans(body: &str) -> String {
+    hmac(body)
+}
+
+fn hmac(message: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(SECRET.as_bytes()).unwrap();
+    mac.update(message.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
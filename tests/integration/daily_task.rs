@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use chrono::{NaiveDate, NaiveTime, TimeZone};
+use chrono_tz::{America::New_York, Asia::Kolkata};
+use root::daily_task::{execute_daily_task, next_run};
+use sqlx::PgPool;
+
+async fn attendance_on(pool: &PgPool, date: NaiveDate) -> Vec<(i32, bool)> {
+    sqlx::query_as(
+        "SELECT member_id, is_present FROM Attendance WHERE date = $1 ORDER BY member_id",
+    )
+    .bind(date)
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+async fn days_attended(pool: &PgPool) -> Vec<(i32, i32)> {
+    sqlx::query_as(
+        "SELECT member_id, days_attended FROM AttendanceSummary
+         WHERE year = 2025 AND month = 1 ORDER BY member_id",
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn daily_task_adds_absences_for_active_members(pool: PgPool) {
+    let today = NaiveDate::from_ymd_opt(2025, 1, 11).unwrap();
+
+    assert!(execute_daily_task(Arc::new(pool.clone()), today).await);
+
+    assert_eq!(
+        attendance_on(&pool, today).await,
+        vec![(1, false), (2, false), (3, false)]
+    );
+}
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn daily_task_counts_yesterdays_attendance(pool: PgPool) {
+    let today = NaiveDate::from_ymd_opt(2025, 1, 11).unwrap();
+
+    execute_daily_task(Arc::new(pool.clone()), today).await;
+
+    // Rahul and Devika were present on the 10th; Devika had no summary for January yet.
+    assert_eq!(days_attended(&pool).await, vec![(1, 5), (2, 3), (3, 1)]);
+}
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn daily_task_leaves_existing_records_alone(pool: PgPool) {
+    let today = NaiveDate::from_ymd_opt(2025, 1, 10).unwrap();
+
+    assert!(execute_daily_task(Arc::new(pool.clone()), today).await);
+
+    assert_eq!(
+        attendance_on(&pool, today).await,
+        vec![(1, false), (2, true), (3, true)]
+    );
+}
+
+#[test]
+fn next_run_is_the_coming_run_time_in_the_club_timezone() {
+    let midnight = NaiveTime::MIN;
+
+    let evening = Kolkata.with_ymd_and_hms(2025, 1, 10, 21, 0, 0).unwrap();
+    assert_eq!(
+        next_run(evening, midnight),
+        Kolkata.with_ymd_and_hms(2025, 1, 11, 0, 0, 0).unwrap()
+    );
+
+    let at_midnight = Kolkata.with_ymd_and_hms(2025, 1, 11, 0, 0, 0).unwrap();
+    assert_eq!(
+        next_run(at_midnight, midnight),
+        Kolkata.with_ymd_and_hms(2025, 1, 12, 0, 0, 0).unwrap()
+    );
+}
+
+#[test]
+fn next_run_follows_daylight_saving_changes() {
+    let two_am = NaiveTime::from_hms_opt(2, 0, 0).unwrap();
+
+    // Clocks skip from 02:00 to 03:00 on 9 March 2025 in New York.
+    let before_skip = New_York.with_ymd_and_hms(2025, 3, 8, 12, 0, 0).unwrap();
+    assert_eq!(
+        next_run(before_skip, two_am),
+        New_York.with_ymd_and_hms(2025, 3, 9, 3, 0, 0).unwrap()
+    );
+
+    // The day after is a calendar day later, not 24 hours later.
+    let after_skip = New_York.with_ymd_and_hms(2025, 3, 9, 12, 0, 0).unwrap();
+    assert_eq!(
+        next_run(after_skip, two_am),
+        New_York.with_ymd_and_hms(2025, 3, 10, 2, 0, 0).unwrap()
+    );
+}
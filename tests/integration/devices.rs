@@ -0,0 +1,161 @@
+use async_graphql::Request;
+use root::auth::Role;
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::common::{as_caller, as_member, execute, execute_err, schema};
+
+fn add_device(member_id: i32, mac: &str, label: &str) -> String {
+    format!(
+        r#"mutation {{ addDevice(input: {{ memberId: {}, macAddress: "{}", label: "{}" }}) {{
+            deviceId macAddress isVerified
+        }} }}"#,
+        member_id, mac, label
+    )
+}
+
+fn member_by_mac(mac: &str) -> Request {
+    as_caller(
+        format!(r#"{{ memberByMac(mac: "{}") {{ name }} }}"#, mac),
+        Role::Admin,
+    )
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn devices_members_add_are_matched_once_verified(pool: PgPool) {
+    let schema = schema(&pool);
+
+    let data = execute(&schema, member_by_mac("aa-bb-cc-dd-ee-01")).await;
+    assert_eq!(data["memberByMac"], json!({ "name": "Asha Nair" }));
+    let error = execute_err(&schema, member_by_mac("not a mac")).await;
+    assert_eq!(error, json!({ "code": "VALIDATION", "field": "mac" }));
+    let anonymous = r#"{ memberByMac(mac: "AA:BB:CC:DD:EE:01") { name } }"#;
+    let error = execute_err(&schema, anonymous).await;
+    assert_eq!(error, json!({ "code": "UNAUTHENTICATED" }));
+    let error = execute_err(&schema, as_member(anonymous, 1)).await;
+    assert_eq!(error, json!({ "code": "FORBIDDEN" }));
+
+    let error = execute_err(
+        &schema,
+        as_member(add_device(2, "02:00:00:00:00:02", "Phone"), 1),
+    )
+    .await;
+    assert_eq!(error, json!({ "code": "FORBIDDEN" }));
+    let error = execute_err(
+        &schema,
+        as_member(add_device(2, "AA:BB:CC:DD:EE:01", "Phone"), 2),
+    )
+    .await;
+    assert_eq!(error, json!({ "code": "CONFLICT", "field": "macAddress" }));
+    let error = execute_err(
+        &schema,
+        as_member(add_device(2, "02:00:00:00:00:02", " "), 2),
+    )
+    .await;
+    assert_eq!(error, json!({ "code": "VALIDATION", "field": "label" }));
+
+    let data = execute(
+        &schema,
+        as_member(add_device(2, "02-00-00-00-00-02", "Phone"), 2),
+    )
+    .await;
+    let device_id = data["addDevice"]["deviceId"].as_i64().unwrap();
+    assert_eq!(data["addDevice"]["macAddress"], "02:00:00:00:00:02");
+    assert_eq!(data["addDevice"]["isVerified"], false);
+    let data = execute(&schema, member_by_mac("02:00:00:00:00:02")).await;
+    assert_eq!(data["memberByMac"], json!(null));
+
+    let verify = format!(
+        "mutation {{ verifyDevice(deviceId: {}) {{ isVerified }} }}",
+        device_id
+    );
+    let error = execute_err(&schema, as_member(verify.as_str(), 2)).await;
+    assert_eq!(error, json!({ "code": "FORBIDDEN" }));
+    execute(&schema, as_caller(verify, Role::Admin)).await;
+    let data = execute(&schema, member_by_mac("02:00:00:00:00:02")).await;
+    assert_eq!(data["memberByMac"], json!({ "name": "Rahul Menon" }));
+
+    let data = execute(
+        &schema,
+        as_caller(add_device(2, "02:00:00:00:00:12", "Laptop"), Role::Admin),
+    )
+    .await;
+    assert_eq!(data["addDevice"]["isVerified"], true);
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn primary_device_follows_the_members_mac_address(pool: PgPool) {
+    let schema = schema(&pool);
+    execute(
+        &schema,
+        as_member(add_device(2, "02:00:00:00:00:02", "Phone"), 2),
+    )
+    .await;
+
+    let remove = |device_id: i32| {
+        as_member(
+            format!(
+                "mutation {{ removeDevice(memberId: 2, deviceId: {}) {{ label }} }}",
+                device_id
+            ),
+            2,
+        )
+    };
+    let error = execute_err(&schema, remove(2)).await;
+    assert_eq!(error, json!({ "code": "VALIDATION", "field": "deviceId" }));
+    let error = execute_err(&schema, remove(1)).await;
+    assert_eq!(error, json!({ "code": "NOT_FOUND" }));
+
+    execute(
+        &schema,
+        as_member(
+            r#"mutation { updateMyProfile(input: { macAddress: "AA:BB:CC:DD:EE:22" }) {
+                pendingChange { changeId }
+            } }"#,
+            2,
+        ),
+    )
+    .await;
+    execute(
+        &schema,
+        as_caller(
+            "mutation { reviewProfileChange(changeId: 1, approve: true) { status } }",
+            Role::Admin,
+        ),
+    )
+    .await;
+
+    let data = execute(
+        &schema,
+        as_member("{ me { devices { label macAddress isPrimary } } }", 2),
+    )
+    .await;
+    assert_eq!(
+        data["me"]["devices"],
+        json!([
+            { "label": "Primary", "macAddress": "AA:BB:CC:DD:EE:22", "isPrimary": true },
+            { "label": "Phone", "macAddress": "02:00:00:00:00:02", "isPrimary": false },
+        ])
+    );
+
+    let data = execute(&schema, remove(5)).await;
+    assert_eq!(data["removeDevice"], json!({ "label": "Phone" }));
+    let data = execute(&schema, member_by_mac("02:00:00:00:00:02")).await;
+    assert_eq!(data["memberByMac"], json!(null));
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn only_members_and_admins_see_their_devices(pool: PgPool) {
+    let schema = schema(&pool);
+    let devices = "{ members(groupId: 2) { devices { lastSeenAt } } }";
+    let pending = "{ members(groupId: 2) { pendingProfileChange { macAddress } } }";
+
+    for query in [devices, pending] {
+        let error = execute_err(&schema, query).await;
+        assert_eq!(error, json!({ "code": "UNAUTHENTICATED" }));
+        let error = execute_err(&schema, as_member(query, 1)).await;
+        assert_eq!(error, json!({ "code": "FORBIDDEN" }));
+        execute(&schema, as_member(query, 3)).await;
+        execute(&schema, as_caller(query, Role::Admin)).await;
+    }
+}
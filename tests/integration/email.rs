@@ -0,0 +1,271 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{NaiveDate, TimeDelta};
+use chrono_tz::Asia::Kolkata;
+use root::auth::Role;
+use root::clock::{ClubClock, FixedClock};
+use root::config::{EmailConfig, SmtpTls};
+use root::daily_task::execute_daily_task;
+use root::email::{EmailDispatcher, EmailTemplates, Mailer, MockMailer, OutgoingEmail, SmtpMailer};
+use serde_json::json;
+use sqlx::PgPool;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+use crate::common::{as_caller, as_member, execute, execute_err, now, schema};
+
+fn dispatcher(pool: &PgPool, clock: Arc<FixedClock>, mailer: Arc<MockMailer>) -> EmailDispatcher {
+    let config = EmailConfig {
+        max_attempts: 2,
+        initial_backoff_secs: 60,
+        ..EmailConfig::default()
+    };
+    EmailDispatcher::new(
+        Arc::new(pool.clone()),
+        ClubClock::new(clock, Kolkata),
+        mailer,
+        EmailTemplates::default(),
+        &config,
+    )
+}
+
+fn date(day: &str) -> NaiveDate {
+    day.parse().unwrap()
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn new_members_are_welcomed(pool: PgPool) {
+    let schema = schema(&pool);
+    execute(
+        &schema,
+        r#"mutation { createMember(input: {
+            rollNo: "AM.EN.U4CSE24099", name: "Nila", email: "nila@example.com", sex: F,
+            year: 1, hostel: "Kaveri", macAddress: "AA:BB:CC:DD:EE:99",
+            discordId: "100000000000000099", groupId: 2
+        }) { memberId } }"#,
+    )
+    .await;
+
+    let mailer = Arc::new(MockMailer::default());
+    let dispatcher = dispatcher(&pool, Arc::new(FixedClock::new(now())), mailer.clone());
+    assert_eq!(dispatcher.send_due().await.unwrap(), 1);
+    assert_eq!(dispatcher.send_due().await.unwrap(), 0);
+
+    let sent = mailer.sent();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].to, "nila@example.com");
+    assert_eq!(sent[0].subject, "Welcome to amFOSS, Nila!");
+    assert!(sent[0].text.starts_with("Hi Nila,"));
+    assert!(sent[0]
+        .html
+        .contains("part of <strong>Systems</strong>, registered as AM.EN.U4CSE24099"));
+}
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn reports_go_out_on_mondays_and_the_first_of_the_month(pool: PgPool) {
+    let schema = schema(&pool);
+    execute(
+        &schema,
+        as_member(
+            "mutation { setEmailOptOut(memberId: 2, optOut: true) { emailOptOut } }",
+            2,
+        ),
+    )
+    .await;
+
+    let mailer = Arc::new(MockMailer::default());
+    let dispatcher = dispatcher(&pool, Arc::new(FixedClock::new(now())), mailer.clone());
+
+    // Sunday, then Monday.
+    execute_daily_task(Arc::new(pool.clone()), date("2025-01-12")).await;
+    assert_eq!(dispatcher.send_due().await.unwrap(), 0);
+    execute_daily_task(Arc::new(pool.clone()), date("2025-01-13")).await;
+    execute_daily_task(Arc::new(pool.clone()), date("2025-01-13")).await;
+    assert_eq!(dispatcher.send_due().await.unwrap(), 2);
+
+    let sent = mailer.sent();
+    let recipients: Vec<&str> = sent.iter().map(|email| email.to.as_str()).collect();
+    assert_eq!(recipients, ["asha@example.com", "devika@example.com"]);
+    assert_eq!(
+        sent[0].subject,
+        "Your attendance for the week of 2025-01-06"
+    );
+    assert!(sent[0].text.contains(
+        "From 2025-01-06 to 2025-01-12 you were in the lab on 1 of 3 days.\n\
+         Your status update streak is at 3."
+    ));
+
+    execute_daily_task(Arc::new(pool.clone()), date("2025-02-01")).await;
+    assert_eq!(dispatcher.send_due().await.unwrap(), 1);
+    let summary = mailer.sent().pop().unwrap();
+    assert_eq!(summary.to, "asha@example.com");
+    assert_eq!(summary.subject, "Web attendance for January 2025");
+    assert!(summary
+        .text
+        .contains("- Asha Nair, Web, 5\n- Rahul Menon, Web, 2"));
+    assert!(summary
+        .html
+        .contains("<tr><td>Asha Nair</td><td>Web</td><td>5</td></tr>"));
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn failed_sends_are_retried_then_given_up_on(pool: PgPool) {
+    sqlx::query("INSERT INTO EmailOutbox (kind, member_id) VALUES ('welcome', 1)")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let clock = Arc::new(FixedClock::new(now()));
+    let mailer = Arc::new(MockMailer::default());
+    mailer.fail_next(2);
+    let dispatcher = dispatcher(&pool, clock.clone(), mailer.clone());
+
+    assert_eq!(dispatcher.send_due().await.unwrap(), 1);
+    assert_eq!(dispatcher.send_due().await.unwrap(), 0);
+    clock.advance(TimeDelta::seconds(60));
+    assert_eq!(dispatcher.send_due().await.unwrap(), 1);
+
+    let (status, attempts, last_error): (String, i32, Option<String>) = sqlx::query_as(
+        "SELECT status::TEXT, attempts, last_error FROM EmailOutbox WHERE member_id = 1",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        (status.as_str(), attempts, last_error.as_deref()),
+        ("failed", 2, Some("Mock failure"))
+    );
+    assert!(mailer.sent().is_empty());
+}
+
+/// Takes its time over each send, like a slow SMTP server.
+#[derive(Default)]
+struct SlowMailer(MockMailer);
+
+#[async_trait::async_trait]
+impl Mailer for SlowMailer {
+    async fn send(&self, email: &OutgoingEmail) -> Result<(), String> {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        self.0.send(email).await
+    }
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn emails_are_not_locked_while_they_are_sent(pool: PgPool) {
+    sqlx::query("INSERT INTO EmailOutbox (kind, member_id) VALUES ('welcome', 1)")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let clock = Arc::new(FixedClock::new(now()));
+    let slow = EmailDispatcher::new(
+        Arc::new(pool.clone()),
+        ClubClock::new(clock.clone(), Kolkata),
+        Arc::new(SlowMailer::default()),
+        EmailTemplates::default(),
+        &EmailConfig::default(),
+    );
+    let other = dispatcher(&pool, clock, Arc::new(MockMailer::default()));
+
+    let while_sending = async {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        // Another instance finds nothing to claim, without waiting on a lock.
+        assert_eq!(other.send_due().await.unwrap(), 0);
+        sqlx::query("SELECT 1 FROM EmailOutbox FOR UPDATE NOWAIT")
+            .execute(&pool)
+            .await
+            .unwrap();
+    };
+    let (sent, ()) = tokio::join!(slow.send_due(), while_sending);
+
+    assert_eq!(sent.unwrap(), 1);
+    let status: String = sqlx::query_scalar("SELECT status::TEXT FROM EmailOutbox")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(status, "sent");
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn only_the_member_or_an_admin_can_opt_out(pool: PgPool) {
+    let schema = schema(&pool);
+    let opt_out = "mutation { setEmailOptOut(memberId: 1, optOut: true) { emailOptOut } }";
+
+    let error = execute_err(&schema, opt_out).await;
+    assert_eq!(error, json!({ "code": "UNAUTHENTICATED" }));
+    let error = execute_err(&schema, as_member(opt_out, 2)).await;
+    assert_eq!(error, json!({ "code": "FORBIDDEN" }));
+
+    let data = execute(&schema, as_caller(opt_out, Role::Admin)).await;
+    assert_eq!(data["setEmailOptOut"], json!({ "emailOptOut": true }));
+}
+
+/// Accepts one SMTP session and returns the message it was sent.
+async fn receive_one(listener: TcpListener) -> String {
+    let (stream, _) = listener.accept().await.unwrap();
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+    let mut message = String::new();
+    let mut in_data = false;
+
+    write.write_all(b"220 localhost ready\r\n").await.unwrap();
+    while let Some(line) = lines.next_line().await.unwrap() {
+        let reply: &[u8] = if in_data {
+            if line == "." {
+                in_data = false;
+                b"250 Queued\r\n"
+            } else {
+                message.push_str(&line);
+                message.push('\n');
+                continue;
+            }
+        } else if line.starts_with("DATA") {
+            in_data = true;
+            b"354 Go ahead\r\n"
+        } else if line.starts_with("QUIT") {
+            write.write_all(b"221 Bye\r\n").await.unwrap();
+            break;
+        } else {
+            b"250 OK\r\n"
+        };
+        write.write_all(reply).await.unwrap();
+    }
+    message
+}
+
+#[tokio::test]
+async fn smtp_mailer_sends_plaintext_and_html_alternatives() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = tokio::spawn(receive_one(listener));
+
+    let mut config = EmailConfig::default();
+    config.smtp.host = "127.0.0.1".to_string();
+    config.smtp.port = port;
+    config.smtp.tls = SmtpTls::None;
+    let mailer = SmtpMailer::new(&config).unwrap();
+    mailer
+        .send(&OutgoingEmail {
+            to_name: "Asha Nair".to_string(),
+            to: "asha@example.com".to_string(),
+            subject: "Hello".to_string(),
+            text: "Plain".to_string(),
+            html: "<p>Rich</p>".to_string(),
+        })
+        .await
+        .unwrap();
+
+    let message = server.await.unwrap();
+    assert!(
+        message.contains("From: amFOSS <root@amfoss.in>"),
+        "{}",
+        message
+    );
+    assert!(
+        message.contains("To: \"Asha Nair\" <asha@example.com>"),
+        "{}",
+        message
+    );
+    assert!(message.contains("Subject: Hello"), "{}", message);
+    assert!(message.contains("multipart/alternative"), "{}", message);
+    assert!(message.contains("<p>Rich</p>"), "{}", message);
+}
@@ -0,0 +1,194 @@
+use async_graphql::Request;
+use chrono::{DateTime, TimeDelta, Utc};
+use root::auth::Role;
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::common::{
+    as_caller, as_member, execute, execute_err, now, schema, sign_check_in, RootSchema,
+};
+
+/// Creates an event from `fields` as an admin and returns its id.
+async fn create_event(schema: &RootSchema, fields: &str) -> i64 {
+    let data = execute(
+        schema,
+        as_caller(
+            format!(
+                "mutation {{ createEvent(input: {{ {} }}) {{ eventId }} }}",
+                fields
+            ),
+            Role::Admin,
+        ),
+    )
+    .await;
+    data["createEvent"]["eventId"].as_i64().unwrap()
+}
+
+fn rsvp(event_id: i64, member_id: i32, status: &str) -> Request {
+    as_member(
+        format!(
+            "mutation {{ rsvpEvent(input: {{ eventId: {}, memberId: {}, status: {} }}) {{ status }} }}",
+            event_id, member_id, status
+        ),
+        member_id,
+    )
+}
+
+/// A check-in for `member_id`, signed at `signed_at` over `signer`'s id.
+fn check_in(event_id: i64, member_id: i32, signer: i32, signed_at: DateTime<Utc>) -> String {
+    format!(
+        r#"mutation {{ checkInEvent(input: {{
+            eventId: {}, memberId: {}, signedAt: "{}", hmacSignature: "{}"
+        }}) {{ checkedInAt }} }}"#,
+        event_id,
+        member_id,
+        signed_at.to_rfc3339(),
+        sign_check_in(event_id as i32, signer, signed_at)
+    )
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn admins_create_events_that_are_listed_by_time(pool: PgPool) {
+    let schema = schema(&pool);
+    let create = r#"mutation { createEvent(input: {
+        title: "Rust workshop", kind: WORKSHOP, venue: "Lab 1", organizerId: 1,
+        startsAt: "2025-01-10T10:30:00Z", endsAt: "2025-01-10T09:30:00Z"
+    }) { eventId } }"#;
+
+    let error = execute_err(&schema, as_member(create, 1)).await;
+    assert_eq!(error, json!({ "code": "FORBIDDEN" }));
+    let error = execute_err(&schema, as_caller(create, Role::Admin)).await;
+    assert_eq!(error, json!({ "code": "VALIDATION", "field": "endsAt" }));
+
+    create_event(
+        &schema,
+        r#"title: "Rust workshop", kind: WORKSHOP, venue: "Lab 1", organizerId: 1,
+           startsAt: "2025-01-10T10:30:00Z", endsAt: "2025-01-10T12:30:00Z""#,
+    )
+    .await;
+    create_event(
+        &schema,
+        r#"title: "Weekly meeting", kind: MEETING,
+           startsAt: "2025-01-17T10:30:00Z", endsAt: "2025-01-17T11:30:00Z""#,
+    )
+    .await;
+
+    let data = execute(
+        &schema,
+        r#"{ events(from: "2025-01-10T11:00:00Z", to: "2025-01-11T00:00:00Z") {
+            title kind venue startsAt organizer { name }
+        } }"#,
+    )
+    .await;
+    assert_eq!(
+        data["events"],
+        json!([{
+            "title": "Rust workshop",
+            "kind": "WORKSHOP",
+            "venue": "Lab 1",
+            "startsAt": "2025-01-10T10:30:00+00:00",
+            "organizer": { "name": "Asha Nair" },
+        }])
+    );
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn members_rsvp_to_events_they_are_invited_to(pool: PgPool) {
+    let schema = schema(&pool);
+    let event_id = create_event(
+        &schema,
+        r#"title: "Talk for seniors", audienceYears: [2, 3],
+           startsAt: "2025-01-11T10:30:00Z", endsAt: "2025-01-11T11:30:00Z""#,
+    )
+    .await;
+
+    execute(&schema, rsvp(event_id, 1, "GOING")).await;
+    execute(&schema, rsvp(event_id, 2, "GOING")).await;
+    let data = execute(&schema, rsvp(event_id, 2, "MAYBE")).await;
+    assert_eq!(data["rsvpEvent"], json!({ "status": "MAYBE" }));
+
+    let error = execute_err(&schema, rsvp(event_id, 3, "GOING")).await;
+    assert_eq!(error, json!({ "code": "VALIDATION", "field": "memberId" }));
+    let error = execute_err(
+        &schema,
+        as_member(
+            format!(
+                "mutation {{ rsvpEvent(input: {{ eventId: {}, memberId: 2, status: NOT_GOING }}) {{ status }} }}",
+                event_id
+            ),
+            1,
+        ),
+    )
+    .await;
+    assert_eq!(error, json!({ "code": "FORBIDDEN" }));
+
+    let data = execute(
+        &schema,
+        format!(
+            "{{ event(eventId: {}) {{
+                attendanceCounts {{ going maybe notGoing checkedIn }}
+            }} }}",
+            event_id
+        ),
+    )
+    .await;
+    assert_eq!(
+        data["event"]["attendanceCounts"],
+        json!({ "going": 1, "maybe": 1, "notGoing": 0, "checkedIn": 0 })
+    );
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn check_ins_are_signed_and_only_open_around_the_event(pool: PgPool) {
+    let schema = schema(&pool);
+    // The test clock reads 04:00 UTC.
+    let open = create_event(
+        &schema,
+        r#"title: "Meeting", startsAt: "2025-01-10T04:30:00Z", endsAt: "2025-01-10T05:30:00Z""#,
+    )
+    .await;
+    let later = create_event(
+        &schema,
+        r#"title: "Later", startsAt: "2025-01-10T06:00:00Z", endsAt: "2025-01-10T07:00:00Z""#,
+    )
+    .await;
+    let web_only = create_event(
+        &schema,
+        r#"title: "Web sync", audienceGroupIds: [1],
+            startsAt: "2025-01-10T04:30:00Z", endsAt: "2025-01-10T05:30:00Z""#,
+    )
+    .await;
+
+    let error = execute_err(&schema, check_in(open, 3, 2, now())).await;
+    assert_eq!(error, json!({ "code": "UNAUTHENTICATED" }));
+    let error = execute_err(&schema, check_in(later, 3, 3, now())).await;
+    assert_eq!(error, json!({ "code": "VALIDATION", "field": "eventId" }));
+    // A signature captured earlier can't be replayed.
+    let stale = now() - TimeDelta::minutes(10);
+    let error = execute_err(&schema, check_in(open, 3, 3, stale)).await;
+    assert_eq!(error, json!({ "code": "VALIDATION", "field": "signedAt" }));
+    let error = execute_err(&schema, check_in(web_only, 3, 3, now())).await;
+    assert_eq!(error, json!({ "code": "VALIDATION", "field": "memberId" }));
+
+    let data = execute(&schema, check_in(open, 3, 3, now())).await;
+    assert_eq!(
+        data["checkInEvent"],
+        json!({ "checkedInAt": "2025-01-10T04:00:00+00:00" })
+    );
+
+    let data = execute(
+        &schema,
+        "{ members(groupId: 2) {
+            events { event { title attendanceCounts { checkedIn } } rsvp checkedInAt }
+        } }",
+    )
+    .await;
+    assert_eq!(
+        data["members"][0]["events"],
+        json!([{
+            "event": { "title": "Meeting", "attendanceCounts": { "checkedIn": 1 } },
+            "rsvp": null,
+            "checkedInAt": "2025-01-10T04:00:00+00:00",
+        }])
+    );
+}
@@ -0,0 +1,93 @@
+use axum::http::StatusCode;
+use root::auth::{create_api_key, Role};
+use sqlx::PgPool;
+
+use crate::common::{app_state, get, router, send};
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn admins_export_everyone(pool: PgPool) {
+    let router = router(app_state(&pool));
+    let key = create_api_key(&pool, "admin", Role::Admin, None)
+        .await
+        .unwrap();
+
+    let (status, body) = send(&router, get("/export/members", None)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", body);
+
+    let (status, body) = send(&router, get("/export/members", Some(&key))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.lines().count(), 4, "{}", body);
+
+    let (status, body) = send(&router, get("/export/streaks", Some(&key))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.lines().count(), 3, "{}", body);
+}
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn member_keys_only_export_their_own_records(pool: PgPool) {
+    let router = router(app_state(&pool));
+    let key = create_api_key(&pool, "rahul", Role::Member, Some(2))
+        .await
+        .unwrap();
+    let unlinked = create_api_key(&pool, "mentors", Role::Member, None)
+        .await
+        .unwrap();
+
+    let (status, body) = send(&router, get("/export/members", Some(&key))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+    assert!(body.contains("\"FORBIDDEN\""));
+
+    let (status, body) = send(&router, get("/export/attendance?format=json", Some(&key))).await;
+    assert_eq!(status, StatusCode::OK);
+    let rows: Vec<serde_json::Value> = serde_json::from_str(&body).unwrap();
+    assert_eq!(rows.len(), 2);
+    assert!(rows.iter().all(|row| row["member_id"] == 2), "{}", body);
+
+    let (status, body) = send(&router, get("/export/streaks?memberId=2", Some(&key))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.lines().count(), 2, "{}", body);
+
+    for uri in [
+        "/export/attendance?memberId=1",
+        "/export/attendance-summary?memberId=1",
+        "/export/streaks?memberId=1",
+    ] {
+        let (status, body) = send(&router, get(uri, Some(&key))).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{}: {}", uri, body);
+    }
+    let (status, body) = send(&router, get("/export/streaks", Some(&unlinked))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+}
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn empty_exports_still_have_a_header(pool: PgPool) {
+    let router = router(app_state(&pool));
+    let key = create_api_key(&pool, "admin", Role::Admin, None)
+        .await
+        .unwrap();
+
+    for (route, nothing) in [
+        ("/export/members", "year=9"),
+        ("/export/attendance", "startDate=2030-01-01"),
+        ("/export/attendance-summary", "year=1990"),
+        ("/export/streaks", "memberId=4"),
+    ] {
+        let (status, everything) = send(&router, get(route, Some(&key))).await;
+        assert_eq!(status, StatusCode::OK);
+        let header = everything.lines().next().unwrap();
+        let columns = header.split(',').count();
+        assert!(
+            everything
+                .lines()
+                .skip(1)
+                .all(|row| row.split(',').count() == columns),
+            "{}",
+            everything
+        );
+
+        let uri = format!("{}?{}", route, nothing);
+        let (status, body) = send(&router, get(&uri, Some(&key))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, format!("{}\n", header), "{}", uri);
+    }
+}
@@ -0,0 +1,18 @@
+-- Attendance, summaries, streaks and projects for the members fixture.
+INSERT INTO Attendance (member_id, date, is_present, time_in, time_out) VALUES
+        (1, '2025-01-09', TRUE, '09:00', '17:00'),
+        (1, '2025-01-10', FALSE, NULL, NULL),
+        (2, '2025-01-09', FALSE, NULL, NULL),
+        (2, '2025-01-10', TRUE, '10:30', '16:00'),
+        (3, '2025-01-10', TRUE, '09:15', '18:00'),
+        (4, '2024-05-02', TRUE, '09:00', '12:00');
+
+INSERT INTO AttendanceSummary (member_id, year, month, days_attended) VALUES
+        (1, 2025, 1, 5),
+        (2, 2025, 1, 2);
+
+INSERT INTO StatusUpdateStreak (member_id, current_streak, max_streak) VALUES
+        (1, 3, 5),
+        (2, -2, 4);
+
+INSERT INTO Project (member_id, title) VALUES (1, 'Root');
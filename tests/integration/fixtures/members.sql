@@ -0,0 +1,21 @@
+-- Two groups, three active members and an alumnus. Asha mentors Web.
+INSERT INTO MemberGroup (group_id, name, description) VALUES
+        (1, 'Web', 'Home and amD'),
+        (2, 'Systems', NULL);
+SELECT setval(pg_get_serial_sequence('membergroup', 'group_id'), 2);
+
+INSERT INTO Member
+        (member_id, roll_no, name, email, sex, year, hostel, mac_address, discord_id, group_id,
+         status, graduation_year)
+VALUES
+        (1, 'AM.EN.U4CSE22001', 'Asha Nair', 'asha@example.com', 'F', 3, 'Ganga',
+         'AA:BB:CC:DD:EE:01', '100000000000000001', 1, 'active', NULL),
+        (2, 'AM.EN.U4CSE23002', 'Rahul Menon', 'rahul@example.com', 'M', 2, 'Yamuna',
+         'AA:BB:CC:DD:EE:02', '100000000000000002', 1, 'active', NULL),
+        (3, 'AM.EN.U4ECE24003', 'Devika Pillai', 'devika@example.com', 'F', 1, 'Ganga',
+         'AA:BB:CC:DD:EE:03', '100000000000000003', 2, 'active', NULL),
+        (4, 'AM.EN.U4CSE20004', 'Arjun Das', 'arjun@example.com', 'M', 4, 'Yamuna',
+         'AA:BB:CC:DD:EE:04', '100000000000000004', 2, 'alumni', 2024);
+SELECT setval(pg_get_serial_sequence('member', 'member_id'), 4);
+
+INSERT INTO GroupMentor (group_id, member_id) VALUES (1, 1);
@@ -0,0 +1,203 @@
+use root::auth::Role;
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::common::{as_caller, as_member, execute, execute_err, schema};
+
+#[sqlx::test(fixtures("members"))]
+async fn groups_list_their_members_and_mentors(pool: PgPool) {
+    let schema = schema(&pool);
+
+    let data = execute(&schema, "{ groups { groupId name } }").await;
+    assert_eq!(
+        data["groups"],
+        json!([{ "groupId": 1, "name": "Web" }, { "groupId": 2, "name": "Systems" }])
+    );
+
+    let data = execute(
+        &schema,
+        "{ group(groupId: 2) {
+            members { memberId }
+            everyone: members(includeAlumni: true) { memberId }
+            mentors { memberId }
+        } }",
+    )
+    .await;
+    assert_eq!(
+        data["group"],
+        json!({
+            "members": [{ "memberId": 3 }],
+            "everyone": [{ "memberId": 3 }, { "memberId": 4 }],
+            "mentors": [],
+        })
+    );
+
+    let data = execute(&schema, "{ group(groupId: 1) { mentors { name } } }").await;
+    assert_eq!(data["group"]["mentors"], json!([{ "name": "Asha Nair" }]));
+
+    let error = execute_err(&schema, "{ group(groupId: 9) { name } }").await;
+    assert_eq!(error, json!({ "code": "NOT_FOUND" }));
+}
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn attendance_stats_count_active_members_in_range(pool: PgPool) {
+    let schema = schema(&pool);
+
+    let data = execute(
+        &schema,
+        r#"{ group(groupId: 1) {
+            all: attendanceStats { memberCount daysPresent daysRecorded }
+            day: attendanceStats(startDate: "2025-01-10", endDate: "2025-01-10") {
+                memberCount daysPresent daysRecorded
+            }
+        } }"#,
+    )
+    .await;
+    assert_eq!(
+        data["group"],
+        json!({
+            "all": { "memberCount": 2, "daysPresent": 2, "daysRecorded": 4 },
+            "day": { "memberCount": 2, "daysPresent": 1, "daysRecorded": 2 },
+        })
+    );
+
+    // The alumnus's attendance doesn't count towards their old group.
+    let data = execute(
+        &schema,
+        "{ group(groupId: 2) { attendanceStats { memberCount daysPresent daysRecorded } } }",
+    )
+    .await;
+    assert_eq!(
+        data["group"]["attendanceStats"],
+        json!({ "memberCount": 1, "daysPresent": 1, "daysRecorded": 1 })
+    );
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn create_group_with_mentors_and_rename_it(pool: PgPool) {
+    let schema = schema(&pool);
+
+    let data = execute(
+        &schema,
+        as_caller(
+            r#"mutation { createGroup(input: { name: "Design", mentorIds: [2, 3] }) {
+            groupId mentors { memberId }
+        } }"#,
+            Role::Admin,
+        ),
+    )
+    .await;
+    assert_eq!(
+        data["createGroup"],
+        json!({ "groupId": 3, "mentors": [{ "memberId": 2 }, { "memberId": 3 }] })
+    );
+
+    let error = execute_err(
+        &schema,
+        as_caller(
+            r#"mutation { createGroup(input: { name: "Web" }) { groupId } }"#,
+            Role::Admin,
+        ),
+    )
+    .await;
+    assert_eq!(error, json!({ "code": "CONFLICT", "field": "name" }));
+
+    let data = execute(
+        &schema,
+        as_caller(
+            r#"mutation { renameGroup(input: { groupId: 3, name: "UI/UX" }) { name } }"#,
+            Role::Admin,
+        ),
+    )
+    .await;
+    assert_eq!(data["renameGroup"], json!({ "name": "UI/UX" }));
+
+    let error = execute_err(
+        &schema,
+        as_caller(
+            r#"mutation { renameGroup(input: { groupId: 9, name: "Nowhere" }) { name } }"#,
+            Role::Admin,
+        ),
+    )
+    .await;
+    assert_eq!(error, json!({ "code": "NOT_FOUND" }));
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn move_members_and_replace_mentors(pool: PgPool) {
+    let schema = schema(&pool);
+
+    let data = execute(
+        &schema,
+        as_caller(
+            "mutation { moveMembers(input: { memberIds: [1, 2], groupId: 2 }) { memberId } }",
+            Role::Admin,
+        ),
+    )
+    .await;
+    assert_eq!(
+        data["moveMembers"],
+        json!([{ "memberId": 1 }, { "memberId": 2 }])
+    );
+    let data = execute(&schema, "{ group(groupId: 2) { members { memberId } } }").await;
+    let mut member_ids: Vec<_> = data["group"]["members"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|member| member["memberId"].as_i64().unwrap())
+        .collect();
+    member_ids.sort();
+    assert_eq!(member_ids, [1, 2, 3]);
+
+    let error = execute_err(
+        &schema,
+        as_caller(
+            "mutation { moveMembers(input: { memberIds: [3], groupId: 9 }) { memberId } }",
+            Role::Admin,
+        ),
+    )
+    .await;
+    assert_eq!(error, json!({ "code": "VALIDATION", "field": "groupId" }));
+
+    let data = execute(
+        &schema,
+        as_caller(
+            "mutation { setGroupMentors(input: { groupId: 1, mentorIds: [2] }) {
+            mentors { memberId }
+        } }",
+            Role::Admin,
+        ),
+    )
+    .await;
+    assert_eq!(
+        data["setGroupMentors"]["mentors"],
+        json!([{ "memberId": 2 }])
+    );
+
+    let error = execute_err(
+        &schema,
+        as_caller(
+            "mutation { setGroupMentors(input: { groupId: 9, mentorIds: [] }) { groupId } }",
+            Role::Admin,
+        ),
+    )
+    .await;
+    assert_eq!(error, json!({ "code": "NOT_FOUND" }));
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn only_admins_change_groups(pool: PgPool) {
+    let schema = schema(&pool);
+
+    for mutation in [
+        r#"mutation { createGroup(input: { name: "Design" }) { groupId } }"#,
+        r#"mutation { renameGroup(input: { groupId: 1, name: "UI/UX" }) { name } }"#,
+        "mutation { moveMembers(input: { memberIds: [1], groupId: 2 }) { memberId } }",
+        "mutation { setGroupMentors(input: { groupId: 1, mentorIds: [2] }) { groupId } }",
+    ] {
+        let error = execute_err(&schema, mutation).await;
+        assert_eq!(error, json!({ "code": "UNAUTHENTICATED" }));
+        let error = execute_err(&schema, as_member(mutation, 1)).await;
+        assert_eq!(error, json!({ "code": "FORBIDDEN" }));
+    }
+}
@@ -0,0 +1,79 @@
+use axum::http::{header::RETRY_AFTER, StatusCode};
+use root::config::{Config, LimitsConfig};
+use serde_json::json;
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+use crate::common::{app_state_with_config, execute, execute_err, post_query, router, send};
+
+fn limits(limits: LimitsConfig) -> Config {
+    Config {
+        limits,
+        ..Config::default()
+    }
+}
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn deep_or_expensive_queries_are_rejected(pool: PgPool) {
+    let config = limits(LimitsConfig {
+        max_depth: 2,
+        max_complexity: 3,
+        ..LimitsConfig::default()
+    });
+    let schema = app_state_with_config(&pool, config).schema;
+
+    execute(&schema, "{ members { memberId } }").await;
+
+    let error = execute_err(&schema, "{ members { projects { title } } }").await;
+    assert_eq!(error["code"], json!("LIMIT_EXCEEDED"));
+    assert_eq!(error["limit"], json!("depth"));
+
+    let error = execute_err(&schema, "{ members { memberId name email } }").await;
+    assert_eq!(error["code"], json!("LIMIT_EXCEEDED"));
+    assert_eq!(error["limit"], json!("complexity"));
+}
+
+#[sqlx::test]
+async fn anonymous_clients_are_limited_per_address(pool: PgPool) {
+    let config = limits(LimitsConfig {
+        anonymous_requests_per_minute: 2,
+        ..LimitsConfig::default()
+    });
+    let router = router(app_state_with_config(&pool, config));
+
+    for _ in 0..2 {
+        let (status, body) = send(&router, post_query("{ __typename }", None)).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+    }
+
+    let response = router
+        .clone()
+        .oneshot(post_query("{ __typename }", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key(RETRY_AFTER));
+}
+
+#[sqlx::test]
+async fn invalid_keys_are_limited_before_they_are_looked_up(pool: PgPool) {
+    let config = limits(LimitsConfig {
+        authenticated_requests_per_minute: 2,
+        ..LimitsConfig::default()
+    });
+    let router = router(app_state_with_config(&pool, config));
+
+    for guess in ["guess-1", "guess-2"] {
+        let (status, body) = send(&router, post_query("{ __typename }", Some(guess))).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", body);
+    }
+
+    // Closing the pool would fail the lookup, so this shows the key isn't looked up at all.
+    pool.close().await;
+    let (status, body) = send(&router, post_query("{ __typename }", Some("guess-3"))).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS, "{}", body);
+
+    // Requests without a key have a budget of their own.
+    let (status, body) = send(&router, post_query("{ __typename }", None)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+}
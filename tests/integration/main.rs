@@ -0,0 +1,23 @@
+//! End-to-end tests of the GraphQL API and the daily task. Each test gets a fresh database with
+//! every migration applied, created from `DATABASE_URL` and dropped afterwards.
+
+mod common;
+
+mod attendance;
+mod calendar;
+mod daily_task;
+mod devices;
+mod email;
+mod events;
+mod exports;
+mod groups;
+mod limits;
+mod members;
+mod metrics;
+mod notifier;
+mod presence;
+mod projects;
+mod streaks;
+mod supervisor;
+mod telemetry;
+mod webhooks;
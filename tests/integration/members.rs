@@ -0,0 +1,364 @@
+use std::io::{Seek, Write};
+
+use async_graphql::{Request, UploadValue, Variables};
+use chrono::NaiveDateTime;
+use root::auth::Role;
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::common::{as_caller, as_member, execute, execute_err, schema};
+
+#[sqlx::test(fixtures("members"))]
+async fn members_excludes_alumni_unless_asked(pool: PgPool) {
+    let schema = schema(&pool);
+
+    let data = execute(&schema, "{ members { memberId } }").await;
+    assert_eq!(
+        data["members"],
+        json!([{ "memberId": 1 }, { "memberId": 2 }, { "memberId": 3 }])
+    );
+
+    let data = execute(
+        &schema,
+        "{ members(includeAlumni: true) { memberId status } }",
+    )
+    .await;
+    assert_eq!(data["members"].as_array().unwrap().len(), 4);
+    assert_eq!(data["members"][3]["status"], "ALUMNI");
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn members_filters_by_year_and_group(pool: PgPool) {
+    let schema = schema(&pool);
+
+    let data = execute(&schema, "{ members(year: 2) { name } }").await;
+    assert_eq!(data["members"], json!([{ "name": "Rahul Menon" }]));
+
+    let data = execute(&schema, "{ members(groupId: 2) { name } }").await;
+    assert_eq!(data["members"], json!([{ "name": "Devika Pillai" }]));
+}
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn member_resolves_related_records(pool: PgPool) {
+    let schema = schema(&pool);
+
+    let data = execute(
+        &schema,
+        "{ members(year: 3) {
+            group { name }
+            attendance { date isPresent timeIn timeOut }
+            attendanceSummary { year month daysAttended }
+            streak { currentStreak maxStreak }
+            projects { title }
+        } }",
+    )
+    .await;
+
+    assert_eq!(
+        data["members"][0],
+        json!({
+            "group": { "name": "Web" },
+            "attendance": [
+                { "date": "2025-01-09", "isPresent": true, "timeIn": "09:00:00", "timeOut": "17:00:00" },
+                { "date": "2025-01-10", "isPresent": false, "timeIn": null, "timeOut": null },
+            ],
+            "attendanceSummary": [{ "year": 2025, "month": 1, "daysAttended": 5 }],
+            "streak": [{ "currentStreak": 3, "maxStreak": 5 }],
+            "projects": [{ "title": "Root" }],
+        })
+    );
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn create_member_normalises_input(pool: PgPool) {
+    let schema = schema(&pool);
+
+    let data = execute(
+        &schema,
+        r#"mutation { createMember(input: {
+            rollNo: "am.en.u4cse22099", name: "Nila", email: "Nila@Example.com", sex: F,
+            year: 1, hostel: "Kaveri", macAddress: "aa-bb-cc-dd-ee-99",
+            discordId: "100000000000000099", groupId: 2
+        }) { memberId rollNo email macAddress status } }"#,
+    )
+    .await;
+
+    assert_eq!(
+        data["createMember"],
+        json!({
+            "memberId": 5,
+            "rollNo": "AM.EN.U4CSE22099",
+            "email": "nila@example.com",
+            "macAddress": "AA:BB:CC:DD:EE:99",
+            "status": "ACTIVE",
+        })
+    );
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn create_member_rejects_invalid_and_duplicate_input(pool: PgPool) {
+    let schema = schema(&pool);
+    let create = |mac: &str, roll_no: &str, year: i32| {
+        format!(
+            r#"mutation {{ createMember(input: {{
+                rollNo: "{}", name: "Nila", email: "nila@example.com", sex: F, year: {},
+                hostel: "Kaveri", macAddress: "{}", discordId: "100000000000000099", groupId: 2
+            }}) {{ memberId }} }}"#,
+            roll_no, year, mac
+        )
+    };
+
+    let error = execute_err(&schema, create("not a mac", "AM.EN.U4CSE22099", 1)).await;
+    assert_eq!(
+        error,
+        json!({ "code": "VALIDATION", "field": "macAddress" })
+    );
+
+    let error = execute_err(&schema, create("AA:BB:CC:DD:EE:99", "AM.EN.U4CSE22099", 5)).await;
+    assert_eq!(error, json!({ "code": "VALIDATION", "field": "year" }));
+
+    let error = execute_err(&schema, create("AA:BB:CC:DD:EE:99", "AM.EN.U4CSE22001", 1)).await;
+    assert_eq!(error, json!({ "code": "CONFLICT", "field": "rollNo" }));
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn rollover_promotes_and_graduates_each_year_once(pool: PgPool) {
+    let schema = schema(&pool);
+    let rollover = |academic_year: i32, dry_run: bool| {
+        as_caller(
+            format!(
+                "mutation {{ rolloverAcademicYear(input: {{ academicYear: {}, dryRun: {} }}) {{
+                    promoted {{ memberId year }} graduated {{ memberId status graduationYear }}
+                }} }}",
+                academic_year, dry_run
+            ),
+            Role::Admin,
+        )
+    };
+
+    let data = execute(&schema, rollover(2025, true)).await;
+    assert_eq!(
+        data["rolloverAcademicYear"]["promoted"]
+            .as_array()
+            .unwrap()
+            .len(),
+        3
+    );
+    let data = execute(&schema, "{ members(year: 3) { memberId } }").await;
+    assert_eq!(data["members"], json!([{ "memberId": 1 }]));
+
+    let data = execute(&schema, rollover(2025, false)).await;
+    assert_eq!(
+        data["rolloverAcademicYear"]["promoted"],
+        json!([
+            { "memberId": 1, "year": 4 },
+            { "memberId": 2, "year": 3 },
+            { "memberId": 3, "year": 2 },
+        ])
+    );
+    assert_eq!(data["rolloverAcademicYear"]["graduated"], json!([]));
+
+    let error = execute_err(&schema, rollover(2025, false)).await;
+    assert_eq!(
+        error,
+        json!({ "code": "CONFLICT", "field": "academicYear" })
+    );
+
+    let data = execute(&schema, rollover(2026, false)).await;
+    assert_eq!(
+        data["rolloverAcademicYear"]["graduated"],
+        json!([{ "memberId": 1, "status": "ALUMNI", "graduationYear": 2026 }])
+    );
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn only_admins_roll_over_the_academic_year(pool: PgPool) {
+    let schema = schema(&pool);
+    let rollover = "mutation { rolloverAcademicYear(input: { academicYear: 2025 }) {
+        promoted { memberId }
+    } }";
+
+    let error = execute_err(&schema, rollover).await;
+    assert_eq!(error, json!({ "code": "UNAUTHENTICATED" }));
+    let error = execute_err(&schema, as_member(rollover, 1)).await;
+    assert_eq!(error, json!({ "code": "FORBIDDEN" }));
+
+    let data = execute(&schema, "{ members(year: 3) { memberId } }").await;
+    assert_eq!(data["members"], json!([{ "memberId": 1 }]));
+}
+
+/// A `bulkImportMembers` request uploading `csv`.
+fn import_request(csv: &str) -> Request {
+    let mut file = tempfile::tempfile().unwrap();
+    file.write_all(csv.as_bytes()).unwrap();
+    file.rewind().unwrap();
+
+    let mut request = Request::new(
+        "mutation($file: Upload!) { bulkImportMembers(file: $file) {
+            totalRows imported { name } errors { row field }
+        } }",
+    )
+    .variables(Variables::from_json(json!({ "file": null })));
+    request.set_upload(
+        "variables.file",
+        UploadValue {
+            filename: "members.csv".to_string(),
+            content_type: Some("text/csv".to_string()),
+            content: file,
+        },
+    );
+    request
+}
+
+const IMPORT_CSV: &str = "roll_no,name,email,sex,year,hostel,mac_address,discord_id,group_id\n\
+    AM.EN.U4CSE24010,Nila,nila@example.com,F,1,Kaveri,AA:BB:CC:DD:EE:10,100000000000000010,1\n\
+    AM.EN.U4CSE24011,Hari,asha@example.com,M,1,Yamuna,AA:BB:CC:DD:EE:11,100000000000000011,1\n";
+
+#[sqlx::test(fixtures("members"))]
+async fn bulk_import_inserts_valid_rows_and_reports_the_rest(pool: PgPool) {
+    let schema = schema(&pool);
+
+    let data = execute(&schema, as_caller(import_request(IMPORT_CSV), Role::Admin)).await;
+    assert_eq!(
+        data["bulkImportMembers"],
+        json!({
+            "totalRows": 2,
+            "imported": [{ "name": "Nila" }],
+            "errors": [{ "row": 3, "field": "email" }],
+        })
+    );
+
+    // Stamped by the club's clock, like members created one at a time.
+    let created_at: NaiveDateTime =
+        sqlx::query_scalar("SELECT created_at FROM Member WHERE name = 'Nila'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(created_at.to_string(), "2025-01-10 09:30:00");
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn only_admins_import_members(pool: PgPool) {
+    let schema = schema(&pool);
+
+    let error = execute_err(&schema, import_request(IMPORT_CSV)).await;
+    assert_eq!(error, json!({ "code": "UNAUTHENTICATED" }));
+    let error = execute_err(&schema, as_member(import_request(IMPORT_CSV), 1)).await;
+    assert_eq!(error, json!({ "code": "FORBIDDEN" }));
+
+    let data = execute(&schema, "{ members(includeAlumni: true) { memberId } }").await;
+    assert_eq!(data["members"].as_array().unwrap().len(), 4);
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn members_update_their_own_profile(pool: PgPool) {
+    let schema = schema(&pool);
+
+    let error = execute_err(&schema, "{ me { memberId } }").await;
+    assert_eq!(error, json!({ "code": "UNAUTHENTICATED" }));
+    let error = execute_err(&schema, as_caller("{ me { memberId } }", Role::Admin)).await;
+    assert_eq!(error, json!({ "code": "FORBIDDEN" }));
+
+    let data = execute(&schema, as_member("{ me { memberId name } }", 2)).await;
+    assert_eq!(data["me"], json!({ "memberId": 2, "name": "Rahul Menon" }));
+
+    let update = |fields: &str| {
+        as_member(
+            format!(
+                "mutation {{ updateMyProfile(input: {{ {} }}) {{
+                    member {{ hostel discordId }} pendingChange {{ macAddress }}
+                }} }}",
+                fields
+            ),
+            2,
+        )
+    };
+    let error = execute_err(&schema, update(r#"discordId: "rahul""#)).await;
+    assert_eq!(error, json!({ "code": "VALIDATION", "field": "discordId" }));
+    let error = execute_err(&schema, update(r#"discordId: "100000000000000001""#)).await;
+    assert_eq!(error, json!({ "code": "CONFLICT", "field": "discordId" }));
+
+    let data = execute(
+        &schema,
+        update(r#"hostel: " Kaveri ", discordId: "100000000000000022""#),
+    )
+    .await;
+    assert_eq!(
+        data["updateMyProfile"],
+        json!({
+            "member": { "hostel": "Kaveri", "discordId": "100000000000000022" },
+            "pendingChange": null,
+        })
+    );
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn mac_address_changes_wait_for_approval(pool: PgPool) {
+    let schema = schema(&pool);
+    let request_mac = |mac: &str| {
+        as_member(
+            format!(
+                r#"mutation {{ updateMyProfile(input: {{ macAddress: "{}" }}) {{
+                    member {{ macAddress }} pendingChange {{ changeId macAddress status }}
+                }} }}"#,
+                mac
+            ),
+            2,
+        )
+    };
+
+    let error = execute_err(&schema, request_mac("AA:BB:CC:DD:EE:01")).await;
+    assert_eq!(error, json!({ "code": "CONFLICT", "field": "macAddress" }));
+
+    execute(&schema, request_mac("aa-bb-cc-dd-ee-20")).await;
+    let data = execute(&schema, request_mac("aa-bb-cc-dd-ee-22")).await;
+    assert_eq!(
+        data["updateMyProfile"],
+        json!({
+            "member": { "macAddress": "AA:BB:CC:DD:EE:02" },
+            "pendingChange": { "changeId": 1, "macAddress": "AA:BB:CC:DD:EE:22", "status": "PENDING" },
+        })
+    );
+
+    let review = |approve: bool| {
+        as_caller(
+            format!(
+                "mutation {{ reviewProfileChange(changeId: 1, approve: {}) {{ status }} }}",
+                approve
+            ),
+            Role::Admin,
+        )
+    };
+    let error = execute_err(
+        &schema,
+        as_member("{ profileChangeRequests { memberId } }", 2),
+    )
+    .await;
+    assert_eq!(error, json!({ "code": "FORBIDDEN" }));
+    let data = execute(
+        &schema,
+        as_caller(
+            "{ profileChangeRequests { memberId macAddress } }",
+            Role::Admin,
+        ),
+    )
+    .await;
+    assert_eq!(
+        data["profileChangeRequests"],
+        json!([{ "memberId": 2, "macAddress": "AA:BB:CC:DD:EE:22" }])
+    );
+
+    execute(&schema, review(true)).await;
+    let data = execute(
+        &schema,
+        as_member("{ me { macAddress pendingProfileChange { changeId } } }", 2),
+    )
+    .await;
+    assert_eq!(
+        data["me"],
+        json!({ "macAddress": "AA:BB:CC:DD:EE:22", "pendingProfileChange": null })
+    );
+
+    let error = execute_err(&schema, review(false)).await;
+    assert_eq!(error, json!({ "code": "NOT_FOUND" }));
+}
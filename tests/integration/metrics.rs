@@ -0,0 +1,42 @@
+use std::io::Write;
+
+use axum::http::StatusCode;
+use root::config::{Config, PersistedQueriesConfig};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+use crate::common::{app_state_with_config, get, post_query, router, send};
+
+const REGISTERED: &str = "query RegisteredRoster { __typename }";
+
+#[sqlx::test]
+async fn only_registered_operations_are_labelled_by_name(pool: PgPool) {
+    let mut manifest = tempfile::NamedTempFile::new().unwrap();
+    let id = hex::encode(Sha256::digest(REGISTERED.as_bytes()));
+    let operations = serde_json::json!({
+        "operations": [{ "id": id, "name": "RegisteredRoster", "body": REGISTERED }],
+    });
+    write!(manifest, "{}", operations).unwrap();
+    let config = Config {
+        persisted_queries: PersistedQueriesConfig {
+            manifest: Some(manifest.path().to_path_buf()),
+            ..PersistedQueriesConfig::default()
+        },
+        ..Config::default()
+    };
+    let router = router(app_state_with_config(&pool, config));
+
+    for query in [REGISTERED, "query MadeUpByAClient { __typename }"] {
+        let (status, body) = send(&router, post_query(query, None)).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+    }
+
+    let (_, metrics) = send(&router, get("/metrics", None)).await;
+    assert!(
+        metrics.contains("operation=\"RegisteredRoster\""),
+        "{}",
+        metrics
+    );
+    assert!(metrics.contains("operation=\"other\""), "{}", metrics);
+    assert!(!metrics.contains("MadeUpByAClient"), "{}", metrics);
+}
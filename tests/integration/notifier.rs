@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{NaiveDate, TimeDelta};
+use root::clock::FixedClock;
+use root::config::{DiscordConfig, NotifierConfig};
+use root::notifier::{DiscordWebhookSink, MockSink, Notification, NotificationSink, Notifier};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::common::{execute, now, schema_with_clock};
+
+/// Two days after the activity fixture's last attendance records, so the digests cover the 11th.
+fn today() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2025, 1, 12).unwrap()
+}
+
+fn notifier(pool: &PgPool, sink: Arc<MockSink>) -> Notifier {
+    let config = NotifierConfig {
+        absent_days: 2,
+        ..NotifierConfig::default()
+    };
+    Notifier::new(Arc::new(pool.clone()), sink, &config)
+}
+
+/// Records 11 January's attendance: Asha and Devika stay away, Rahul comes in.
+async fn record_yesterday(pool: &PgPool) {
+    sqlx::query(
+        "INSERT INTO Attendance (member_id, date, is_present, time_in, time_out) VALUES
+            (1, '2025-01-11', FALSE, NULL, NULL),
+            (2, '2025-01-11', TRUE, '10:00', '16:00'),
+            (3, '2025-01-11', FALSE, NULL, NULL)",
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn digests_list_absentees_streaks_at_risk_and_new_members(pool: PgPool) {
+    record_yesterday(&pool).await;
+    let clock = Arc::new(FixedClock::new(now() + TimeDelta::days(1)));
+    let schema = schema_with_clock(&pool, clock);
+    execute(
+        &schema,
+        r#"mutation { createMember(input: {
+            rollNo: "AM.EN.U4CSE24099", name: "Nila", email: "nila@example.com", sex: F,
+            year: 1, hostel: "Kaveri", macAddress: "AA:BB:CC:DD:EE:99",
+            discordId: "100000000000000099", groupId: 2
+        }) { memberId } }"#,
+    )
+    .await;
+
+    let sink = Arc::new(MockSink::default());
+    assert!(notifier(&pool, sink.clone()).send_digests(today()).await);
+
+    // Devika has only missed one day, and Rahul's streak is already broken.
+    assert_eq!(
+        sink.sent(),
+        vec![
+            Notification {
+                group_id: 1,
+                group_name: "Web".to_string(),
+                content: "**Web**: daily digest for 2025-01-11\n\
+                    :warning: <@100000000000000001> hasn't been to the lab in 2 days\n\
+                    :fire: <@100000000000000001>'s 3 day status update streak breaks without an update today"
+                    .to_string(),
+            },
+            Notification {
+                group_id: 2,
+                group_name: "Systems".to_string(),
+                content: "**Systems**: daily digest for 2025-01-11\n\
+                    :wave: Welcome <@100000000000000099> (year 1) to the group!"
+                    .to_string(),
+            },
+        ]
+    );
+}
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn groups_with_nothing_to_report_are_skipped(pool: PgPool) {
+    record_yesterday(&pool).await;
+    sqlx::query(
+        "UPDATE Attendance SET is_present = TRUE, time_in = '09:00', time_out = '17:00' WHERE member_id = 1 AND date = '2025-01-11'",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let clock = Arc::new(FixedClock::new(now() + TimeDelta::days(1)));
+    let schema = schema_with_clock(&pool, clock);
+    execute(
+        &schema,
+        "mutation { incrementStreak(input: { memberId: 1 }) { currentStreak } }",
+    )
+    .await;
+
+    let sink = Arc::new(MockSink::default());
+    assert!(notifier(&pool, sink.clone()).send_digests(today()).await);
+    assert_eq!(sink.sent(), vec![]);
+}
+
+#[tokio::test]
+async fn discord_sink_posts_to_each_groups_webhook_in_chunks() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(204))
+        .mount(&server)
+        .await;
+
+    let config = NotifierConfig {
+        discord: DiscordConfig {
+            default_webhook_url: Some(format!("{}/default", server.uri())),
+            group_webhook_urls: HashMap::from([("1".to_string(), format!("{}/web", server.uri()))]),
+        },
+        ..NotifierConfig::default()
+    };
+    let sink = DiscordWebhookSink::new(&config);
+
+    let line = "x".repeat(1500);
+    let notification = |group_id: i32, content: String| Notification {
+        group_id,
+        group_name: String::new(),
+        content,
+    };
+    sink.send(&notification(1, format!("{}\n{}", line, line)))
+        .await
+        .unwrap();
+    sink.send(&notification(2, "Short".to_string()))
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let sent: Vec<(String, Value)> = requests
+        .iter()
+        .map(|request| (request.url.path().to_string(), request.body_json().unwrap()))
+        .collect();
+    assert_eq!(sent.len(), 3);
+    assert_eq!(sent[0].0, "/web");
+    assert_eq!(sent[0].1["content"], json!(line));
+    assert_eq!(sent[1].0, "/web");
+    assert_eq!(
+        sent[2],
+        (
+            "/default".to_string(),
+            json!({ "content": "Short", "allowed_mentions": { "parse": ["users"] } })
+        )
+    );
+
+    let failing = MockServer::start().await;
+    Mock::given(path("/web"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&failing)
+        .await;
+    let config = NotifierConfig {
+        discord: DiscordConfig {
+            default_webhook_url: Some(format!("{}/web", failing.uri())),
+            ..DiscordConfig::default()
+        },
+        ..NotifierConfig::default()
+    };
+    let error = DiscordWebhookSink::new(&config)
+        .send(&notification(1, "Short".to_string()))
+        .await
+        .unwrap_err();
+    assert!(error.contains("404"), "{}", error);
+}
@@ -0,0 +1,168 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::HeaderMap};
+use root::auth::Role;
+use root::clock::{ClubClock, FixedClock};
+use root::config::AttendancePolicy;
+use root::presence::{ingest_scans, record_scans, Scan};
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::common::{app_state, as_caller, execute, now, schema, sign_scans};
+
+fn club_clock() -> ClubClock {
+    ClubClock::new(Arc::new(FixedClock::new(now())), chrono_tz::Asia::Kolkata)
+}
+
+fn scan(mac_address: &str, seen_at: &str) -> Scan {
+    Scan {
+        mac_address: mac_address.to_string(),
+        seen_at: seen_at.parse().unwrap(),
+    }
+}
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn scans_mark_members_and_report_each_entry(pool: PgPool) {
+    let scans = [
+        scan("aa-bb-cc-dd-ee-01", "2025-01-10T03:45:00Z"),
+        scan("AA:BB:CC:DD:EE:01", "2025-01-10T03:50:00Z"),
+        scan("AA:BB:CC:DD:EE:02", "2025-01-10T03:55:00Z"),
+        scan("02:00:00:00:00:99", "2025-01-10T03:55:00Z"),
+        scan("not a mac", "2025-01-10T03:55:00Z"),
+        scan("AA:BB:CC:DD:EE:03", "2025-01-11T03:00:00Z"),
+        scan("AA:BB:CC:DD:EE:03", "2025-01-09T20:00:00Z"),
+        scan("AA:BB:CC:DD:EE:04", "2025-01-10T03:00:00Z"),
+    ];
+
+    let results = record_scans(&pool, &club_clock(), &AttendancePolicy::default(), &scans)
+        .await
+        .unwrap();
+    let results = serde_json::to_value(results).unwrap();
+    let summary: Vec<_> = results
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|result| {
+            json!([
+                result["status"],
+                result["memberId"],
+                result["timeIn"],
+                result["timeOut"]
+            ])
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            json!(["MARKED", 1, "09:15:00", "09:15:00"]),
+            json!(["MARKED", 1, "09:15:00", "09:20:00"]),
+            json!(["MARKED", 2, "09:25:00", "16:00:00"]),
+            json!(["UNKNOWN_DEVICE", null, null, null]),
+            json!(["INVALID", null, null, null]),
+            json!(["INVALID", null, null, null]),
+            json!(["INVALID", null, null, null]),
+            json!(["NO_RECORD", 4, null, null]),
+        ]
+    );
+    assert_eq!(results[0]["macAddress"], "AA:BB:CC:DD:EE:01");
+    assert_eq!(
+        results[6]["message"],
+        "2025-01-09 20:00:00 UTC is more than 6 hours ago"
+    );
+    assert_eq!(results[7]["date"], "2025-01-10");
+
+    let data = execute(
+        &schema(&pool),
+        as_caller(
+            "{ members(year: 3) { attendance { date isPresent timeIn timeOut } devices { lastSeenAt } } }",
+            Role::Admin,
+        ),
+    )
+    .await;
+    assert_eq!(
+        data["members"][0],
+        json!({
+            "attendance": [
+                { "date": "2025-01-09", "isPresent": true, "timeIn": "09:00:00", "timeOut": "17:00:00" },
+                { "date": "2025-01-10", "isPresent": true, "timeIn": "09:15:00", "timeOut": "09:20:00" },
+            ],
+            "devices": [{ "lastSeenAt": "2025-01-10T03:50:00+00:00" }],
+        })
+    );
+}
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn batches_must_be_signed_and_can_be_resent(pool: PgPool) {
+    let state = app_state(&pool);
+    let body = json!({
+        "scanner": "lab-1",
+        "sentAt": "2025-01-10T04:00:00Z",
+        "scans": [{ "macAddress": "AA:BB:CC:DD:EE:01", "seenAt": "2025-01-10T03:45:00Z" }],
+    })
+    .to_string();
+    let signed = |signature: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-root-signature", signature.parse().unwrap());
+        headers
+    };
+
+    let error = ingest_scans(State(state.clone()), HeaderMap::new(), body.clone())
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), "UNAUTHENTICATED");
+    let error = ingest_scans(
+        State(state.clone()),
+        signed(&sign_scans("{}")),
+        body.clone(),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(error.code(), "UNAUTHENTICATED");
+
+    for _ in 0..2 {
+        let response = ingest_scans(
+            State(state.clone()),
+            signed(&sign_scans(&body)),
+            body.clone(),
+        )
+        .await
+        .unwrap();
+        let results = serde_json::to_value(&response.0.results).unwrap();
+        assert_eq!(
+            results,
+            json!([{
+                "macAddress": "AA:BB:CC:DD:EE:01",
+                "status": "MARKED",
+                "memberId": 1,
+                "date": "2025-01-10",
+                "timeIn": "09:15:00",
+                "timeOut": "09:15:00",
+                "message": null,
+            }])
+        );
+    }
+
+    let stale = json!({
+        "sentAt": "2025-01-10T03:30:00Z",
+        "scans": [{ "macAddress": "AA:BB:CC:DD:EE:01", "seenAt": "2025-01-10T03:25:00Z" }],
+    })
+    .to_string();
+    let error = ingest_scans(State(state.clone()), signed(&sign_scans(&stale)), stale)
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), "VALIDATION");
+
+    let oversized = json!({
+        "sentAt": "2025-01-10T04:00:00Z",
+        "scans": vec![json!({ "macAddress": "AA:BB:CC:DD:EE:01", "seenAt": "2025-01-10T03:45:00Z" }); 1001],
+    })
+    .to_string();
+    let error = ingest_scans(
+        State(state),
+        signed(&sign_scans(&oversized)),
+        oversized.clone(),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(error.code(), "VALIDATION");
+}
@@ -0,0 +1,40 @@
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::common::{execute, execute_err, schema};
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn set_project_adds_to_the_project_list(pool: PgPool) {
+    let schema = schema(&pool);
+
+    let data = execute(
+        &schema,
+        r#"mutation { setProject(input: { memberId: 2, title: "Presense" }) { memberId title } }"#,
+    )
+    .await;
+    assert_eq!(
+        data["setProject"],
+        json!({ "memberId": 2, "title": "Presense" })
+    );
+
+    let data = execute(&schema, "{ projects { memberId title } }").await;
+    assert_eq!(
+        data["projects"],
+        json!([
+            { "memberId": 1, "title": "Root" },
+            { "memberId": 2, "title": "Presense" },
+        ])
+    );
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn set_project_rejects_unknown_members(pool: PgPool) {
+    let schema = schema(&pool);
+
+    let error = execute_err(
+        &schema,
+        r#"mutation { setProject(input: { memberId: 99, title: "Ghost" }) { projectId } }"#,
+    )
+    .await;
+    assert_eq!(error["code"], "VALIDATION");
+}
@@ -0,0 +1,85 @@
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::common::{execute, execute_err, schema};
+
+fn increment(member_id: i32) -> String {
+    format!(
+        "mutation {{ incrementStreak(input: {{ memberId: {} }}) {{ currentStreak maxStreak }} }}",
+        member_id
+    )
+}
+
+fn reset(member_id: i32) -> String {
+    format!(
+        "mutation {{ resetStreak(input: {{ memberId: {} }}) {{ currentStreak maxStreak }} }}",
+        member_id
+    )
+}
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn streaks_are_listed_by_member(pool: PgPool) {
+    let schema = schema(&pool);
+
+    let data = execute(
+        &schema,
+        "{ streak(memberId: 1) { currentStreak maxStreak } }",
+    )
+    .await;
+    assert_eq!(
+        data["streak"],
+        json!({ "currentStreak": 3, "maxStreak": 5 })
+    );
+
+    let data = execute(&schema, "{ streaks { memberId } }").await;
+    assert_eq!(data["streaks"].as_array().unwrap().len(), 2);
+
+    let error = execute_err(&schema, "{ streak(memberId: 3) { currentStreak } }").await;
+    assert_eq!(error, json!({ "code": "NOT_FOUND" }));
+}
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn increment_streak_starts_extends_and_restarts(pool: PgPool) {
+    let schema = schema(&pool);
+
+    let data = execute(&schema, increment(3)).await;
+    assert_eq!(
+        data["incrementStreak"],
+        json!({ "currentStreak": 1, "maxStreak": 1 })
+    );
+
+    let data = execute(&schema, increment(1)).await;
+    assert_eq!(
+        data["incrementStreak"],
+        json!({ "currentStreak": 4, "maxStreak": 5 })
+    );
+
+    let data = execute(&schema, increment(2)).await;
+    assert_eq!(
+        data["incrementStreak"],
+        json!({ "currentStreak": 1, "maxStreak": 4 })
+    );
+}
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn reset_streak_ends_streaks_and_counts_missed_days(pool: PgPool) {
+    let schema = schema(&pool);
+
+    let data = execute(&schema, reset(1)).await;
+    assert_eq!(
+        data["resetStreak"],
+        json!({ "currentStreak": 0, "maxStreak": 5 })
+    );
+
+    let data = execute(&schema, reset(2)).await;
+    assert_eq!(
+        data["resetStreak"],
+        json!({ "currentStreak": -3, "maxStreak": 4 })
+    );
+
+    let data = execute(&schema, reset(3)).await;
+    assert_eq!(
+        data["resetStreak"],
+        json!({ "currentStreak": 0, "maxStreak": 0 })
+    );
+}
@@ -0,0 +1,36 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use root::supervisor::Supervisor;
+
+/// Sets its flag when dropped, which happens once the task holding it is aborted.
+struct Dropped(Arc<AtomicBool>);
+
+impl Drop for Dropped {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+#[tokio::test]
+async fn tasks_that_ignore_shutdown_are_aborted() {
+    let supervisor = Supervisor::default();
+    let dropped = Arc::new(AtomicBool::new(false));
+    let flag = dropped.clone();
+    supervisor.spawn("stubborn", move |_shutdown| {
+        let guard = Dropped(flag.clone());
+        async move {
+            let _guard = guard;
+            std::future::pending::<()>().await;
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    supervisor.shutdown();
+    supervisor.join(Duration::from_millis(10)).await;
+    // Aborted tasks are dropped the next time the runtime polls them.
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    assert!(dropped.load(Ordering::SeqCst));
+}
@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use opentelemetry::{trace::SpanId, Value};
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider, SpanData};
+use root::clock::{ClubClock, FixedClock};
+use root::config::{Config, TelemetryConfig};
+use sqlx::PgPool;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+use crate::common::{execute, now};
+
+fn attribute<'a>(span: &'a SpanData, key: &str) -> Option<&'a Value> {
+    span.attributes
+        .iter()
+        .find(|attribute| attribute.key.as_str() == key)
+        .map(|attribute| &attribute.value)
+}
+
+/// The first span called `name` that `matches`.
+fn find<'a>(
+    spans: &'a [SpanData],
+    name: &str,
+    matches: impl Fn(&SpanData) -> bool,
+) -> &'a SpanData {
+    spans
+        .iter()
+        .find(|span| span.name == name && matches(span))
+        .unwrap_or_else(|| panic!("No matching '{}' span among {:?}", name, spans))
+}
+
+fn id(span: &SpanData) -> SpanId {
+    span.span_context.span_id()
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn queries_are_traced_down_to_their_sql(pool: PgPool) {
+    let exporter = InMemorySpanExporter::default();
+    let provider = SdkTracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    let _subscriber = tracing_subscriber::registry()
+        .with(root::telemetry::layer(&provider))
+        .set_default();
+
+    let config = Config {
+        telemetry: TelemetryConfig {
+            enabled: true,
+            ..TelemetryConfig::default()
+        },
+        ..Config::default()
+    };
+    let clock = ClubClock::new(Arc::new(FixedClock::new(now())), config.attendance.timezone);
+    let schema = root::build_graphql_schema(Arc::new(pool), &config, clock);
+    execute(&schema, "{ members { memberId } }").await;
+
+    let spans = exporter.get_finished_spans().unwrap();
+    let request = find(&spans, "request", |_| true);
+    let operation = find(&spans, "execute", |_| true);
+    let resolver = find(&spans, "field", |span| {
+        attribute(span, "path") == Some(&Value::from("members"))
+    });
+    let query = find(&spans, "sqlx.query", |span| {
+        attribute(span, "db.statement").is_some_and(|sql| sql.as_str().contains("FROM Member"))
+    });
+
+    assert_eq!(request.parent_span_id, SpanId::INVALID);
+    assert_eq!(operation.parent_span_id, id(request));
+    assert_eq!(resolver.parent_span_id, id(operation));
+    assert_eq!(query.parent_span_id, id(resolver));
+
+    let trace_id = request.span_context.trace_id();
+    assert!(spans
+        .iter()
+        .all(|span| span.span_context.trace_id() == trace_id));
+}
@@ -0,0 +1,323 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_graphql::Request;
+use chrono::{NaiveDate, TimeDelta};
+use chrono_tz::Asia::Kolkata;
+use root::auth::Role;
+use root::clock::{ClubClock, FixedClock};
+use root::config::WebhooksConfig;
+use root::daily_task::execute_daily_task;
+use root::webhooks::{signature, WebhookDispatcher};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::common::{as_caller, execute, execute_err, now, schema, schema_with_clock, RootSchema};
+
+fn dispatcher(pool: &PgPool, clock: Arc<FixedClock>) -> WebhookDispatcher {
+    let config = WebhooksConfig {
+        max_attempts: 3,
+        initial_backoff_secs: 30,
+        ..WebhooksConfig::default()
+    };
+    WebhookDispatcher::new(
+        Arc::new(pool.clone()),
+        ClubClock::new(clock, Kolkata),
+        &config,
+    )
+}
+
+/// Registers a webhook for `events` at `url` and returns its id and secret.
+async fn register(schema: &RootSchema, url: &str, events: &str) -> (i64, String) {
+    let data = execute(
+        schema,
+        as_caller(
+            format!(
+                r#"mutation {{ registerWebhook(input: {{ url: "{}", events: [{}] }}) {{
+                    webhook {{ webhookId }} secret
+                }} }}"#,
+                url, events
+            ),
+            Role::Admin,
+        ),
+    )
+    .await;
+    let registered = &data["registerWebhook"];
+    (
+        registered["webhook"]["webhookId"].as_i64().unwrap(),
+        registered["secret"].as_str().unwrap().to_string(),
+    )
+}
+
+async fn deliveries(schema: &RootSchema, webhook_id: i64) -> Value {
+    let data = execute(
+        schema,
+        as_caller(
+            format!(
+                "{{ webhookDeliveries(webhookId: {}) {{
+                    deliveryId eventType status attempts nextAttemptAt lastResponseStatus
+                    attemptLog {{ responseStatus error }}
+                }} }}",
+                webhook_id
+            ),
+            Role::Admin,
+        ),
+    )
+    .await;
+    data["webhookDeliveries"].clone()
+}
+
+const CREATE_MEMBER: &str = r#"mutation { createMember(input: {
+    rollNo: "AM.EN.U4CSE24099", name: "Nila", email: "nila@example.com", sex: F, year: 1,
+    hostel: "Kaveri", macAddress: "AA:BB:CC:DD:EE:99", discordId: "100000000000000099", groupId: 2
+}) { memberId } }"#;
+
+#[sqlx::test(fixtures("members"))]
+async fn webhooks_can_only_be_managed_by_admins(pool: PgPool) {
+    let schema = schema(&pool);
+    let register = r#"mutation { registerWebhook(input: {
+        url: "https://amd.amfoss.in/hooks", events: [MEMBER_CREATED]
+    }) { secret } }"#;
+
+    let error = execute_err(&schema, register).await;
+    assert_eq!(error, json!({ "code": "UNAUTHENTICATED" }));
+
+    let error = execute_err(&schema, as_caller(register, Role::Member)).await;
+    assert_eq!(error, json!({ "code": "FORBIDDEN" }));
+
+    let error = execute_err(&schema, Request::new("{ webhooks { url } }")).await;
+    assert_eq!(error, json!({ "code": "UNAUTHENTICATED" }));
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn register_webhook_validates_its_input(pool: PgPool) {
+    let schema = schema(&pool);
+    let register = |url: &str, events: &str| {
+        as_caller(
+            format!(
+                r#"mutation {{ registerWebhook(input: {{ url: "{}", events: [{}] }}) {{ secret }} }}"#,
+                url, events
+            ),
+            Role::Admin,
+        )
+    };
+
+    let error = execute_err(&schema, register("ftp://amd.amfoss.in", "MEMBER_CREATED")).await;
+    assert_eq!(error, json!({ "code": "VALIDATION", "field": "url" }));
+
+    let error = execute_err(&schema, register("https://amd.amfoss.in/hooks", "")).await;
+    assert_eq!(error, json!({ "code": "VALIDATION", "field": "events" }));
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn subscribed_events_are_delivered_signed(pool: PgPool) {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/hooks"))
+        .respond_with(ResponseTemplate::new(204))
+        .mount(&server)
+        .await;
+    let clock = Arc::new(FixedClock::new(now()));
+    let schema = schema_with_clock(&pool, clock.clone());
+    let dispatcher = dispatcher(&pool, clock);
+
+    let url = format!("{}/hooks", server.uri());
+    let (webhook_id, secret) = register(&schema, &url, "MEMBER_CREATED").await;
+    // Nobody subscribes to these, so they aren't recorded at all.
+    let (unrelated_id, _) = register(&schema, &url, "STREAK_RESET").await;
+
+    execute(&schema, CREATE_MEMBER).await;
+    assert_eq!(dispatcher.deliver_due().await.unwrap(), 1);
+    assert_eq!(dispatcher.deliver_due().await.unwrap(), 0);
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1);
+    let request = &requests[0];
+    let header = |name: &str| request.headers[name].to_str().unwrap().to_string();
+    assert_eq!(header("x-root-event"), "member.created");
+    assert_eq!(header("x-root-timestamp"), now().timestamp().to_string());
+    assert_eq!(
+        header("x-root-signature"),
+        signature(&secret, now().timestamp(), &request.body)
+    );
+
+    let body: Value = request.body_json().unwrap();
+    assert_eq!(body["type"], "member.created");
+    assert_eq!(body["data"]["member_id"], 5);
+    assert_eq!(body["data"]["name"], "Nila");
+
+    assert_eq!(
+        deliveries(&schema, webhook_id).await,
+        json!([{
+            "deliveryId": header("x-root-delivery").parse::<i64>().unwrap(),
+            "eventType": "MEMBER_CREATED",
+            "status": "DELIVERED",
+            "attempts": 1,
+            "nextAttemptAt": null,
+            "lastResponseStatus": 204,
+            "attemptLog": [{ "responseStatus": 204, "error": null }],
+        }])
+    );
+    assert_eq!(deliveries(&schema, unrelated_id).await, json!([]));
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn failed_deliveries_back_off_then_give_up(pool: PgPool) {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&server)
+        .await;
+    let clock = Arc::new(FixedClock::new(now()));
+    let schema = schema_with_clock(&pool, clock.clone());
+    let dispatcher = dispatcher(&pool, clock.clone());
+
+    let (webhook_id, _) = register(&schema, &server.uri(), "MEMBER_CREATED").await;
+    execute(&schema, CREATE_MEMBER).await;
+
+    assert_eq!(dispatcher.deliver_due().await.unwrap(), 1);
+    let delivery = &deliveries(&schema, webhook_id).await[0];
+    assert_eq!(delivery["status"], "PENDING");
+    assert_eq!(delivery["lastResponseStatus"], 503);
+    assert_eq!(
+        delivery["nextAttemptAt"],
+        (now() + TimeDelta::seconds(30)).to_rfc3339()
+    );
+
+    // Not due again until the backoff has passed, which then doubles.
+    clock.advance(TimeDelta::seconds(29));
+    assert_eq!(dispatcher.deliver_due().await.unwrap(), 0);
+    clock.advance(TimeDelta::seconds(1));
+    assert_eq!(dispatcher.deliver_due().await.unwrap(), 1);
+    clock.advance(TimeDelta::seconds(59));
+    assert_eq!(dispatcher.deliver_due().await.unwrap(), 0);
+    clock.advance(TimeDelta::seconds(1));
+    assert_eq!(dispatcher.deliver_due().await.unwrap(), 1);
+
+    let delivery = deliveries(&schema, webhook_id).await[0].clone();
+    assert_eq!(delivery["status"], "FAILED");
+    assert_eq!(delivery["attempts"], 3);
+    assert_eq!(delivery["attemptLog"].as_array().unwrap().len(), 3);
+    clock.advance(TimeDelta::days(1));
+    assert_eq!(dispatcher.deliver_due().await.unwrap(), 0);
+
+    // Once the endpoint is fixed, an admin can have it tried again.
+    server.reset().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    execute(
+        &schema,
+        as_caller(
+            format!(
+                "mutation {{ retryWebhookDelivery(deliveryId: {}) {{ status }} }}",
+                delivery["deliveryId"]
+            ),
+            Role::Admin,
+        ),
+    )
+    .await;
+    assert_eq!(dispatcher.deliver_due().await.unwrap(), 1);
+    assert_eq!(
+        deliveries(&schema, webhook_id).await[0]["status"],
+        "DELIVERED"
+    );
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn unreachable_endpoints_are_retried(pool: PgPool) {
+    let clock = Arc::new(FixedClock::new(now()));
+    let schema = schema_with_clock(&pool, clock.clone());
+    let dispatcher = dispatcher(&pool, clock);
+
+    // Nothing listens on the discard port.
+    let (webhook_id, _) = register(&schema, "http://127.0.0.1:9/hooks", "MEMBER_CREATED").await;
+    execute(&schema, CREATE_MEMBER).await;
+
+    assert_eq!(dispatcher.deliver_due().await.unwrap(), 1);
+    let delivery = &deliveries(&schema, webhook_id).await[0];
+    assert_eq!(delivery["status"], "PENDING");
+    assert_eq!(delivery["lastResponseStatus"], Value::Null);
+    assert!(delivery["attemptLog"][0]["error"].is_string());
+}
+
+#[sqlx::test(fixtures("members"))]
+async fn deliveries_are_not_locked_while_endpoints_respond(pool: PgPool) {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(204).set_delay(Duration::from_millis(500)))
+        .mount(&server)
+        .await;
+    let clock = Arc::new(FixedClock::new(now()));
+    let schema = schema_with_clock(&pool, clock.clone());
+    let dispatcher = dispatcher(&pool, clock);
+
+    let (webhook_id, _) = register(&schema, &server.uri(), "MEMBER_CREATED").await;
+    execute(&schema, CREATE_MEMBER).await;
+
+    let while_delivering = async {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        // Another instance finds nothing to claim, without waiting on a lock.
+        assert_eq!(dispatcher.deliver_due().await.unwrap(), 0);
+        sqlx::query("SELECT 1 FROM WebhookDelivery FOR UPDATE NOWAIT")
+            .execute(&pool)
+            .await
+            .unwrap();
+    };
+    let (delivered, ()) = tokio::join!(dispatcher.deliver_due(), while_delivering);
+
+    assert_eq!(delivered.unwrap(), 1);
+    assert_eq!(
+        deliveries(&schema, webhook_id).await[0]["status"],
+        "DELIVERED"
+    );
+}
+
+#[sqlx::test(fixtures("members", "activity"))]
+async fn deactivated_webhooks_get_no_new_events(pool: PgPool) {
+    let schema = schema(&pool);
+    let (webhook_id, _) = register(
+        &schema,
+        "https://amd.amfoss.in/hooks",
+        "MEMBER_CREATED, DAILY_TASK_COMPLETED",
+    )
+    .await;
+
+    execute_daily_task(
+        Arc::new(pool.clone()),
+        NaiveDate::from_ymd_opt(2025, 1, 11).unwrap(),
+    )
+    .await;
+    let event: Value = sqlx::query_scalar("SELECT payload FROM WebhookEvent")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(
+        event,
+        json!({ "date": "2025-01-11", "members": 3, "succeeded": true })
+    );
+
+    execute(
+        &schema,
+        as_caller(
+            format!(
+                "mutation {{ updateWebhook(input: {{ webhookId: {}, active: false }}) {{ active }} }}",
+                webhook_id
+            ),
+            Role::Admin,
+        ),
+    )
+    .await;
+    execute(&schema, CREATE_MEMBER).await;
+    assert_eq!(
+        deliveries(&schema, webhook_id)
+            .await
+            .as_array()
+            .unwrap()
+            .len(),
+        1
+    );
+}